@@ -0,0 +1,100 @@
+use crate::Error;
+use anyhow::format_err;
+use fehler::{throw, throws};
+
+/// Resolves an ed-style line address (`N`, `.`, `$`, `+N`, `-N`, `.+N`) to a
+/// one-based line number, where 0 addresses the position before the first
+/// line.
+#[throws]
+pub fn parse(address: &str, current: usize, last: usize) -> usize {
+    let (base, offset) = match address.find(['+', '-']) {
+        Some(0) => (current, address),
+        Some(i) => (base(&address[..i], current, last)?, &address[i..]),
+        None => (base(address, current, last)?, ""),
+    };
+    let line = if offset.is_empty() {
+        base as isize
+    } else {
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let digits = &offset[1..];
+        let n = if digits.is_empty() {
+            1
+        } else {
+            digits
+                .parse::<isize>()
+                .map_err(|_| format_err!("invalid address '{}'", address))?
+        };
+        base as isize + sign * n
+    };
+    if line < 0 || line as usize > last {
+        throw!(format_err!(
+            "address '{}' is out of range (1-{})",
+            address,
+            last
+        ));
+    }
+    line as usize
+}
+
+/// Resolves `A,B` or a single address to an inclusive range of one-based lines.
+#[throws]
+pub fn parse_range(range: &str, current: usize, last: usize) -> (usize, usize) {
+    let (start, end) = match range.find(',') {
+        Some(i) => (
+            parse(&range[..i], current, last)?,
+            parse(&range[i + 1..], current, last)?,
+        ),
+        None => {
+            let line = parse(range, current, last)?;
+            (line, line)
+        }
+    };
+    if start == 0 || start > end {
+        throw!(format_err!("invalid range '{}'", range));
+    }
+    (start, end)
+}
+
+#[throws]
+fn base(address: &str, current: usize, last: usize) -> usize {
+    match address {
+        "." => current,
+        "$" => last,
+        _ => address
+            .parse()
+            .map_err(|_| format_err!("invalid address '{}'", address))?,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_addresses() {
+        assert_eq!(parse("5", 3, 10).unwrap(), 5);
+        assert_eq!(parse(".", 3, 10).unwrap(), 3);
+        assert_eq!(parse("$", 3, 10).unwrap(), 10);
+        assert_eq!(parse("+2", 3, 10).unwrap(), 5);
+        assert_eq!(parse("-", 3, 10).unwrap(), 2);
+        assert_eq!(parse(".+3", 3, 10).unwrap(), 6);
+        assert_eq!(parse("$-1", 3, 10).unwrap(), 9);
+        assert_eq!(parse("0", 3, 10).unwrap(), 0);
+        assert_eq!(parse_range("2,4", 3, 10).unwrap(), (2, 4));
+        assert_eq!(parse_range(".", 3, 10).unwrap(), (3, 3));
+        assert_eq!(parse_range("1,$", 3, 10).unwrap(), (1, 10));
+    }
+
+    #[test]
+    fn rejects_bad_addresses() {
+        let error = |address: &str| parse(address, 3, 10).unwrap_err().to_string();
+        assert_eq!(error("11"), "address '11' is out of range (1-10)");
+        assert_eq!(error("-4"), "address '-4' is out of range (1-10)");
+        assert_eq!(error("x"), "invalid address 'x'");
+        assert_eq!(error("+x"), "invalid address '+x'");
+        let error = |range: &str| parse_range(range, 3, 10).unwrap_err().to_string();
+        assert_eq!(error("4,2"), "invalid range '4,2'");
+        assert_eq!(error("0"), "invalid range '0'");
+        assert_eq!(error("1,x"), "invalid address 'x'");
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{regex::Regex, unicode};
+
+/// Aligns the columns of `lines`, split on `separator` or on whitespace.
+pub fn table(lines: &[String], separator: Option<&str>) -> Vec<String> {
+    let rows = lines
+        .iter()
+        .map(|line| {
+            let indent_len = line.len() - line.trim_start().len();
+            let cells: Vec<&str> = match separator {
+                Some(separator) => line[indent_len..].split(separator).map(str::trim).collect(),
+                None => line.split_whitespace().collect(),
+            };
+            (&line[..indent_len], cells)
+        })
+        .collect::<Vec<_>>();
+    let mut widths = Vec::new();
+    for (_, cells) in &rows {
+        for (i, cell) in cells.iter().enumerate() {
+            let width = unicode::str_width(cell);
+            match widths.get_mut(i) {
+                Some(max) if *max < width => *max = width,
+                Some(_) => {}
+                None => widths.push(width),
+            }
+        }
+    }
+    rows.into_iter()
+        .map(|(indent, cells)| {
+            let mut line = String::from(indent);
+            let last = cells.len().saturating_sub(1);
+            for (i, cell) in cells.into_iter().enumerate() {
+                match separator {
+                    Some(separator) if i > 0 => {
+                        if i > 1 || !line.trim_start().is_empty() {
+                            line.push(' ');
+                        }
+                        line.push_str(separator);
+                        line.push(' ');
+                    }
+                    None if i > 0 => line.push(' '),
+                    _ => {}
+                }
+                line.push_str(cell);
+                if i < last && !(i == 0 && cell.is_empty()) {
+                    for _ in unicode::str_width(cell)..widths[i] {
+                        line.push(' ');
+                    }
+                }
+            }
+            line.truncate(line.trim_end().len());
+            line
+        })
+        .collect()
+}
+
+/// Pads `lines` so the first match of `regex` starts at the same display
+/// column in each; lines without a match are left alone.
+pub fn on_regex(lines: &[String], regex: &Regex) -> Vec<String> {
+    let starts = lines
+        .iter()
+        .map(|line| {
+            regex
+                .find_at(line, 0)
+                .map(|captures| captures.range().start)
+        })
+        .collect::<Vec<_>>();
+    let column = lines
+        .iter()
+        .zip(&starts)
+        .filter_map(|(line, start)| Some(unicode::str_width(&line[..(*start)?])))
+        .max()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .zip(starts)
+        .map(|(line, start)| match start {
+            Some(start) => {
+                let padding = column - unicode::str_width(&line[..start]);
+                format!(
+                    "{}{}{}",
+                    &line[..start],
+                    " ".repeat(padding),
+                    &line[start..]
+                )
+            }
+            None => line.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn aligns_tables() {
+        assert_eq!(
+            table(&lines(&["x 1 foo", "  long 22 b"]), None),
+            lines(&["x    1  foo", "  long 22 b"])
+        );
+        assert_eq!(
+            table(&lines(&["| a | bb |", "|ccc|d|"]), Some("|")),
+            lines(&["| a   | bb |", "| ccc | d  |"])
+        );
+        assert_eq!(
+            table(&lines(&["日本 x", "a y"]), None),
+            lines(&["日本 x", "a    y"])
+        );
+        assert!(table(&[], None).is_empty());
+    }
+
+    #[test]
+    fn aligns_on_regex() {
+        let regex = Regex::new("=").unwrap();
+        assert_eq!(
+            on_regex(&lines(&["a = 1", "long = 2", "none"]), &regex),
+            lines(&["a    = 1", "long = 2", "none"])
+        );
+        assert_eq!(
+            on_regex(&lines(&["日本 = 1", "a = 2"]), &regex),
+            lines(&["日本 = 1", "a    = 2"])
+        );
+        let regex = Regex::new("x").unwrap();
+        assert_eq!(on_regex(&lines(&["a", "b"]), &regex), lines(&["a", "b"]));
+    }
+}
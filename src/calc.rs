@@ -0,0 +1,226 @@
+use crate::Error;
+use anyhow::format_err;
+use fehler::{throw, throws};
+use std::{f64::consts, iter::Peekable, str::Chars};
+
+#[throws]
+pub fn eval(expr: &str) -> f64 {
+    let mut parser = Parser {
+        chars: expr.chars().peekable(),
+    };
+    let value = parser.expr()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.chars.next() {
+        throw!(format_err!("unexpected '{}' in expression", c));
+    }
+    value
+}
+
+pub fn format(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    #[throws]
+    fn expr(&mut self) -> f64 {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                break value;
+            }
+        }
+    }
+
+    #[throws]
+    fn term(&mut self) -> f64 {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    throw!(format_err!("division by zero"));
+                }
+                value /= divisor;
+            } else if self.eat('%') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    throw!(format_err!("division by zero"));
+                }
+                value %= divisor;
+            } else {
+                break value;
+            }
+        }
+    }
+
+    #[throws]
+    fn unary(&mut self) -> f64 {
+        if self.eat('-') {
+            -self.unary()?
+        } else if self.eat('+') {
+            self.unary()?
+        } else {
+            self.power()?
+        }
+    }
+
+    #[throws]
+    fn power(&mut self) -> f64 {
+        let base = self.atom()?;
+        if self.eat('^') {
+            base.powf(self.unary()?)
+        } else {
+            base
+        }
+    }
+
+    #[throws]
+    fn atom(&mut self) -> f64 {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some('(') => {
+                self.chars.next();
+                let value = self.expr()?;
+                if !self.eat(')') {
+                    throw!(format_err!("missing ')' in expression"));
+                }
+                value
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number()?,
+            Some(c) if c.is_alphabetic() => self.call()?,
+            Some(c) => throw!(format_err!("unexpected '{}' in expression", c)),
+            None => throw!(format_err!("unexpected end of expression")),
+        }
+    }
+
+    #[throws]
+    fn number(&mut self) -> f64 {
+        let mut number = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_ascii_digit() || c == '.' || c == '_' {
+                if c != '_' {
+                    number.push(c);
+                }
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        number
+            .parse()
+            .map_err(|_| format_err!("invalid number '{}'", number))?
+    }
+
+    #[throws]
+    fn call(&mut self) -> f64 {
+        let mut name = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_alphanumeric() {
+                name.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        match &*name {
+            "pi" => return consts::PI,
+            "e" => return consts::E,
+            _ => {}
+        }
+        let function = function(&name).ok_or_else(|| format_err!("unknown function '{}'", name))?;
+        if !self.eat('(') {
+            throw!(format_err!("expected '(' after '{}'", name));
+        }
+        let argument = self.expr()?;
+        if !self.eat(')') {
+            throw!(format_err!("missing ')' in expression"));
+        }
+        function(argument)
+    }
+}
+
+fn function(name: &str) -> Option<fn(f64) -> f64> {
+    Some(match name {
+        "sqrt" => f64::sqrt,
+        "abs" => f64::abs,
+        "floor" => f64::floor,
+        "ceil" => f64::ceil,
+        "round" => f64::round,
+        "ln" => f64::ln,
+        "log" => f64::log10,
+        "sin" => f64::sin,
+        "cos" => f64::cos,
+        "tan" => f64::tan,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(expr: &str) -> String {
+        eval(expr).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn evaluates() {
+        assert_eq!(eval("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(eval("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(eval("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(eval("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(eval("7 % 4 - 10 / 4").unwrap(), 0.5);
+        assert_eq!(eval("1_000 * .5").unwrap(), 500.0);
+        assert_eq!(eval("sqrt(16) + abs(-1)").unwrap(), 5.0);
+        assert_eq!(eval("round(pi)").unwrap(), 3.0);
+    }
+
+    #[test]
+    fn rejects_bad_expressions() {
+        assert_eq!(error("1 / 0"), "division by zero");
+        assert_eq!(error("5 % 0"), "division by zero");
+        assert_eq!(error("(1 + 2"), "missing ')' in expression");
+        assert_eq!(error("1 +"), "unexpected end of expression");
+        assert_eq!(error("1 2"), "unexpected '2' in expression");
+        assert_eq!(error("1..2"), "invalid number '1..2'");
+        assert_eq!(error("foo(1)"), "unknown function 'foo'");
+        assert_eq!(error("sqrt 4"), "expected '(' after 'sqrt'");
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(format(3.0), "3");
+        assert_eq!(format(-0.25), "-0.25");
+        assert_eq!(format(1e20), "100000000000000000000");
+    }
+}
@@ -0,0 +1,65 @@
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// The copy and paste commands for the system clipboard of the current
+/// session, if it has one.
+fn tools() -> Option<(&'static [&'static str], &'static [&'static str])> {
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        Some((&["wl-copy"], &["wl-paste", "--no-newline"]))
+    } else if env::var_os("DISPLAY").is_some() {
+        Some((
+            &["xclip", "-selection", "clipboard"],
+            &["xclip", "-selection", "clipboard", "-o"],
+        ))
+    } else if cfg!(target_os = "macos") {
+        Some((&["pbcopy"], &["pbpaste"]))
+    } else {
+        None
+    }
+}
+
+/// Reads the system clipboard, or `None` if there is none or it can't be
+/// read.
+pub fn get() -> Option<String> {
+    let (_, paste) = tools()?;
+    let output = Command::new(paste[0])
+        .args(&paste[1..])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Writes `text` to the system clipboard, returning whether it worked.
+pub fn set(text: &str) -> bool {
+    let copy = match tools() {
+        Some((copy, _)) => copy,
+        None => return false,
+    };
+    // The copy tools stay around to serve the clipboard, so their output
+    // isn't waited on.
+    let child = Command::new(copy[0])
+        .args(&copy[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return false,
+    };
+    let written = child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(text.as_bytes())
+        .is_ok();
+    written && child.wait().is_ok_and(|status| status.success())
+}
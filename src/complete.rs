@@ -0,0 +1,167 @@
+use crate::{keymap, location::is_word_char};
+use ropey::Rope;
+use std::{collections::HashSet, fs, path::Path};
+
+/// What an argument completes to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Source {
+    Nothing,
+    Path,
+    Command,
+    Mode,
+    Action,
+}
+
+/// Candidates for the word before the prompt cursor, cycled through with tab.
+#[derive(Debug)]
+pub struct Completion {
+    /// Byte offset of the word in the prompt text.
+    pub start: usize,
+    pub candidates: Vec<String>,
+    /// The candidate currently in the prompt.
+    pub index: usize,
+}
+
+/// Words from open buffers for the word before the primary cursor in Insert
+/// mode, shown in a popup and put in with Ctrl-n and Ctrl-p.
+#[derive(Debug)]
+pub struct Words {
+    pub candidates: Vec<String>,
+    /// The candidate put in, or `None` while the typed word is filtering.
+    pub index: Option<usize>,
+    /// Words the language server offered, kept first when collecting again.
+    pub server: Vec<String>,
+    /// The words starting with `prefix` when the popup was opened, which
+    /// typing more of the word narrows down without collecting them again.
+    pub collected: Vec<String>,
+    pub prefix: String,
+}
+
+/// The words of `first` and then of `texts` that start with `prefix` and are
+/// longer than it, in the order they first appear.
+pub fn words<'a>(
+    first: &[String],
+    texts: impl Iterator<Item = &'a Rope>,
+    prefix: &str,
+) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut words = first
+        .iter()
+        .filter(|word| {
+            word.len() > prefix.len() && word.starts_with(prefix) && seen.insert(word.to_string())
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut word = String::new();
+    for text in texts {
+        for c in text.chars().chain(Some('\n')) {
+            if is_word_char(c) {
+                word.push(c);
+                continue;
+            }
+            if word.len() > prefix.len() && word.starts_with(prefix) && seen.insert(word.clone()) {
+                words.push(word.clone());
+            }
+            word.clear();
+        }
+    }
+    words
+}
+
+/// Completes the last word of `text`, a command line up to the cursor:
+/// command names for the first word, and for the arguments whatever `source`
+/// gives for the command name and argument index.
+pub fn complete<'a>(
+    text: &str,
+    commands: impl Iterator<Item = &'a str>,
+    source: impl Fn(&str, usize) -> Source,
+) -> Option<Completion> {
+    let start = text.rfind(' ').map_or(0, |i| i + 1);
+    let word = &text[start..];
+    let mut before = text[..start].split_whitespace();
+    let source = match before.next() {
+        Some(name) => source(name, before.count()),
+        None => Source::Command,
+    };
+    let mut candidates = match source {
+        Source::Nothing => Vec::new(),
+        Source::Path => paths(word),
+        Source::Command => starting_with(commands, word),
+        Source::Mode => starting_with(keymap::MODES.iter().copied(), word),
+        Source::Action => starting_with(keymap::action_names(), word),
+    };
+    if candidates.is_empty() {
+        return None;
+    }
+    candidates.sort();
+    candidates.dedup();
+    Some(Completion {
+        start,
+        candidates,
+        index: 0,
+    })
+}
+
+/// Names containing the chars of `pattern` in order, ignoring ASCII case,
+/// with the tightest and then earliest matches first.
+pub fn fuzzy<'a>(names: impl Iterator<Item = &'a str>, pattern: &str) -> Vec<String> {
+    let mut scored = names
+        .filter_map(|name| Some((score(name, pattern)?, name)))
+        .collect::<Vec<_>>();
+    scored.sort();
+    scored.dedup();
+    scored
+        .into_iter()
+        .map(|(_, name)| name.to_owned())
+        .collect()
+}
+
+/// The span and start of the leftmost match of `pattern` in `name`.
+fn score(name: &str, pattern: &str) -> Option<(usize, usize)> {
+    let mut chars = name.char_indices();
+    let mut first = None;
+    let mut last = 0;
+    for p in pattern.chars() {
+        let (i, _) = chars.find(|(_, c)| c.eq_ignore_ascii_case(&p))?;
+        first.get_or_insert(i);
+        last = i;
+    }
+    let first = first.unwrap_or(0);
+    Some((last - first, first))
+}
+
+fn starting_with<'a>(names: impl Iterator<Item = &'a str>, prefix: &str) -> Vec<String> {
+    names
+        .filter(|name| name.starts_with(prefix))
+        .map(String::from)
+        .collect()
+}
+
+/// Paths starting with `prefix`, with a `/` after directories. Hidden files
+/// are only offered once their `.` is typed.
+fn paths(prefix: &str) -> Vec<String> {
+    let (dir, name) = match prefix.rfind('/') {
+        Some(i) => prefix.split_at(i + 1),
+        None => ("", prefix),
+    };
+    let entries = match fs::read_dir(if dir.is_empty() {
+        Path::new(".")
+    } else {
+        Path::new(dir)
+    }) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            if !file_name.starts_with(name) || file_name.starts_with('.') && !name.starts_with('.')
+            {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}", dir, file_name, slash))
+        })
+        .collect()
+}
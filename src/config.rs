@@ -0,0 +1,412 @@
+use crate::{keymap::Keymap, location::Wrap, Error};
+use anyhow::{format_err, Context as _};
+use crossbeam_channel::Sender;
+use fehler::{throw, throws};
+use std::{
+    collections::HashMap,
+    fs, thread,
+    time::{Duration, SystemTime},
+};
+use toml::{value::Table, Value};
+use xdg::BaseDirectories;
+
+#[derive(Debug)]
+pub struct Config {
+    pub snippets: HashMap<String, HashMap<String, String>>,
+    /// Language server commands by filetype.
+    pub lsp: HashMap<String, String>,
+    /// Shell commands run after events, by event name. `rename` gets the old
+    /// and new paths as `$1` and `$2`.
+    pub hooks: HashMap<String, String>,
+    /// Lines kept visible above and below the cursor when scrolling.
+    pub scrolloff: usize,
+    /// Files bigger than this many bytes are opened in large-file mode.
+    pub large_file_size: usize,
+    /// Files bigger than this many bytes are read in the background.
+    pub async_load_size: usize,
+    /// Whether to set the terminal title to the current buffer.
+    pub title: bool,
+    /// Whether to emit OSC 8 hyperlinks for URLs.
+    pub hyperlinks: bool,
+    /// Whether to show the terminal cursor, colored by mode with OSC 12.
+    pub cursor_color: bool,
+    pub line_numbers: LineNumbers,
+    /// Milliseconds without input before background tasks run.
+    pub idle_delay: usize,
+    /// Filetypes where `j` and `k` move by screen row, `*` for all.
+    pub display_lines: Vec<String>,
+    /// How long lines are broken; `j` and `k` move by screen row when
+    /// breaking at words.
+    pub wrap: Wrap,
+    /// Columns lines are wrapped at when the window is wider, 0 for none.
+    pub wrap_column: usize,
+    pub highlight: bool,
+    /// The default key bindings with any from the `map` table applied.
+    pub keymap: Keymap,
+    /// The shell command `:make` runs, followed by its arguments.
+    pub make: String,
+    pub paste: PasteStrategy,
+    /// Draws slower than this many milliseconds are logged.
+    pub frame_budget: usize,
+    /// Whether to show the last draw time in the status line.
+    pub show_draw_time: bool,
+    /// Whether `C` puts copies that don't fit on short lines at their ends
+    /// instead of skipping those lines.
+    pub copy_clamp: bool,
+    /// Columns `>` and `<` indent by, and that a tab counts for.
+    pub indent_width: usize,
+    /// Whether `>` indents with tabs instead of spaces.
+    pub indent_tabs: bool,
+    /// Whether writing a file keeps the old one as `file~`.
+    pub backup: bool,
+    /// Whether written files always end in a newline. Otherwise they end in
+    /// one only if they did when read.
+    pub final_newline: bool,
+    /// Whether Enter in Insert mode keeps the indentation of the line.
+    pub auto_indent: bool,
+    /// Whether it also indents a level deeper after a line that opens a
+    /// block, such as one ending in `{`, or in `:` in Python.
+    pub smart_indent: bool,
+    /// Whether typing a bracket or quote in Insert mode also inserts the
+    /// one that closes it.
+    pub auto_pairs: bool,
+}
+
+/// How register values are spread over selections when their numbers differ.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PasteStrategy {
+    /// Selection `n` gets value `n`, wrapping around.
+    Cycle,
+    /// Every selection gets the first value.
+    First,
+    /// Every selection gets all values, one per line.
+    Concatenate,
+}
+
+impl PasteStrategy {
+    /// The strategy for a count of 1, 2 or 3 given before a paste.
+    pub fn from_count(count: usize) -> Option<Self> {
+        match count {
+            1 => Some(PasteStrategy::Cycle),
+            2 => Some(PasteStrategy::First),
+            3 => Some(PasteStrategy::Concatenate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LineNumbers {
+    None,
+    Absolute,
+    /// Distances from the cursor line, which shows its own number.
+    Relative,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            snippets: HashMap::new(),
+            lsp: HashMap::new(),
+            hooks: HashMap::new(),
+            scrolloff: 0,
+            large_file_size: 16 * 1024 * 1024,
+            async_load_size: 1024 * 1024,
+            title: true,
+            hyperlinks: false,
+            cursor_color: false,
+            line_numbers: LineNumbers::Absolute,
+            idle_delay: 500,
+            display_lines: Vec::new(),
+            wrap: Wrap::Chars,
+            wrap_column: 0,
+            highlight: true,
+            keymap: Keymap::default(),
+            make: String::from("make"),
+            paste: PasteStrategy::Cycle,
+            frame_budget: 16,
+            show_draw_time: false,
+            copy_clamp: false,
+            indent_width: 4,
+            indent_tabs: false,
+            backup: false,
+            final_newline: true,
+            auto_indent: true,
+            smart_indent: true,
+            auto_pairs: false,
+        }
+    }
+}
+
+impl Config {
+    #[throws]
+    pub fn load() -> Self {
+        let dirs = BaseDirectories::with_prefix("edot")?;
+        match dirs.find_config_file("config.toml") {
+            Some(path) => {
+                let text = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                Self::parse(&text).with_context(|| format!("invalid config {}", path.display()))?
+            }
+            None => Self::default(),
+        }
+    }
+
+    /// Polls the config file, sending a message whenever it is created,
+    /// modified or removed.
+    pub fn watch(changed: Sender<()>) {
+        let modified = || {
+            let dirs = BaseDirectories::with_prefix("edot").ok()?;
+            let path = dirs.find_config_file("config.toml")?;
+            fs::metadata(path).and_then(|m| m.modified()).ok()
+        };
+        thread::spawn(move || {
+            let mut last: Option<SystemTime> = modified();
+            loop {
+                thread::sleep(Duration::from_secs(1));
+                let current = modified();
+                if current != last {
+                    last = current;
+                    if changed.send(()).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    #[throws]
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        let root = text.parse::<Value>()?;
+        for (key, value) in table(&root, "config")? {
+            match &**key {
+                "snippets" => {
+                    for (filetype, snippets) in table(value, key)? {
+                        let snippets = table(snippets, filetype)?
+                            .iter()
+                            .map(|(trigger, template)| {
+                                Ok((trigger.clone(), string(template, trigger)?))
+                            })
+                            .collect::<Result<_, Error>>()?;
+                        config.snippets.insert(filetype.clone(), snippets);
+                    }
+                }
+                "lsp" => {
+                    for (filetype, command) in table(value, key)? {
+                        config
+                            .lsp
+                            .insert(filetype.clone(), string(command, filetype)?);
+                    }
+                }
+                "hooks" => {
+                    for (event, command) in table(value, key)? {
+                        config.hooks.insert(event.clone(), string(command, event)?);
+                    }
+                }
+                "options" => {
+                    for (option, value) in table(value, key)? {
+                        match &**option {
+                            "scrolloff" => config.scrolloff = integer(value, option)?,
+                            "title" => config.title = boolean(value, option)?,
+                            "hyperlinks" => config.hyperlinks = boolean(value, option)?,
+                            "highlight" => config.highlight = boolean(value, option)?,
+                            "cursor_color" => config.cursor_color = boolean(value, option)?,
+                            "make" => config.make = string(value, option)?,
+                            "frame_budget" => config.frame_budget = integer(value, option)?,
+                            "show_draw_time" => config.show_draw_time = boolean(value, option)?,
+                            "copy_clamp" => config.copy_clamp = boolean(value, option)?,
+                            "indent_width" => config.indent_width = integer(value, option)?,
+                            "indent_tabs" => config.indent_tabs = boolean(value, option)?,
+                            "backup" => config.backup = boolean(value, option)?,
+                            "final_newline" => config.final_newline = boolean(value, option)?,
+                            "auto_indent" => config.auto_indent = boolean(value, option)?,
+                            "smart_indent" => config.smart_indent = boolean(value, option)?,
+                            "auto_pairs" => config.auto_pairs = boolean(value, option)?,
+                            "paste" => {
+                                config.paste = match &*string(value, option)? {
+                                    "cycle" => PasteStrategy::Cycle,
+                                    "first" => PasteStrategy::First,
+                                    "concatenate" => PasteStrategy::Concatenate,
+                                    other => throw!(format_err!(
+                                        "paste must be cycle, first or concatenate, not '{}'",
+                                        other
+                                    )),
+                                }
+                            }
+                            "line_numbers" => {
+                                config.line_numbers = match &*string(value, option)? {
+                                    "none" => LineNumbers::None,
+                                    "absolute" => LineNumbers::Absolute,
+                                    "relative" => LineNumbers::Relative,
+                                    other => throw!(format_err!(
+                                        "line_numbers must be none, absolute or relative, not '{}'",
+                                        other
+                                    )),
+                                }
+                            }
+                            "wrap" => {
+                                config.wrap = match &*string(value, option)? {
+                                    "chars" => Wrap::Chars,
+                                    "words" => Wrap::Words,
+                                    "none" => Wrap::None,
+                                    other => throw!(format_err!(
+                                        "wrap must be chars, words or none, not '{}'",
+                                        other
+                                    )),
+                                }
+                            }
+                            "wrap_column" => config.wrap_column = integer(value, option)?,
+                            "large_file_size" => config.large_file_size = integer(value, option)?,
+                            "async_load_size" => config.async_load_size = integer(value, option)?,
+                            "idle_delay" => config.idle_delay = integer(value, option)?,
+                            "display_lines" => {
+                                config.display_lines = value
+                                    .as_array()
+                                    .ok_or_else(|| format_err!("'{}' must be an array", option))?
+                                    .iter()
+                                    .map(|filetype| string(filetype, option))
+                                    .collect::<Result<_, Error>>()?
+                            }
+                            _ => throw!(format_err!("unknown option '{}'", option)),
+                        }
+                    }
+                }
+                "map" => {
+                    for (mode, bindings) in table(value, key)? {
+                        for (keys, action) in table(bindings, mode)? {
+                            config
+                                .keymap
+                                .map(mode, keys, &string(action, keys)?)
+                                .with_context(|| format!("invalid mapping '{}'", keys))?;
+                        }
+                    }
+                }
+                _ => throw!(format_err!("unknown config key '{}'", key)),
+            }
+        }
+        config
+    }
+
+    pub fn display_lines(&self, filetype: Option<&str>) -> bool {
+        self.display_lines
+            .iter()
+            .any(|f| f == "*" || Some(&**f) == filetype)
+    }
+
+    pub fn snippet(&self, filetype: Option<&str>, trigger: &str) -> Option<&str> {
+        filetype
+            .and_then(|filetype| self.snippets.get(filetype))
+            .and_then(|snippets| snippets.get(trigger))
+            .or_else(|| {
+                self.snippets
+                    .get("*")
+                    .and_then(|snippets| snippets.get(trigger))
+            })
+            .map(|template| &**template)
+    }
+}
+
+#[throws]
+fn table<'a>(value: &'a Value, key: &str) -> &'a Table {
+    value
+        .as_table()
+        .ok_or_else(|| format_err!("'{}' must be a table", key))?
+}
+
+#[throws]
+fn string(value: &Value, key: &str) -> String {
+    value
+        .as_str()
+        .ok_or_else(|| format_err!("'{}' must be a string", key))?
+        .to_owned()
+}
+
+#[throws]
+fn integer(value: &Value, key: &str) -> usize {
+    value
+        .as_integer()
+        .filter(|&n| n >= 0)
+        .ok_or_else(|| format_err!("'{}' must be a non-negative integer", key))? as usize
+}
+
+#[throws]
+fn boolean(value: &Value, key: &str) -> bool {
+    value
+        .as_bool()
+        .ok_or_else(|| format_err!("'{}' must be a boolean", key))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        format!("{:#}", Config::parse(text).unwrap_err())
+    }
+
+    #[test]
+    fn parses() {
+        let config = Config::parse(
+            r#"
+            [options]
+            scrolloff = 3
+            title = true
+            wrap = "words"
+            line_numbers = "relative"
+            display_lines = ["markdown"]
+
+            [snippets.rust]
+            fn = "fn $1() {\n\t$0\n}"
+            [snippets."*"]
+            date = "2020"
+
+            [lsp]
+            rust = "rust-analyzer"
+
+            [hooks]
+            rename = "echo"
+
+            [map.normal]
+            "<c-s>" = "matching-bracket"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.scrolloff, 3);
+        assert!(config.title);
+        assert!(matches!(config.wrap, Wrap::Words));
+        assert!(matches!(config.line_numbers, LineNumbers::Relative));
+        assert!(config.display_lines(Some("markdown")));
+        assert!(!config.display_lines(None));
+        assert_eq!(
+            config.snippet(Some("rust"), "fn"),
+            Some("fn $1() {\n\t$0\n}")
+        );
+        assert_eq!(config.snippet(Some("rust"), "date"), Some("2020"));
+        assert_eq!(config.snippet(None, "fn"), None);
+        assert_eq!(config.lsp["rust"], "rust-analyzer");
+        assert_eq!(config.hooks["rename"], "echo");
+        assert!(config.keymap.dump().contains("<c-s>"));
+    }
+
+    #[test]
+    fn rejects_bad_config() {
+        assert_eq!(error("[other]"), "unknown config key 'other'");
+        assert_eq!(error("options = 1"), "'options' must be a table");
+        assert_eq!(error("[options]\nnope = 1"), "unknown option 'nope'");
+        assert_eq!(error("[options]\ntitle = 1"), "'title' must be a boolean");
+        assert_eq!(
+            error("[options]\nscrolloff = -1"),
+            "'scrolloff' must be a non-negative integer"
+        );
+        assert_eq!(
+            error("[options]\nwrap = \"lines\""),
+            "wrap must be chars, words or none, not 'lines'"
+        );
+        assert_eq!(error("[lsp]\nrust = 1"), "'rust' must be a string");
+        assert!(
+            error("[map.normal]\n\"<nope>\" = \"matching-bracket\"").starts_with("invalid mapping")
+        );
+        assert!(Config::parse("[options").is_err());
+    }
+}
@@ -1,33 +1,65 @@
 use crate::{
+    address, align, calc, clipboard,
+    complete::{self, Source},
+    config::{Config, LineNumbers, PasteStrategy},
+    effect, filetype,
+    follow::{self, Appended},
+    format::{self, Encoding, Format, LineEnding},
+    highlight,
     id_vec::{Id, IdVec},
-    location::{Column, Line, Movement, MovementError, Position, Selection},
-    terminal::{Point, Rect},
+    json::{object, Value},
+    keymap::Action,
+    keys,
+    layout::{Layout, Orientation},
+    load::{self, Loaded},
+    location::{
+        self, is_word_char, row_starts, Column, Line, Movement, MovementError, Position, Selection,
+        TextObject, Wrap,
+    },
+    lsp::{self, Incoming, Pending, Severity},
+    perf::Perf,
+    prompt::Prompt,
+    quickfix, recent,
+    regex::Regex,
+    save,
+    screen::{Color, Screen, Style},
+    search::Search,
+    snippet, subprocess, substitute,
+    terminal::{Point, Rect, Size},
+    unicode, url,
+    watch::Watcher,
     Error, Result,
 };
 use anyhow::{format_err, Context as _};
-use crossbeam_channel::{select, unbounded, Receiver, Sender};
-use fehler::throws;
-use log::{error, info, trace};
+use crossbeam_channel::{after, bounded, never, select, unbounded, Receiver, Sender};
+use fehler::{throw, throws};
+use log::{error, info, trace, warn};
 use ropey::Rope;
 use shlex::split as shlex;
 use signal_hook::{iterator::Signals, SIGWINCH};
 use std::{
+    cmp::Reverse,
     collections::{HashMap, VecDeque},
+    env,
     fmt::Debug,
-    fs::File,
+    fs::{self, File, OpenOptions},
     io::{self, Write},
     mem::take,
+    ops::Range,
     os::raw::c_int,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    rc::Rc,
     thread,
+    time::{Duration, Instant, SystemTime},
 };
 use termion::{
-    clear, color, cursor,
-    event::{Event, Key},
+    cursor,
+    event::{Event, Key, MouseButton, MouseEvent},
     get_tty,
-    input::TermRead,
+    input::{MouseTerminal, TermRead},
     raw::{IntoRawMode, RawTerminal},
-    screen, style, terminal_size,
+    screen, terminal_size,
 };
 
 #[macro_export]
@@ -44,14 +76,79 @@ macro_rules! id {
     };
 }
 
+const DELETED_LEN: usize = 9;
+const HISTORY_LEN: usize = 1000;
+const MESSAGES_LEN: usize = 100;
+const LARGE_HISTORY_LEN: usize = 20;
+/// How deeply macros may replay other macros, or themselves.
+const MACRO_DEPTH: usize = 16;
+/// The largest count a command can be given.
+const MAX_COUNT: usize = 10_000;
+
 pub struct Edot {
     signal: Receiver<c_int>,
     input: Receiver<io::Result<Event>>,
+    /// Lets the input thread read one more event, so it leaves the terminal
+    /// alone while interactive commands run.
+    input_gate: Sender<()>,
     exit: (Sender<()>, Receiver<()>),
+    follow: (Sender<Appended>, Receiver<Appended>),
+    make: (Sender<Made>, Receiver<Made>),
+    load: (Sender<Loaded>, Receiver<Loaded>),
+    lsp: (Sender<Incoming>, Receiver<Incoming>),
+    /// Language servers by filetype, started when a file of it is opened;
+    /// `None` for ones that failed to start or exited.
+    servers: HashMap<&'static str, Option<lsp::Server>>,
+    config_changed: Receiver<()>,
+    /// Receives when open files should be checked for changes on disk.
+    watch: Receiver<()>,
+    watcher: Option<Watcher>,
     windows: IdVec<WindowId, Window>,
+    layout: Layout,
     buffers: IdVec<BufferId, Buffer>,
     commands: HashMap<String, CommandDesc>,
-    output: RawTerminal<File>,
+    config: Config,
+    registers: HashMap<char, Register>,
+    /// Recently deleted texts, newest first, readable as registers `1`-`9`.
+    deleted: VecDeque<Register>,
+    recent: Vec<recent::Entry>,
+    /// Matches from `:find-all` across buffers, with the current one.
+    results: Vec<(BufferId, Range<usize>, usize)>,
+    result_index: Option<usize>,
+    /// The location list from `:grep` or tool output, with the current entry.
+    locations: Vec<quickfix::Entry>,
+    location_index: Option<usize>,
+    output: MouseTerminal<RawTerminal<File>>,
+    screen: Screen,
+    /// Screen regions from the last draw, for mouse hit testing.
+    hits: Vec<(Rect, Hit)>,
+    /// The region of the layout whose separator at the point is being
+    /// dragged with the mouse.
+    resizing: Option<(Rect, Point)>,
+    /// Recent status line messages, oldest first.
+    messages: VecDeque<String>,
+    /// Info messages and output collected while a command is redirected.
+    captured: Option<String>,
+    /// The terminal title last set, if any.
+    title: Option<String>,
+    /// Where the focused window's primary cursor was last drawn.
+    cursor: Option<Point>,
+    /// The cursor color last set with OSC 12, if any.
+    cursor_color: Option<&'static str>,
+    /// The register keys are being recorded into with `Q`, the keys so far,
+    /// and how many of them came before the command being typed.
+    recording: Option<(char, Vec<Key>, usize)>,
+    /// How many macro replays are running inside each other.
+    replaying: usize,
+    /// The keys of the change being typed, collected for `.`.
+    change: Option<ChangeKeys>,
+    /// The keys of the last complete change, which `.` replays.
+    last_change: Vec<Key>,
+    /// Background work waiting for input to go idle, run oldest first.
+    idle: VecDeque<IdleTask>,
+    /// When the last input arrived, which idle tasks wait `idle_delay` after.
+    input_at: Instant,
+    perf: Perf,
     focused: WindowId,
     tabline_dirty: bool,
     editor_dirty: bool,
@@ -59,6 +156,18 @@ pub struct Edot {
     message: Option<(Importance, String)>,
 }
 
+/// The result of a background `:make`.
+struct Made {
+    command: String,
+    output: io::Result<process::Output>,
+}
+
+/// Low-priority work deferred until there is no input to handle.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum IdleTask {
+    SaveRecent,
+}
+
 id!(WindowId);
 id!(BufferId);
 
@@ -67,54 +176,86 @@ impl Edot {
     pub fn new() -> Self {
         let (signals, signal) = unbounded();
         let (inputs, input) = unbounded();
-        let signal_iter = Signals::new(&[SIGWINCH])?;
+        let (input_gate, gate) = unbounded();
+        input_gate.send(()).unwrap();
+        let signal_iter = Signals::new([SIGWINCH])?;
         thread::spawn(move || {
             for signal in signal_iter.forever() {
                 signals.send(signal).unwrap();
             }
         });
         let tty = get_tty()?;
+        let mut message = None;
+        let config = Config::load().unwrap_or_else(|err| {
+            error!("{:#}", err);
+            message = Some((Importance::Error, format!("{:#}", err)));
+            Config::default()
+        });
+        let (watcher, config_changed) = unbounded();
+        Config::watch(watcher);
+        let (changed, watch) = bounded(1);
+        let watcher = Watcher::new(changed)
+            .map_err(|err| error!("failed to watch files: {}", err))
+            .ok();
+        let recent = recent::load().unwrap_or_else(|err| {
+            error!("{:#}", err);
+            Vec::new()
+        });
         thread::spawn(move || {
-            for event in tty.events() {
-                inputs.send(event).unwrap();
+            let mut events = tty.events();
+            for () in gate {
+                match events.next() {
+                    Some(event) => inputs.send(event).unwrap(),
+                    None => break,
+                }
             }
         });
         Self {
             signal,
             input,
+            input_gate,
             exit: unbounded(),
-            windows: vec![Window {
-                buffer: BufferId(0),
-                mode: Mode::Normal,
-                selections: vec![Selection {
-                    start: Position {
-                        line: Line::from_one_based(1),
-                        column: Column::from_one_based(1),
-                    },
-                    end: Position {
-                        line: Line::from_one_based(1),
-                        column: Column::from_one_based(1),
-                    },
-                }]
-                .into(),
-                command: String::new(),
-                top: Line::from_one_based(1),
-            }]
-            .into(),
-            buffers: vec![Buffer {
-                content: Rope::from("\n"),
-                name: String::from("scratch"),
-                history: VecDeque::new(),
-                path: None,
-            }]
-            .into(),
+            follow: unbounded(),
+            make: unbounded(),
+            load: unbounded(),
+            lsp: unbounded(),
+            servers: HashMap::new(),
+            config_changed,
+            watch,
+            watcher,
+            windows: vec![Window::new(BufferId(0))].into(),
+            layout: Layout::Window(WindowId(0)),
+            buffers: vec![Buffer::new(String::from("scratch"), Rope::from("\n"), None)].into(),
             commands: HashMap::new(),
-            output: get_tty()?.into_raw_mode()?,
+            config,
+            registers: HashMap::new(),
+            deleted: VecDeque::new(),
+            recent,
+            results: Vec::new(),
+            result_index: None,
+            locations: Vec::new(),
+            location_index: None,
+            output: MouseTerminal::from(get_tty()?.into_raw_mode()?),
+            screen: Screen::default(),
+            hits: Vec::new(),
+            resizing: None,
+            messages: VecDeque::new(),
+            captured: None,
+            title: None,
+            cursor: None,
+            cursor_color: None,
+            recording: None,
+            replaying: 0,
+            change: None,
+            last_change: Vec::new(),
+            idle: VecDeque::new(),
+            input_at: Instant::now(),
+            perf: Perf::default(),
             focused: WindowId(0),
             tabline_dirty: true,
             editor_dirty: true,
             statusline_dirty: true,
-            message: None,
+            message,
         }
     }
 
@@ -128,15 +269,105 @@ impl Edot {
             cursor::Hide,
             cursor::SteadyBar
         )?;
+        if self.config.title {
+            // Save the title on the terminal's title stack.
+            write!(self.output, "\x1b[22;2t")?;
+        }
         self.register::<Quit>("q")
             .register::<Quit>("quit")
+            .register::<ForceQuit>("q!")
+            .register::<ForceQuit>("quit!")
+            .register::<WriteQuit>("wq")
+            .register::<Exit>("x")
+            .register::<Exit>("exit")
+            .register::<Messages>("messages")
+            .register::<Help>("help")
+            .register::<BufferCommand>("b")
+            .register::<BufferCommand>("buffer")
+            .register::<BufferNext>("bn")
+            .register::<BufferNext>("bnext")
+            .register::<BufferPrev>("bp")
+            .register::<BufferPrev>("bprev")
+            .register::<Buffers>("ls")
+            .register::<Buffers>("buffers")
+            .register::<KeymapDump>("keymap-dump")
+            .register::<Map>("map")
+            .register::<ReloadConfig>("config-reload")
+            .register::<Unmap>("unmap")
             .register::<Edit>("e")
-            .register::<Edit>("edit");
+            .register::<Edit>("edit")
+            .register::<ReloadFile>("e!")
+            .register::<ReloadFile>("edit!")
+            .register::<ViewCommand>("view")
+            .register::<WriteFile>("w")
+            .register::<WriteFile>("write")
+            .register::<ForceWrite>("w!")
+            .register::<ForceWrite>("write!")
+            .register::<Rename>("rename")
+            .register::<ForceRename>("rename!")
+            .register::<Read>("r")
+            .register::<Read>("read")
+            .register::<Follow>("follow")
+            .register::<SplitHere>("split-here")
+            .register::<Split>("split")
+            .register::<Split>("sp")
+            .register::<VerticalSplit>("vsplit")
+            .register::<VerticalSplit>("vs")
+            .register::<Deleted>("deleted")
+            .register::<Bookmarks>("bookmarks")
+            .register::<Unicode>("unicode")
+            .register::<Calc>("calc")
+            .register::<AlignTable>("align-table")
+            .register::<AlignRegex>("align")
+            .register::<Goto>("goto")
+            .register::<MoveLines>("move")
+            .register::<MoveLines>("m")
+            .register::<CopyLines>("copy")
+            .register::<CopyLines>("t")
+            .register::<MacroEdit>("macro-edit")
+            .register::<MacroStore>("macro-store")
+            .register::<InsertIndex>("insert-index")
+            .register::<RotateSelections>("rotate-selections")
+            .register::<RotateContents>("rotate-contents")
+            .register::<SearchCommand>("search")
+            .register::<Keep>("keep")
+            .register::<KeepNot>("keep-not")
+            .register::<FindAll>("find-all")
+            .register::<FindNext>("find-next")
+            .register::<FindPrev>("find-prev")
+            .register::<Grep>("grep")
+            .register::<Make>("make")
+            .register::<ShellCommand>("!")
+            .register::<Shell>("sh")
+            .register::<Shell>("shell")
+            .register::<LocationNext>("cn")
+            .register::<LocationNext>("cnext")
+            .register::<LocationPrev>("cp")
+            .register::<LocationPrev>("cprev")
+            .register::<LocationList>("cl")
+            .register::<LocationList>("clist")
+            .register::<Definition>("definition")
+            .register::<Hover>("hover")
+            .register::<Diagnostics>("diagnostics")
+            .register::<PerfReport>("perf")
+            .register::<Substitute>("s")
+            .register::<Substitute>("substitute");
         loop {
+            let start = Instant::now();
             self.draw()?;
-            match self.main() {
+            let elapsed = start.elapsed();
+            let budget = Duration::from_millis(self.config.frame_budget as u64);
+            if self.perf.draw(elapsed, budget) {
+                warn!("draw took {:?}, over the {:?} budget", elapsed, budget);
+            }
+            let result = self.main();
+            self.commit_changes();
+            self.sync_documents();
+            self.merge_selections();
+            self.scroll_windows();
+            match result {
                 Ok(true) => continue,
-                Ok(false) => return,
+                Ok(false) => return self.save_recent(),
                 Err(err) => {
                     error!("{}", err);
                     self.show_message(Importance::Error, err.to_string());
@@ -147,21 +378,59 @@ impl Edot {
 
     #[throws]
     fn main(&mut self) -> bool {
+        let idle = if self.idle.is_empty() {
+            never()
+        } else {
+            let at = self.input_at + Duration::from_millis(self.config.idle_delay as u64);
+            after(at.saturating_duration_since(Instant::now()))
+        };
         select! {
-            recv(self.input) -> input => self.event(input??)?,
+            recv(self.input) -> input => {
+                let input = input??;
+                self.input_at = Instant::now();
+                if let Event::Key(key) = input {
+                    if let Some((_, keys, _)) = &mut self.recording {
+                        keys.push(key);
+                    }
+                    self.record_change_key(key);
+                }
+                let start = Instant::now();
+                let result = self.event(input);
+                self.perf.event(start.elapsed());
+                self.finish_change();
+                if let (true, Some((_, keys, start))) = (self.waiting(), &mut self.recording) {
+                    *start = keys.len();
+                }
+                self.input_gate.send(()).unwrap();
+                result?;
+                self.schedule(IdleTask::SaveRecent);
+            }
+            recv(idle) -> _ => self.run_idle(),
             recv(self.signal) -> signal => self.signal(signal?)?,
             recv(self.exit.1) -> exit => { exit?; return Ok(false); },
+            recv(self.follow.1) -> appended => self.append(appended?),
+            recv(self.make.1) -> made => self.made(made?)?,
+            recv(self.load.1) -> loaded => self.loaded(loaded?)?,
+            recv(self.lsp.1) -> incoming => self.lsp_message(incoming?)?,
+            recv(self.watch) -> _ => self.check_files(),
+            recv(self.config_changed) -> changed => {
+                changed?;
+                self.reload_config()?;
+            }
         }
         true
     }
 
     #[throws]
     fn cmd(&mut self, args: &[&str]) {
-        let name = args.get(0).context("no command given")?;
+        let name = args.first().context("no command given")?;
         let cmd = self
             .commands
             .get(*name)
             .ok_or_else(|| format_err!("command '{}' doesn't exist", name))?;
+        if !cmd.accepts(args.len() - 1) {
+            throw!(format_err!("usage: {} {}", name, cmd.usage()));
+        }
         (cmd.run)(
             Context {
                 window: self.focused,
@@ -179,550 +448,6163 @@ impl Edot {
     #[throws]
     fn event(&mut self, event: Event) {
         trace!("event: {:?}", event);
-        match self.windows[self.focused].mode {
-            Mode::Normal => match event {
-                Event::Key(Key::Char('i')) => {
-                    self.order_selections(self.focused);
-                    self.set_mode(self.focused, Mode::Insert);
-                }
-                Event::Key(Key::Char('c')) => {
-                    self.delete_selections(self.focused);
-                    self.set_mode(self.focused, Mode::Insert);
-                }
-                Event::Key(Key::Char('a')) => {
-                    self.order_selections(self.focused);
-                    self.set_mode(self.focused, Mode::Append);
-                }
-                Event::Key(Key::Char('A')) => {
-                    self.move_selections(self.focused, Movement::LineEnd, false)?;
-                    self.set_mode(self.focused, Mode::Insert);
-                }
-                Event::Key(Key::Char('o')) => {
-                    for selection_id in self.selections(self.focused) {
-                        self.move_selection(self.focused, selection_id, Movement::LineEnd, false)?;
-                        self.insert_char_after(self.focused, selection_id, '\n');
-                        self.move_selection(self.focused, selection_id, Movement::Down, false)?;
-                        self.move_selection(
-                            self.focused,
-                            selection_id,
-                            Movement::LineStart,
-                            false,
-                        )?;
-                    }
-                    self.set_mode(self.focused, Mode::Insert);
-                }
-                Event::Key(Key::Char('x')) => {
-                    // self.move_selections(self.focused, Movement::Line, false)?;
-                }
-                Event::Key(Key::Char('X')) => {
-                    // self.move_selections(self.focused, Movement::Line, true)?;
-                }
-                Event::Key(Key::Char('g')) => {
-                    self.set_mode(self.focused, Mode::Goto { drag: false });
-                }
-                Event::Key(Key::Char('G')) => {
-                    self.set_mode(self.focused, Mode::Goto { drag: true });
+        self.windows[self.focused].hover = None;
+        if let Event::Mouse(event) = event {
+            return self.mouse_event(event)?;
+        }
+        let window = &mut self.windows[self.focused];
+        if let (Mode::Normal, Event::Key(Key::Char(c))) = (window.mode, &event) {
+            if let Some(digit) = c.to_digit(10).filter(|&d| d > 0 || window.count.is_some()) {
+                let count = window.count.unwrap_or(0);
+                let count = count.saturating_mul(10).saturating_add(digit as usize);
+                window.count = Some(count.min(MAX_COUNT));
+                return;
+            }
+        }
+        let register = window.register.take().unwrap_or('"');
+        let count = window.count.take();
+        let mode = window.mode;
+        if let Some(name) = mode.keymap() {
+            self.set_mode(self.focused, Mode::Normal);
+            if let Event::Key(key) = event {
+                if let Some(action) = self.config.keymap.get(name, key) {
+                    self.action(action, mode, register, count)?;
                 }
-                Event::Key(Key::Char(':')) => {
-                    self.set_mode(self.focused, Mode::Command);
+            }
+            return;
+        }
+        match mode {
+            Mode::Normal
+            | Mode::Goto { .. }
+            | Mode::View
+            | Mode::Window
+            | Mode::Object { .. }
+            | Mode::Surround => unreachable!(),
+            Mode::Register => {
+                if let Event::Key(Key::Char(name)) = event {
+                    self.windows[self.focused].register = Some(name);
                 }
-                Event::Key(Key::Char('h')) | Event::Key(Key::Left) => {
-                    self.move_selections(self.focused, Movement::Left, false)?;
+                self.set_mode(self.focused, Mode::Normal);
+            }
+            Mode::Replace => {
+                self.set_mode(self.focused, Mode::Normal);
+                if let Event::Key(Key::Char(c)) = event {
+                    self.replace_chars(self.focused, c);
                 }
-                Event::Key(Key::Char('j')) | Event::Key(Key::Down) => {
-                    self.move_selections(self.focused, Movement::Down, false)?;
+            }
+            Mode::SetMark => {
+                self.set_mode(self.focused, Mode::Normal);
+                if let Event::Key(Key::Char(name)) = event {
+                    self.set_mark(self.focused, name);
                 }
-                Event::Key(Key::Char('k')) | Event::Key(Key::Up) => {
-                    self.move_selections(self.focused, Movement::Up, false)?;
+            }
+            Mode::JumpMark => {
+                self.set_mode(self.focused, Mode::Normal);
+                if let Event::Key(Key::Char(name)) = event {
+                    self.jump_mark(self.focused, name)?;
                 }
-                Event::Key(Key::Char('l')) | Event::Key(Key::Right) => {
-                    self.move_selections(self.focused, Movement::Right, false)?;
+            }
+            Mode::CombineSelections { register } => {
+                self.set_mode(self.focused, Mode::Normal);
+                let combine = match event {
+                    Event::Key(Key::Char('a')) => Combine::Append,
+                    Event::Key(Key::Char('u')) => Combine::Union,
+                    Event::Key(Key::Char('i')) => Combine::Intersect,
+                    _ => return,
+                };
+                self.restore_selections(self.focused, register, combine)?;
+            }
+            Mode::SurroundAdd => {
+                self.set_mode(self.focused, Mode::Normal);
+                if let Event::Key(Key::Char(c)) = event {
+                    self.surround_add(self.focused, c);
                 }
-                Event::Key(Key::Char('H')) => {
-                    self.move_selections(self.focused, Movement::Left, true)?;
+            }
+            Mode::SurroundDelete => {
+                self.set_mode(self.focused, Mode::Normal);
+                if let Event::Key(Key::Char(c)) = event {
+                    self.surround_change(self.focused, c, None)?;
                 }
-                Event::Key(Key::Char('J')) => {
-                    self.move_selections(self.focused, Movement::Down, true)?;
+            }
+            Mode::SurroundChange { from: None } => match event {
+                Event::Key(Key::Char(c)) => {
+                    self.set_mode(self.focused, Mode::SurroundChange { from: Some(c) })
                 }
-                Event::Key(Key::Char('K')) => {
-                    self.move_selections(self.focused, Movement::Up, true)?;
+                _ => self.set_mode(self.focused, Mode::Normal),
+            },
+            Mode::SurroundChange { from: Some(from) } => {
+                self.set_mode(self.focused, Mode::Normal);
+                if let Event::Key(Key::Char(c)) = event {
+                    self.surround_change(self.focused, from, Some(c))?;
                 }
-                Event::Key(Key::Char('L')) => {
-                    self.move_selections(self.focused, Movement::Right, true)?;
+            }
+            Mode::Insert | Mode::Append => {
+                if let Some(input) = self.windows[self.focused].unicode.take() {
+                    return self.unicode_event(input, event)?;
                 }
-                Event::Key(Key::Char('d')) => {
-                    self.delete_selections(self.focused);
+                if take(&mut self.windows[self.focused].register_pending) {
+                    if let Event::Key(Key::Char(name)) = event {
+                        self.insert_register(self.focused, name)?;
+                    }
+                    return;
                 }
-                _ => {}
-            },
-            Mode::Goto { drag } => {
+                let filters = match event {
+                    Event::Key(Key::Char(c)) => is_word_char(c),
+                    Event::Key(Key::Backspace) => true,
+                    _ => false,
+                };
+                let cycles = matches!(
+                    event,
+                    Event::Key(Key::Ctrl('n')) | Event::Key(Key::Ctrl('p'))
+                );
+                let completing = self.windows[self.focused].words.is_some();
                 match event {
-                    Event::Key(Key::Char('h')) => {
-                        self.move_selections(self.focused, Movement::LineStart, drag)?;
+                    Event::Key(Key::Esc) => self.set_mode(self.focused, Mode::Normal),
+                    Event::Key(Key::Char('\t')) if self.windows[self.focused].snippet.is_some() => {
+                        self.jump_snippet_field(self.focused, true);
+                    }
+                    Event::Key(Key::BackTab) if self.windows[self.focused].snippet.is_some() => {
+                        self.jump_snippet_field(self.focused, false);
+                    }
+                    Event::Key(Key::Char('\t')) => {
+                        let mut expanded = false;
+                        for selection_id in self.selections(self.focused) {
+                            if self.expand_snippet(self.focused, selection_id) {
+                                expanded = true;
+                            } else {
+                                self.type_char_at(self.focused, selection_id, '\t')?;
+                            }
+                        }
+                        if expanded {
+                            self.jump_snippet_field(self.focused, true);
+                        }
+                    }
+                    Event::Key(Key::Char('\n')) if self.config.auto_indent => {
+                        self.replace_snippet_placeholder(self.focused);
+                        self.insert_newline(self.focused);
                     }
-                    Event::Key(Key::Char('j')) => {
-                        self.move_selections(self.focused, Movement::FileEnd, drag)?;
+                    Event::Key(Key::Char(c)) if self.config.auto_pairs => {
+                        self.replace_snippet_placeholder(self.focused);
+                        self.type_paired(self.focused, c)?;
                     }
-                    Event::Key(Key::Char('k')) => {
-                        self.move_selections(self.focused, Movement::FileStart, drag)?;
+                    Event::Key(Key::Char(c)) => {
+                        self.replace_snippet_placeholder(self.focused);
+                        self.type_char(self.focused, c)?;
                     }
-                    Event::Key(Key::Char('l')) => {
-                        self.move_selections(self.focused, Movement::LineEnd, drag)?;
+                    Event::Key(Key::Ctrl('v')) => {
+                        self.windows[self.focused].unicode =
+                            Some(UnicodeInput::Codepoint(String::new()));
                     }
+                    Event::Key(Key::Ctrl('k')) => {
+                        self.windows[self.focused].unicode = Some(UnicodeInput::Digraph(None));
+                    }
+                    Event::Key(Key::Ctrl('r')) => {
+                        self.windows[self.focused].register_pending = true;
+                    }
+                    Event::Key(Key::Ctrl('n')) => self.complete_word(self.focused, true)?,
+                    Event::Key(Key::Ctrl('p')) => self.complete_word(self.focused, false)?,
+                    Event::Key(Key::Backspace) => self.backspace(self.focused)?,
                     _ => {}
-                };
-                self.set_mode(self.focused, Mode::Normal);
+                }
+                if completing && filters {
+                    self.filter_words(self.focused);
+                } else if !cycles {
+                    self.windows[self.focused].words = None;
+                }
             }
-            mode @ Mode::Insert | mode @ Mode::Append => match event {
-                Event::Key(Key::Esc) => self.set_mode(self.focused, Mode::Normal),
-                Event::Key(Key::Char(c)) => {
-                    for selection_id in self.selections(self.focused) {
+            Mode::Command
+            | Mode::Search
+            | Mode::Select
+            | Mode::Keep { .. }
+            | Mode::Pipe
+            | Mode::Palette
+            | Mode::Characters => {
+                let mode = self.windows[self.focused].mode;
+                let picking = matches!(mode, Mode::Palette | Mode::Characters);
+                if event != Event::Key(Key::Char('\t')) && !picking {
+                    self.windows[self.focused].completion = None;
+                }
+                let prompt = self.windows[self.focused].prompt_mut();
+                match event {
+                    Event::Key(Key::Esc) => {
+                        prompt.clear();
+                        self.end_search_preview(self.focused);
+                        self.set_mode(self.focused, Mode::Normal);
+                    }
+                    Event::Key(Key::Char('\t')) if matches!(mode, Mode::Command) => {
+                        self.complete_command(self.focused)
+                    }
+                    Event::Key(Key::Char('\t')) if picking => {
+                        self.cycle_palette(self.focused, true)
+                    }
+                    Event::Key(Key::BackTab) if picking => self.cycle_palette(self.focused, false),
+                    Event::Key(Key::Char('\t')) => {}
+                    Event::Key(Key::Char('\n')) => {
+                        let text = prompt.take();
+                        self.end_search_preview(self.focused);
+                        self.set_mode(self.focused, Mode::Normal);
                         match mode {
-                            Mode::Insert => {
-                                self.insert_char_before(self.focused, selection_id, c);
-                                self.shift_selection(self.focused, selection_id, Movement::Right)?;
+                            Mode::Search => self.search(self.focused, &text)?,
+                            Mode::Select => self.select_matches(self.focused, &text)?,
+                            Mode::Keep { matching } => {
+                                self.keep_selections(self.focused, &text, matching)?
                             }
-                            Mode::Append => {
-                                self.move_selection(
-                                    self.focused,
-                                    selection_id,
-                                    Movement::Right,
-                                    true,
-                                )?;
-                                self.insert_char_after(self.focused, selection_id, c);
-                            }
-                            _ => unreachable!(),
+                            Mode::Pipe => self.pipe_selections(self.focused, &text)?,
+                            Mode::Palette => self.run_palette(self.focused, &text)?,
+                            Mode::Characters => self.pick_character(self.focused, &text)?,
+                            _ => self.command_line(&text)?,
                         }
                     }
+                    Event::Key(key) if !prompt.edit(key) => {
+                        self.end_search_preview(self.focused);
+                        self.set_mode(self.focused, Mode::Normal);
+                    }
+                    Event::Key(_) if matches!(mode, Mode::Search) => {
+                        self.preview_search(self.focused)
+                    }
+                    Event::Key(_) if picking => self.filter_palette(self.focused),
+                    _ => {}
                 }
-                Event::Key(Key::Backspace) => {
-                    self.move_selections(self.focused, Movement::Left, false)?;
-                    self.delete_selections(self.focused);
+            }
+        }
+    }
+
+    /// Runs a mapped key's action, with `mode` the mode it was pressed in.
+    /// Motions, searches and undo take a count of repetitions.
+    #[throws]
+    fn action(&mut self, action: Action, mode: Mode, register: char, count: Option<usize>) {
+        let window = self.focused;
+        let times = count.unwrap_or(1);
+        match action {
+            Action::Register => self.set_mode(window, Mode::Register),
+            Action::Insert => {
+                self.order_selections(window);
+                self.set_mode(window, Mode::Insert);
+            }
+            Action::Append => {
+                self.order_selections(window);
+                self.set_mode(window, Mode::Append);
+            }
+            Action::AppendLineEnd => {
+                self.move_selections(window, Movement::LineEnd, false)?;
+                self.set_mode(window, Mode::Insert);
+            }
+            Action::Open { above } => {
+                for selection_id in self.selections(window) {
+                    self.open_line(window, selection_id, above);
                 }
-                _ => {}
-            },
-            Mode::Command => match event {
-                Event::Key(Key::Esc) => {
-                    self.windows[self.focused].command.clear();
-                    self.set_mode(self.focused, Mode::Normal);
-                }
-                Event::Key(Key::Char('\t')) => {}
-                Event::Key(Key::Char('\n')) => {
-                    let command = take(&mut self.windows[self.focused].command);
-                    self.set_mode(self.focused, Mode::Normal);
-                    let command = shlex(&command)
-                        .ok_or_else(|| format_err!("failed to parse command '{}'", command))?;
-                    trace!("command: {:?}", command);
-                    let command = command.iter().map(|x| &**x).collect::<Vec<&str>>();
-                    self.cmd(&command)?;
+                self.set_mode(window, Mode::Insert);
+            }
+            Action::Change { yank } => {
+                if yank {
+                    self.yank_selections(window, register);
                 }
-                Event::Key(Key::Char(c)) => {
-                    self.windows[self.focused].command.push(c);
+                self.record_deleted(window);
+                self.delete_selections(window);
+                self.set_mode(window, Mode::Insert);
+            }
+            Action::Delete { yank } => {
+                if yank {
+                    self.yank_selections(window, register);
                 }
-                Event::Key(Key::Backspace) => {
-                    if self.windows[self.focused].command.pop().is_none() {
-                        self.set_mode(self.focused, Mode::Normal);
-                    } else {
-                    }
+                self.record_deleted(window);
+                self.delete_selections(window);
+            }
+            Action::Yank => self.yank_selections(window, register),
+            Action::Replace => self.set_mode(window, Mode::Replace),
+            Action::Paste { before } => {
+                let strategy = match count {
+                    Some(count) => PasteStrategy::from_count(count)
+                        .context("paste count must be 1 (cycle), 2 (first) or 3 (concatenate)")?,
+                    None => self.config.paste,
+                };
+                self.paste(window, register, before, strategy)?;
+            }
+            Action::Undo => {
+                self.not_repeatable();
+                for _ in 0..times {
+                    self.undo(window)?;
+                }
+            }
+            Action::Redo => {
+                self.not_repeatable();
+                for _ in 0..times {
+                    self.redo(window)?;
+                }
+            }
+            Action::Repeat => {
+                self.not_repeatable();
+                if self.last_change.is_empty() {
+                    throw!(format_err!("no change to repeat"));
+                }
+                for _ in 0..times {
+                    self.replay_keys(self.last_change.clone())?;
                 }
-                _ => {}
+            }
+            Action::Move { movement, drag } => {
+                let drag = drag || matches!(mode, Mode::Goto { drag: true });
+                self.move_selections_times(window, movement, drag, times)?;
+            }
+            Action::Vertical { up, drag } => {
+                let movement = self.vertical_movement(window, up);
+                self.move_selections_times(window, movement, drag, times)?;
+            }
+            Action::Row { up, drag } => {
+                let width = self.windows[window].text_width;
+                let movement = if up {
+                    Movement::RowUp(width, self.config.wrap)
+                } else {
+                    Movement::RowDown(width, self.config.wrap)
+                };
+                self.move_selections_times(window, movement, drag, times)?;
+            }
+            Action::Goto { drag } => match count {
+                Some(line) => self.goto_line(window, line, drag)?,
+                None => self.set_mode(window, Mode::Goto { drag }),
             },
+            Action::View => self.set_mode(window, Mode::View),
+            Action::Window => self.set_mode(window, Mode::Window),
+            Action::Object { around } => self.set_mode(window, Mode::Object { around }),
+            Action::Command => self.set_mode(window, Mode::Command),
+            Action::Search => self.set_mode(window, Mode::Search),
+            Action::Select => self.set_mode(window, Mode::Select),
+            Action::Keep { matching } => self.set_mode(window, Mode::Keep { matching }),
+            Action::Pipe => self.set_mode(window, Mode::Pipe),
+            Action::Palette => self.set_mode(window, Mode::Palette),
+            Action::SearchNext { forward } => self.search_next(window, forward, times)?,
+            Action::Calc => self.calc_selections(window)?,
+            Action::Case(case) => self.convert_case(window, case),
+            Action::RecordMacro => self.record_macro(macro_register(register)),
+            Action::ReplayMacro => {
+                for _ in 0..times {
+                    self.replay_macro(macro_register(register))?;
+                }
+            }
+            Action::Indent { deeper } => self.indent_lines(window, deeper, times),
+            Action::Join => self.join_lines(window),
+            Action::SelectLines { up } => self.select_lines(window, up, times),
+            Action::RotateSelections { forward } => self.rotate_selections(window, forward),
+            Action::RotateContents { forward } => self.rotate_contents(window, forward),
+            Action::CopySelections { down } => self.copy_selections(window, down, times),
+            Action::ToggleBookmark => self.toggle_bookmark(window),
+            Action::SetMark => self.set_mode(window, Mode::SetMark),
+            Action::JumpMark => self.set_mode(window, Mode::JumpMark),
+            Action::SaveSelections => self.save_selections(window, register),
+            Action::RestoreSelections => {
+                self.restore_selections(window, register, Combine::Replace)?
+            }
+            Action::CombineSelections => {
+                self.set_mode(window, Mode::CombineSelections { register })
+            }
+            Action::Surround => self.set_mode(window, Mode::Surround),
+            Action::SurroundAdd => self.set_mode(window, Mode::SurroundAdd),
+            Action::SurroundDelete => self.set_mode(window, Mode::SurroundDelete),
+            Action::SurroundChange => self.set_mode(window, Mode::SurroundChange { from: None }),
+            Action::Bookmark { forward } => self.jump_bookmark(window, forward)?,
+            Action::Result { forward } => self.jump_result(forward)?,
+            Action::LastEdit => self.goto_last_edit(window)?,
+            Action::OpenAtCursor => self.open_at_cursor(window)?,
+            Action::Definition => self.request_definition(window)?,
+            Action::Hover => self.request_hover(window)?,
+            Action::Align(view) => {
+                let scrolloff = self.config.scrolloff;
+                self.windows[window].align_view(view, scrolloff);
+            }
+            Action::Split(orientation) => self.split(orientation),
+            Action::Focus(direction) => self.focus_neighbor(direction)?,
+            Action::SelectObject(object) => {
+                let around = matches!(mode, Mode::Object { around: true });
+                self.select_objects(window, object, around);
+            }
+        }
+    }
+
+    #[throws]
+    /// Clicks focus and place the cursor, drags extend the primary selection
+    /// or move split separators and the wheel scrolls the window under the
+    /// pointer.
+    fn mouse_event(&mut self, event: MouseEvent) {
+        let (x, y) = match event {
+            MouseEvent::Press(_, x, y) | MouseEvent::Hold(x, y) | MouseEvent::Release(x, y) => {
+                (x, y)
+            }
+        };
+        let hit = self
+            .hits
+            .iter()
+            .find(|(rect, _)| rect.contains(Point { x, y }))
+            .map(|&(_, hit)| hit);
+        match (event, hit) {
+            (MouseEvent::Press(MouseButton::Left, ..), Some(Hit::Tab(window_id))) => {
+                self.focus(window_id)
+            }
+            (MouseEvent::Press(MouseButton::Left, ..), Some(Hit::Window(window_id))) => {
+                self.focus(window_id);
+                let window = &mut self.windows[window_id];
+                let content = &self.buffers[window.buffer].content;
+                if let Some(position) = window.position_at(content, x, y) {
+                    window.selections = vec![Selection {
+                        start: position,
+                        end: position,
+                    }]
+                    .into();
+                }
+            }
+            (MouseEvent::Press(MouseButton::Left, ..), Some(Hit::Mode)) => self.cmd(&["help"])?,
+            (MouseEvent::Press(MouseButton::Left, ..), Some(Hit::Separator(region))) => {
+                self.resizing = Some((region, Point { x, y }));
+            }
+            (MouseEvent::Hold(..), _) if self.resizing.is_some() => {
+                let (region, from) = self.resizing.unwrap();
+                if let Some(at) = self.layout.drag(region, from, Point { x, y }) {
+                    self.resizing = Some((region, at));
+                    self.editor_dirty = true;
+                }
+            }
+            (MouseEvent::Release(..), _) => self.resizing = None,
+            (MouseEvent::Hold(..), _) => {
+                let window = &mut self.windows[self.focused];
+                let content = &self.buffers[window.buffer].content;
+                if let Some(position) = window.position_at(content, x, y) {
+                    window.selections.truncate(1);
+                    window.selections[SelectionId(0)].end = position;
+                }
+            }
+            (MouseEvent::Press(button, ..), Some(Hit::Window(window_id))) => {
+                let lines = match button {
+                    MouseButton::WheelUp => -3,
+                    MouseButton::WheelDown => 3,
+                    _ => return,
+                };
+                let window = &mut self.windows[window_id];
+                window.scroll_by(&self.buffers[window.buffer].content, lines);
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs a command line; a final `>x` argument sends its output to
+    /// register `x` and a final `>` to a new scratch buffer. A `!` command is
+    /// passed to the shell as it is, since `>` redirects there too.
+    #[throws]
+    fn command_line(&mut self, command: &str) {
+        let command = command.trim();
+        if let Some(shell) = command.strip_prefix('!') {
+            return self.cmd(&["!", shell.trim()])?;
+        }
+        let (name, rest) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        if self.commands.get(name).is_some_and(CommandDesc::raw) {
+            let rest = rest.trim_start();
+            return match rest {
+                "" => self.cmd(&[name])?,
+                rest => self.cmd(&[name, rest])?,
+            };
+        }
+        let last = command.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let mut redirect_chars = command[last..].chars();
+        let redirect = match (
+            redirect_chars.next(),
+            redirect_chars.next(),
+            redirect_chars.next(),
+        ) {
+            (Some('>'), None, _) => Some(Redirect::Buffer),
+            (Some('>'), Some(name), None) => Some(Redirect::Register(
+                register_arg(&[&name.to_string()])?.context("no register given")?,
+            )),
+            _ => None,
+        };
+        let command = match redirect {
+            Some(_) => command[..last].trim_end(),
+            None => command,
+        };
+        let words = if command.starts_with(|c: char| c.is_ascii_digit()) {
+            vec![String::from("goto"), command.to_owned()]
+        } else {
+            shlex(command).ok_or_else(|| format_err!("failed to parse command '{}'", command))?
+        };
+        trace!("command: {:?}", words);
+        let command = words.iter().map(|x| &**x).collect::<Vec<&str>>();
+        if redirect.is_none() {
+            return self.cmd(&command)?;
+        }
+        self.captured = Some(String::new());
+        let result = self.cmd(&command);
+        let captured = self.captured.take().unwrap_or_default();
+        result?;
+        match redirect {
+            Some(Redirect::Register(name)) => {
+                let register = Register {
+                    values: vec![captured],
+                    block: false,
+                };
+                self.set_register(name, register);
+            }
+            Some(Redirect::Buffer) => {
+                let name = format!("*{}*", command.first().copied().unwrap_or("output"));
+                self.open(Buffer::new(name, Rope::from(captured), None));
+            }
+            None => {}
+        }
+    }
+
+    #[throws]
+    fn unicode_event(&mut self, input: UnicodeInput, event: Event) {
+        let key = match event {
+            Event::Key(Key::Esc) => return,
+            Event::Key(key) => key,
+            _ => {
+                self.windows[self.focused].unicode = Some(input);
+                return;
+            }
+        };
+        match (input, key) {
+            (UnicodeInput::Codepoint(mut hex), Key::Backspace) => {
+                hex.pop();
+                self.windows[self.focused].unicode = Some(UnicodeInput::Codepoint(hex));
+            }
+            (UnicodeInput::Codepoint(mut hex), Key::Char(c)) if c.is_ascii_hexdigit() => {
+                hex.push(c);
+                if hex.len() < 6 {
+                    self.windows[self.focused].unicode = Some(UnicodeInput::Codepoint(hex));
+                } else {
+                    self.type_codepoint(&hex)?;
+                }
+            }
+            (UnicodeInput::Codepoint(hex), Key::Char('\n'))
+            | (UnicodeInput::Codepoint(hex), Key::Char(' ')) => {
+                self.type_codepoint(&hex)?;
+            }
+            (UnicodeInput::Codepoint(_), key) => {
+                throw!(format_err!("invalid codepoint digit {:?}", key));
+            }
+            (UnicodeInput::Digraph(None), Key::Char(c)) => {
+                self.windows[self.focused].unicode = Some(UnicodeInput::Digraph(Some(c)));
+            }
+            (UnicodeInput::Digraph(Some(first)), Key::Char(second)) => {
+                let c = unicode::digraph(first, second)
+                    .ok_or_else(|| format_err!("unknown digraph '{}{}'", first, second))?;
+                self.type_char(self.focused, c)?;
+            }
+            (UnicodeInput::Digraph(_), key) => {
+                throw!(format_err!("invalid digraph key {:?}", key));
+            }
         }
     }
 
+    #[throws]
+    fn type_codepoint(&mut self, hex: &str) {
+        let c =
+            unicode::codepoint(hex).ok_or_else(|| format_err!("invalid codepoint '{}'", hex))?;
+        self.type_char(self.focused, c)?;
+    }
+
     #[throws]
     fn signal(&mut self, signal: c_int) {
         info!("received signal: {}", signal);
-        match signal {
-            signal_hook::SIGWINCH => self.draw()?,
-            _ => {}
+        if signal == signal_hook::SIGWINCH {
+            self.draw()?;
         }
     }
 
     #[throws]
     fn draw(&mut self) {
         let (width, height) = terminal_size()?;
+        if self.screen.begin((width, height)) {
+            self.editor_dirty = true;
+        }
+        self.hits.clear();
+        self.cursor = None;
 
-        let region = Rect {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let screen = Rect {
             start: Point { x: 1, y: 1 },
-            end: Point { x: width, y: 1 },
-        };
-        self.draw_tabs(region)?;
-
-        let region = Rect {
-            start: Point { x: 1, y: 2 },
             end: Point {
                 x: width,
-                y: height - 1,
+                y: height,
             },
         };
-        self.draw_window(self.focused, region)?;
-
-        let region = Rect {
-            start: Point { x: 1, y: height },
+        if height < 3 {
+            // There's no room for a window between the tabs and status line.
+            self.screen.fill(screen, Style::default());
+            self.screen.print(
+                screen.start,
+                screen.end.x,
+                "terminal too small",
+                Style::default(),
+            );
+            return self.screen.flush(&mut self.output)?;
+        }
+        let (tabs, rest) = screen.split_horizontal(Size::Fixed(1));
+        self.draw_tabs(tabs)?;
+        let rest = rest.unwrap();
+        let (region, status) = rest.split_horizontal(Size::Fixed(rest.rows() - 1));
+        let status = status.unwrap();
+        let above = Rect {
+            start: Point {
+                x: status.start.x,
+                y: status.start.y - 1,
+            },
             end: Point {
-                x: width,
-                y: height,
+                x: status.end.x,
+                y: status.start.y - 1,
             },
         };
-        self.draw_status(region)?;
+
+        let (windows, separators) = self.layout.regions(region);
+        for (window_id, region) in windows {
+            self.draw_window(window_id, region)?;
+        }
+        for separator in separators {
+            self.hits.push((separator, Hit::Separator(region)));
+            let c = if separator.width() == 0 { '│' } else { '─' };
+            for y in separator.range_y() {
+                for x in separator.range_x() {
+                    self.screen.set(Point { x, y }, c, Style::default());
+                }
+            }
+        }
+        self.editor_dirty = false;
+
+        self.draw_words(region);
+        self.draw_hover(region);
+        self.draw_completion(above)?;
+        self.draw_status(status)?;
+        self.screen.flush(&mut self.output)?;
+        self.draw_cursor()?;
+
+        if self.config.title {
+            let buffer = &self.buffers[self.windows[self.focused].buffer];
+            // A name with control characters could end the sequence early.
+            let name = buffer.name.replace(char::is_control, "");
+            let title = format!(
+                "{}{} - edot",
+                name,
+                if buffer.modified { " [+]" } else { "" }
+            );
+            if self.title.as_ref() != Some(&title) {
+                write!(self.output, "\x1b]2;{}\x07", title)?;
+                self.title = Some(title);
+            }
+        }
 
         self.output.flush()?;
     }
 
+    /// Shows the terminal cursor on the primary selection in a mode color,
+    /// or red in read-only buffers, while not in a prompt, which draws its own.
+    #[throws]
+    fn draw_cursor(&mut self) {
+        let window = &self.windows[self.focused];
+        let read_only = self.buffers[window.buffer].read_only;
+        let color = window
+            .mode
+            .cursor_color()
+            .map(|color| if read_only { "red" } else { color });
+        let cursor = self.cursor.filter(|_| self.config.cursor_color);
+        match (cursor, color) {
+            (Some(point), Some(color)) => {
+                if self.cursor_color != Some(color) {
+                    write!(self.output, "\x1b]12;{}\x07", color)?;
+                    self.cursor_color = Some(color);
+                }
+                write!(self.output, "{}{}", point.goto(), cursor::Show)?;
+            }
+            _ => write!(self.output, "{}", cursor::Hide)?,
+        }
+    }
+
     #[throws]
     fn draw_tabs(&mut self, region: Rect) {
-        write!(self.output, "{}{}", region.start.goto(), clear::CurrentLine)?;
+        self.screen.fill(region, Style::default());
+        let mut x = region.start.x;
         for window_id in (0..self.windows.len()).map(WindowId) {
             let window = &self.windows[window_id];
             let buffer = &self.buffers[window.buffer];
-            write!(self.output, "{} ", buffer.name)?;
+            let start = Point {
+                x,
+                y: region.start.y,
+            };
+            self.screen
+                .print(start, region.end.x, &buffer.name, Style::default());
+            let width = unicode::str_width(&buffer.name) as u16;
+            let rect = Rect {
+                start: Point {
+                    x,
+                    y: region.start.y,
+                },
+                end: Point {
+                    x: x + width,
+                    y: region.start.y,
+                },
+            };
+            self.hits.push((rect, Hit::Tab(window_id)));
+            x += width + 1;
         }
         self.tabline_dirty = false;
     }
 
+    /// Lists command completion candidates on one row, the current one
+    /// inverted, while there are any.
     #[throws]
-    fn draw_status(&mut self, region: Rect) {
-        if let Some((_importance, message)) = self.message.take() {
-            write!(
-                self.output,
-                "{}{}{}{} {} {}",
-                region.start.goto(),
-                clear::CurrentLine,
-                color::Bg(color::Red),
-                color::Fg(color::White),
-                message,
-                style::Reset,
-            )?;
-        } else {
-            let mode = self.windows[self.focused].mode;
-            write!(
-                self.output,
-                "{}{}{} {:?} {}",
-                region.start.goto(),
-                clear::CurrentLine,
-                style::Invert,
-                mode,
-                style::Reset,
-            )?;
-            match mode {
-                Mode::Command => {
-                    write!(
-                        self.output,
-                        " :{}{} {}",
-                        self.windows[self.focused].command,
-                        style::Invert,
-                        style::Reset,
-                    )?;
-                }
-                _ => {}
-            }
-            self.statusline_dirty = false;
+    fn draw_completion(&mut self, region: Rect) {
+        let completion = match self.shown_completion() {
+            Some(completion) => completion,
+            None => return,
+        };
+        let candidates = &completion.candidates;
+        let columns = region.columns() as usize;
+        let width_of = |i: usize| candidates[i].chars().count() + 1;
+        // Start early enough to fill the row, but late enough to show the
+        // current candidate.
+        let mut first = completion.index.min(candidates.len());
+        let mut width = (first..candidates.len()).next().map_or(0, width_of);
+        while first > 0 && width + width_of(first - 1) <= columns {
+            first -= 1;
+            width += width_of(first);
         }
-    }
-
-    #[throws]
-    fn draw_window(&mut self, window_id: WindowId, region: Rect) {
-        // TODO: draw a block where the next character will go in insert mode
-        let window = &self.windows[window_id];
-        let buffer = &self.buffers[window.buffer];
-        let mut lines = buffer.content.lines_at(window.top.zero_based()).enumerate();
-        let mut range_y = region.range_y();
-        'outer: while let Some(y) = range_y.next() {
-            write!(self.output, "{}{}", cursor::Goto(1, y), clear::CurrentLine)?;
-            if let Some((line, text)) = lines.next() {
-                let mut chars = text.chars().enumerate();
-                let mut col = 0;
-                while let Some((file_col, mut c)) = chars.next() {
-                    if col == region.width() as usize + 1 {
-                        write!(self.output, "\r\n{}", clear::CurrentLine)?;
-                        if range_y.next().is_none() {
-                            break 'outer;
-                        }
-                        col = 0;
-                    }
-                    let pos = Position {
-                        line: Line::from_zero_based(line),
-                        column: Column::from_zero_based(file_col),
-                    };
-                    if c == '\n' {
-                        c = '␤';
-                    }
-                    // TODO: special case tab rendering
-                    if window
-                        .selections
-                        .iter()
-                        .map(|s| s.valid(&buffer.content))
-                        .any(|s| s.contains(pos))
-                    {
-                        write!(self.output, "{}{}{}", style::Invert, c, style::Reset)?;
-                    } else {
-                        write!(self.output, "{}", c)?;
-                    }
-                    col += 1;
-                }
+        let mut cells = Vec::new();
+        let mut width = 0;
+        for (i, candidate) in candidates.iter().enumerate().skip(first) {
+            width += candidate.chars().count() + 1;
+            if width > region.columns() as usize {
+                break;
             }
+            let style = if i == completion.index {
+                Style::INVERT
+            } else {
+                Style::default()
+            };
+            cells.push((candidate.clone(), style));
+        }
+        self.screen.fill(region, Style::default());
+        self.screen.overlay(region.start.y);
+        let mut x = region.start.x;
+        for (candidate, style) in cells {
+            let start = Point {
+                x: x + 1,
+                y: region.start.y,
+            };
+            x = self.screen.print(start, region.end.x, &candidate, style);
         }
     }
 
-    pub fn show_message(&mut self, importance: Importance, message: String) {
-        self.message = Some((importance, message));
+    /// Lists word completions in a popup below the primary cursor, or above
+    /// it if there is no room, starting at the word being completed.
+    fn draw_words(&mut self, region: Rect) {
+        let window = &self.windows[self.focused];
+        let (words, cursor) = match (&window.words, self.cursor) {
+            (Some(words), Some(cursor)) => (words, cursor),
+            _ => return,
+        };
+        let content = &self.buffers[window.buffer].content;
+        let at = self.cursor_char(self.focused, SelectionId(0));
+        let typed = content.slice(word_start(content, at)..at).to_string();
+        let first = words
+            .index
+            .map_or(0, |index| (index + 1).saturating_sub(WORDS_ROWS));
+        let lines = words
+            .candidates
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(WORDS_ROWS)
+            .map(|(i, word)| {
+                let style = if words.index == Some(i) {
+                    Style::INVERT
+                } else {
+                    POPUP
+                };
+                (word.clone(), style)
+            })
+            .collect::<Vec<_>>();
+        let at = Point {
+            x: cursor
+                .x
+                .saturating_sub(unicode::str_width(&typed) as u16 + 1),
+            y: cursor.y,
+        };
+        self.draw_popup(region, at, &lines);
     }
 
-    pub fn quit(&mut self) {
-        self.exit.0.send(()).unwrap();
+    /// Shows the language server hover in a popup at the primary cursor,
+    /// cut to half the editor's height.
+    fn draw_hover(&mut self, region: Rect) {
+        let (hover, cursor) = match (&self.windows[self.focused].hover, self.cursor) {
+            (Some(hover), Some(cursor)) => (hover, cursor),
+            _ => return,
+        };
+        let lines = hover
+            .iter()
+            .take((region.rows() as usize / 2).max(1))
+            .map(|line| (line.replace('\t', "    "), POPUP))
+            .collect::<Vec<_>>();
+        self.draw_popup(region, cursor, &lines);
     }
 
-    pub fn set_mode(&mut self, window: WindowId, mode: Mode) {
-        self.windows[window].mode = mode;
-        match mode {
-            Mode::Normal => {}
-            Mode::Insert => {}
-            Mode::Append => {}
-            Mode::Goto { .. } => {}
-            Mode::Command => {}
+    /// Draws `lines` from column `at.x` on the rows below `at.y`, or above it
+    /// if there is no room, moved left as needed to fit in `region`.
+    fn draw_popup(&mut self, region: Rect, at: Point, lines: &[(String, Style)]) {
+        let rows = lines.len() as u16;
+        let y = if at.y + rows <= region.end.y {
+            at.y + 1
+        } else {
+            at.y.saturating_sub(rows).max(region.start.y)
+        };
+        let width = lines
+            .iter()
+            .map(|(line, _)| unicode::str_width(line) as u16 + 2)
+            .max()
+            .unwrap_or(0)
+            .min(region.columns());
+        let x = at.x.min(region.end.x + 1 - width).max(region.start.x);
+        for (row, (text, style)) in lines.iter().enumerate() {
+            let y = y + row as u16;
+            let line = Rect {
+                start: Point { x, y },
+                end: Point {
+                    x: x + width - 1,
+                    y,
+                },
+            };
+            self.screen.fill(line, *style);
+            self.screen.overlay(y);
+            self.screen
+                .print(Point { x: x + 1, y }, line.end.x, text, *style);
         }
     }
 
-    pub fn selections(&self, window: WindowId) -> impl Iterator<Item = SelectionId> {
-        let window = &self.windows[window];
-        (0..window.selections.len()).map(SelectionId)
-    }
-
-    pub fn insert_char_before(&mut self, window_id: WindowId, selection_id: SelectionId, c: char) {
-        let window = &mut self.windows[window_id];
-        let buffer = &mut self.buffers[window.buffer];
-        let selection = &mut window.selections[selection_id];
-        selection.start.insert_char(&mut buffer.content, c);
-    }
-
-    pub fn insert_char_after(&mut self, window_id: WindowId, selection_id: SelectionId, c: char) {
-        let window = &mut self.windows[window_id];
-        let buffer = &mut self.buffers[window.buffer];
-        let selection = &mut window.selections[selection_id];
-        selection.end.insert_char(&mut buffer.content, c);
+    /// The completion candidates drawn above the status line, if any.
+    fn shown_completion(&self) -> Option<&complete::Completion> {
+        let window = &self.windows[self.focused];
+        match (window.mode, &window.completion) {
+            (Mode::Command | Mode::Palette | Mode::Characters, Some(completion)) => {
+                Some(completion)
+            }
+            _ => None,
+        }
     }
 
-    #[throws(MovementError)]
-    pub fn move_selection(
-        &mut self,
-        window_id: WindowId,
-        selection_id: SelectionId,
-        movement: Movement,
-        drag: bool,
-    ) {
+    #[throws]
+    fn draw_status(&mut self, region: Rect) {
+        let end = region.end.x;
+        self.screen.fill(region, Style::default());
+        if let Some((importance, message)) = self.message.take() {
+            let style = match importance {
+                Importance::Error => Style {
+                    fg: Some(Color::White),
+                    bg: Some(Color::Red),
+                    ..Style::default()
+                },
+                Importance::Info => Style {
+                    bold: true,
+                    ..Style::default()
+                },
+            };
+            let text = format!(" {} ", message);
+            self.screen.print(region.start, end, &text, style);
+        } else {
+            let mode = self.windows[self.focused].mode;
+            let width = format!(" {:?} ", mode).len() as u16;
+            let rect = Rect {
+                start: region.start,
+                end: Point {
+                    x: region.start.x + width - 1,
+                    y: region.start.y,
+                },
+            };
+            self.hits.push((rect, Hit::Mode));
+            let mode_text = format!(" {:?} ", mode);
+            let mut at = region.start;
+            at.x = self.screen.print(at, end, &mode_text, Style::INVERT);
+            let (left, right) = self.status_segments();
+            let mut used = width as usize;
+            for segment in &left {
+                let text = format!(" {}", segment);
+                at.x = self.screen.print(at, end, &text, Style::default());
+                used += segment.chars().count() + 1;
+            }
+            if let Mode::Command
+            | Mode::Search
+            | Mode::Select
+            | Mode::Keep { .. }
+            | Mode::Pipe
+            | Mode::Palette
+            | Mode::Characters = mode
+            {
+                let window = &self.windows[self.focused];
+                let (prefix, prompt) = match mode {
+                    Mode::Search => ("/", &window.search_prompt),
+                    Mode::Select => ("select: ", &window.search_prompt),
+                    Mode::Keep { matching: true } => ("keep: ", &window.search_prompt),
+                    Mode::Keep { matching: false } => ("keep-not: ", &window.search_prompt),
+                    Mode::Pipe => ("|", &window.shell_prompt),
+                    Mode::Palette => ("command: ", &window.palette_prompt),
+                    Mode::Characters => ("character: ", &window.palette_prompt),
+                    _ => (":", &window.prompt),
+                };
+                let (before, after) = prompt.split();
+                let mut after = after.chars();
+                used += prefix.len() + prompt.text.chars().count() + 2;
+                let text = format!(" {}{}", prefix, before);
+                at.x = self.screen.print(at, end, &text, Style::default());
+                let cursor = after.next().unwrap_or(' ').to_string();
+                at.x = self.screen.print(at, end, &cursor, Style::INVERT);
+                self.screen.print(at, end, after.as_str(), Style::default());
+            }
+            let right = right.join("  ");
+            let right_width = right.chars().count() as u16 + 1;
+            if used + right_width as usize <= region.width() as usize {
+                let start = Point {
+                    x: region.end.x + 1 - right_width,
+                    y: region.start.y,
+                };
+                self.screen.print(start, end, &right, Style::default());
+            }
+            self.statusline_dirty = false;
+        }
+    }
+
+    /// The status line's segments after the mode, as the left-aligned
+    /// transient state and the right-aligned buffer details.
+    fn status_segments(&self) -> (Vec<String>, Vec<String>) {
+        let window = &self.windows[self.focused];
+        let buffer = &self.buffers[window.buffer];
+        let mut left = Vec::new();
+        let (above, below) = window.offscreen_selections();
+        if above > 0 {
+            left.push(format!("↑{}", above));
+        }
+        if below > 0 {
+            left.push(format!("↓{}", below));
+        }
+        if let Some((read, total)) = buffer.loading {
+            left.push(format!("loading {}%", read * 100 / total.max(1)));
+        }
+        if buffer.large {
+            left.push("[large]".to_owned());
+        }
+        if let Some(name) = window.register {
+            left.push(format!("\"{}", name));
+        }
+        if let Some(count) = window.count {
+            left.push(count.to_string());
+        }
+        if self.config.show_draw_time {
+            if let Some(time) = self.perf.last_draw() {
+                left.push(format!("{:.1}ms", time.as_secs_f64() * 1000.0));
+            }
+        }
+        if window.register_pending {
+            left.push("^R".to_owned());
+        }
+        if let Some((name, ..)) = &self.recording {
+            left.push(format!("recording {}", name));
+        }
+        match &window.unicode {
+            Some(UnicodeInput::Codepoint(hex)) => left.push(format!("^V {}", hex)),
+            Some(UnicodeInput::Digraph(None)) => left.push("^K".to_owned()),
+            Some(UnicodeInput::Digraph(Some(c))) => left.push(format!("^K {}", c)),
+            None => {}
+        }
+        let mut right = Vec::new();
+        if buffer.modified {
+            right.push(format!("{} [+]", buffer.name));
+        } else {
+            right.push(buffer.name.clone());
+        }
+        if buffer.read_only {
+            right.push(String::from("[ro]"));
+        }
+        if buffer.format.encoding != Encoding::Utf8 {
+            right.push(buffer.format.encoding.name().to_owned());
+        }
+        if buffer.format.line_ending == LineEnding::Crlf {
+            right.push(String::from("crlf"));
+        }
+        if let Some(filetype) = buffer.filetype {
+            right.push(filetype.to_owned());
+        }
+        if window.selections.len() > 1 {
+            let primary = window.selections[SelectionId(0)].ordered().start;
+            let before = window
+                .selections
+                .iter()
+                .filter(|s| s.ordered().start < primary)
+                .count();
+            right.push(format!("{}/{} sel", before + 1, window.selections.len()));
+        }
+        let cursor = window.selections[SelectionId(0)].end;
+        right.push(format!(
+            "{}:{}",
+            cursor.line.one_based(),
+            cursor.column.one_based()
+        ));
+        (left, right)
+    }
+
+    #[throws]
+    fn draw_window(&mut self, window_id: WindowId, region: Rect) {
+        // TODO: draw a block where the next character will go in insert mode
+        self.hits.push((region, Hit::Window(window_id)));
         let window = &mut self.windows[window_id];
+        let buffer = &self.buffers[window.buffer];
+        let height = region.height() as usize + 1;
+        if window.follow {
+            window.height = height;
+            let last = buffer.content.len_lines().saturating_sub(1).max(1);
+            window.top = Line::from_one_based(last.saturating_sub(window.height - 1).max(1));
+        } else if window.height != height {
+            window.height = height;
+            window.scroll_to_cursor(self.config.scrolloff);
+        }
+        let end = window.top.zero_based() + height;
         let buffer = &mut self.buffers[window.buffer];
-        let selection = &mut window.selections[selection_id];
-        selection.end.move_to(&buffer.content, movement)?;
-        if !drag {
-            selection.start = selection.end;
+        let highlighted = buffer.highlighted(&self.config);
+        if highlighted {
+            buffer.highlight(end);
+        }
+        let window = &self.windows[window_id];
+        let buffer = &self.buffers[window.buffer];
+        let mut drawn = Drawn {
+            region,
+            revision: buffer.effects.revision(),
+            filetype: buffer.filetype,
+            top: window.top,
+            left: window.left,
+            selections: window.selections.to_vec(),
+            focused: window_id == self.focused,
+            matches: window
+                .search_state
+                .as_ref()
+                .map_or_else(Vec::new, |state| state.matches.clone()),
+            bookmarks: buffer.bookmarks.clone(),
+            diagnostics: diagnostic_lines(buffer),
+        };
+        if !self.editor_dirty && window.drawn.as_ref() == Some(&drawn) && self.screen.intact(region)
+        {
+            if drawn.focused {
+                self.cursor = window.cursor;
+            }
+            return;
+        }
+        let signs = !buffer.bookmarks.is_empty() || !drawn.diagnostics.is_empty();
+        let line_numbers = self.config.line_numbers;
+        let number_width = match line_numbers {
+            LineNumbers::None => 0,
+            _ => Line::last(&buffer.content).one_based().to_string().len() + 1,
+        };
+        // Rows continuing a wrapped line are marked in a gutter cell of their
+        // own, after the line numbers.
+        let wraps = self.config.wrap != Wrap::None;
+        let gutter = usize::from(signs) + number_width + usize::from(wraps);
+        let mut text_width = (region.width() as usize + 1).saturating_sub(gutter).max(1);
+        if self.config.wrap_column > 0 {
+            text_width = text_width.min(self.config.wrap_column);
+        }
+        let window = &mut self.windows[window_id];
+        window.text_width = text_width;
+        match self.config.wrap {
+            Wrap::None => window.scroll_sideways(&buffer.content),
+            _ => window.left = 0,
+        }
+        let left = window.left;
+        drawn.left = left;
+        let marker = Style::fg(Color::LightBlack);
+        self.screen.fill(region, Style::default());
+        let window = &self.windows[window_id];
+        let cursor_line = window.cursor_line();
+        let primary = window.selections[SelectionId(0)].valid(&buffer.content).end;
+        let mut lines = buffer.content.lines_at(window.top.zero_based()).enumerate();
+        let mut range_y = region.range_y();
+        let mut rows = Vec::new();
+        let mut cursor = None;
+        'outer: while let Some(mut y) = range_y.next() {
+            let (line, slice) = match lines.next() {
+                Some(line) => line,
+                None => continue,
+            };
+            let mut at = Point {
+                x: region.start.x,
+                y,
+            };
+            let line_index = window.top.zero_based() + line;
+            rows.push(Row {
+                y,
+                line: line_index,
+                column: 0,
+            });
+            if signs {
+                let start = buffer.content.line_to_char(line_index);
+                let diagnostic = drawn
+                    .diagnostics
+                    .binary_search_by_key(&line_index, |&(line, _)| line)
+                    .map(|i| drawn.diagnostics[i].1);
+                if let Ok(severity) = diagnostic {
+                    let color = match severity {
+                        Severity::Error => Color::Red,
+                        Severity::Warning => Color::Yellow,
+                        Severity::Information | Severity::Hint => Color::Blue,
+                    };
+                    self.screen.set(at, '●', Style::fg(color));
+                } else if buffer.bookmarks.binary_search(&start).is_ok() {
+                    self.screen.set(at, '▶', Style::fg(Color::Blue));
+                }
+                at.x += 1;
+            }
+            if line_numbers != LineNumbers::None {
+                let number = match line_numbers {
+                    LineNumbers::Relative if line_index != cursor_line => {
+                        line_index.abs_diff(cursor_line)
+                    }
+                    _ => line_index + 1,
+                };
+                let style = if line_index == cursor_line {
+                    Style {
+                        bold: true,
+                        ..Style::default()
+                    }
+                } else {
+                    Style::fg(Color::LightBlack)
+                };
+                let text = format!("{:>width$} ", number, width = number_width - 1);
+                self.screen.print(at, region.end.x, &text, style);
+            }
+            let line_text = slice.to_string();
+            let urls = url::find(&line_text);
+            let mut starts = row_starts(slice, text_width, self.config.wrap)
+                .into_iter()
+                .skip(1)
+                .peekable();
+            let mut col = gutter;
+            let mut last = at;
+            // Display column of the next char, and whether it's left of the
+            // window when scrolled sideways.
+            let mut display_col = 0;
+            let mut hidden = false;
+            let mut clipped = false;
+            for (file_col, mut c) in slice.chars().enumerate() {
+                if c == '\n' {
+                    c = '␤';
+                }
+                let char_width = unicode::width(c);
+                if self.config.wrap == Wrap::None && char_width > 0 {
+                    hidden = display_col < left;
+                    clipped |= hidden;
+                    display_col += char_width;
+                    if !hidden && display_col - left > text_width {
+                        let x = region.start.x + (gutter + text_width - 1) as u16;
+                        self.screen.set(Point { x, y }, '>', marker);
+                        break;
+                    }
+                    col = gutter + display_col.saturating_sub(left + char_width);
+                }
+                if hidden {
+                    continue;
+                }
+                if starts.next_if_eq(&file_col).is_some() {
+                    y = match range_y.next() {
+                        Some(y) => y,
+                        None => break 'outer,
+                    };
+                    col = gutter;
+                    rows.push(Row {
+                        y,
+                        line: line_index,
+                        column: file_col,
+                    });
+                    if wraps {
+                        let x = region.start.x + gutter as u16 - 1;
+                        self.screen.set(Point { x, y }, '↪', marker);
+                    }
+                }
+                let mut point = Point {
+                    x: region.start.x + col as u16,
+                    y,
+                };
+                // Combining marks go on the char before them, or on a space
+                // when there's none on the row.
+                let lone = char_width == 0 && col == gutter;
+                let pos = Position {
+                    line: window.top + line,
+                    column: Column::from_zero_based(file_col),
+                };
+                if pos == primary {
+                    cursor = Some(if char_width == 0 && !lone {
+                        last
+                    } else {
+                        point
+                    });
+                }
+                let mut style = Style::default();
+                let highlight = Some(&buffer.highlights)
+                    .filter(|_| highlighted)
+                    .and_then(|highlights| highlights.style(line_index, file_col));
+                if let Some(highlight) = highlight {
+                    style.fg = Some(highlight.color());
+                }
+                let url = urls.iter().find(|url| url.contains(&file_col));
+                style.underline = url.is_some();
+                let link = url.filter(|_| self.config.hyperlinks).map(|url| {
+                    let target = line_text.chars().skip(url.start).take(url.len());
+                    Rc::from(target.collect::<String>())
+                });
+                let char_index = buffer.content.line_to_char(line_index) + file_col;
+                let matched = window.search_state.as_ref().is_some_and(|state| {
+                    let i = state
+                        .matches
+                        .partition_point(|range| range.end <= char_index);
+                    state
+                        .matches
+                        .get(i)
+                        .is_some_and(|range| range.contains(&char_index))
+                });
+                if matched {
+                    style.bg = Some(Color::Yellow);
+                }
+                // TODO: special case tab rendering
+                let selected = window
+                    .selections
+                    .iter()
+                    .map(|s| s.valid(&buffer.content))
+                    .position(|s| s.contains(pos));
+                match selected {
+                    Some(0) => style.invert = true,
+                    Some(_) => {
+                        style.fg = Some(Color::White);
+                        style.bg = Some(Color::LightBlack);
+                    }
+                    None => {}
+                }
+                if lone {
+                    self.screen.set(point, ' ', style);
+                    point.x += 1;
+                    col += 1;
+                }
+                if char_width > 0 {
+                    last = point;
+                }
+                self.screen.set_link(point, c, style, link);
+                col += char_width;
+            }
+            if clipped {
+                let x = region.start.x + gutter as u16;
+                self.screen.set(Point { x, y }, '<', marker);
+            }
+        }
+        if drawn.focused {
+            self.cursor = cursor;
+        }
+        let window = &mut self.windows[window_id];
+        window.rows = rows;
+        window.text_x = region.start.x + gutter as u16;
+        window.drawn = Some(drawn);
+        window.cursor = cursor;
+    }
+
+    /// Replaces the config with a freshly loaded one, dropping `:map` changes,
+    /// or keeps the current one if the file is invalid.
+    #[throws]
+    pub fn reload_config(&mut self) {
+        self.config = Config::load()?;
+        self.tabline_dirty = true;
+        self.editor_dirty = true;
+        self.statusline_dirty = true;
+        self.show_message(Importance::Info, String::from("config reloaded"));
+    }
+
+    /// Runs the `make` command with `args` in the background.
+    pub fn make(&mut self, args: &[&str]) {
+        let mut command = self.config.make.clone();
+        for arg in args {
+            command.push(' ');
+            command.push_str(&shlex::quote(arg));
+        }
+        self.show_message(Importance::Info, format!("running {}", command));
+        let sender = self.make.0.clone();
+        thread::spawn(move || {
+            let output = process::Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .stdin(Stdio::null())
+                .output();
+            sender.send(Made { command, output }).unwrap();
+        });
+    }
+
+    /// Fills the location list from a finished `:make`.
+    #[throws]
+    fn made(&mut self, made: Made) {
+        let Made { command, output } = made;
+        let output = output.with_context(|| format!("failed to run {}", command))?;
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        let locations = quickfix::parse(&text, &std::env::current_dir()?);
+        if !locations.is_empty() {
+            self.set_locations(locations)?;
+        } else if output.status.success() {
+            self.show_message(Importance::Info, format!("{} succeeded", command));
+        } else {
+            throw!(format_err!("{} failed: {}", command, output.status));
+        }
+    }
+
+    fn append(&mut self, appended: Appended) {
+        let buffer_id = appended.buffer;
+        let buffer = &mut self.buffers[buffer_id];
+        if buffer.follow.is_none() {
+            return;
+        }
+        self.editor_dirty = true;
+        if !appended.reset {
+            let end = buffer.content.len_chars();
+            buffer.content.insert(end, &appended.text);
+            buffer
+                .effects
+                .record(end..end, appended.text.chars().count());
+            return;
+        }
+        let mut content = Rope::from(appended.text);
+        if content.len_chars() == 0 {
+            content.insert_char(0, '\n');
+        }
+        let last = content.len_chars() - 1;
+        for window in self.windows.iter_mut().filter(|w| w.buffer == buffer_id) {
+            for selection in window.selections.iter_mut() {
+                let valid = selection.valid(&buffer.content);
+                let moved = |p: Position| {
+                    Position::from_char(&content, p.char_of(&buffer.content).min(last))
+                };
+                selection.start = moved(valid.start);
+                selection.end = moved(valid.end);
+            }
+        }
+        let old = buffer.content.len_chars();
+        buffer.content = content;
+        buffer.effects.record(0..old, buffer.content.len_chars());
+    }
+
+    /// Updates a buffer being loaded, swapping in its text once it is read.
+    #[throws]
+    fn loaded(&mut self, loaded: Loaded) {
+        self.statusline_dirty = true;
+        let (buffer_id, content) = match loaded {
+            Loaded::Progress { buffer, read } => {
+                if let Some((_, total)) = self.buffers[buffer].loading {
+                    self.buffers[buffer].loading = Some((read, total));
+                }
+                return;
+            }
+            Loaded::Done { buffer, content } => (buffer, content),
+        };
+        let buffer = &mut self.buffers[buffer_id];
+        buffer.loading = None;
+        let (content, format) = match content {
+            Ok(content) => content,
+            Err(err) => {
+                // Writing the placeholder text would replace the file.
+                buffer.path = None;
+                buffer.read_only = true;
+                self.editor_dirty = true;
+                throw!(Error::from(err).context(format!("failed to read {}", buffer.name)));
+            }
+        };
+        let old = buffer.content.len_chars();
+        buffer.content = content;
+        buffer.format = format;
+        buffer.mtime = buffer.path.as_deref().and_then(modified_time);
+        if buffer.content.len_chars() == 0 {
+            buffer.content.insert_char(0, '\n');
+        }
+        buffer.effects.record(0..old, buffer.content.len_chars());
+        if buffer.content.len_bytes() > self.config.large_file_size {
+            buffer.large = true;
+            buffer.filetype = None;
+        }
+        self.warn_latin1(buffer_id);
+        self.attach(buffer_id);
+        for window_id in (0..self.windows.len()).map(WindowId) {
+            if self.windows[window_id].buffer == buffer_id {
+                self.restore_position(window_id);
+            }
         }
+        self.editor_dirty = true;
+    }
+
+    pub fn open(&mut self, buffer: Buffer) -> WindowId {
+        let buffer_id = BufferId(self.buffers.len());
+        self.buffers.push(buffer);
+        let window_id = WindowId(self.windows.len());
+        self.windows.push(Window::new(buffer_id));
+        self.focus(window_id);
+        self.attach(buffer_id);
+        self.watch_file(buffer_id);
+        window_id
+    }
+
+    /// Watches a buffer's file for changes on disk.
+    fn watch_file(&mut self, buffer_id: BufferId) {
+        if let (Some(watcher), Some(path)) = (&mut self.watcher, &self.buffers[buffer_id].path) {
+            if let Err(err) = watcher.add(path) {
+                warn!("failed to watch {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    /// Opens a file's buffer in the language server configured for its
+    /// filetype, starting the server first if it isn't running.
+    fn attach(&mut self, buffer_id: BufferId) {
+        let buffer = &self.buffers[buffer_id];
+        let (path, filetype) = match (&buffer.path, buffer.filetype) {
+            (Some(path), Some(filetype))
+                if buffer.document.is_none() && !buffer.large && buffer.loading.is_none() =>
+            {
+                (path.clone(), filetype)
+            }
+            _ => return,
+        };
+        let command = match self.config.lsp.get(filetype) {
+            Some(command) => command.clone(),
+            None => return,
+        };
+        let root = env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+        if !self.servers.contains_key(filetype) {
+            let server = lsp::Server::spawn(filetype, &command, &root, self.lsp.0.clone());
+            let server = server
+                .map_err(|err| {
+                    error!("{:#}", err);
+                    self.show_message(Importance::Error, format!("{:#}", err));
+                })
+                .ok();
+            self.servers.insert(filetype, server);
+        }
+        if let Some(Some(server)) = self.servers.get_mut(filetype) {
+            let buffer = &mut self.buffers[buffer_id];
+            let document = lsp::Document::open(
+                server,
+                &root.join(path),
+                filetype,
+                &buffer.content,
+                buffer.effects.revision(),
+            );
+            buffer.document = Some(document);
+        }
+    }
+
+    /// Closes a buffer in its language server, if it is open there.
+    fn detach(&mut self, buffer_id: BufferId) {
+        let buffer = &mut self.buffers[buffer_id];
+        buffer.diagnostics.clear();
+        if let Some(server) = server_of(&mut self.servers, buffer) {
+            let document = buffer.document.as_ref().unwrap();
+            let params = object(vec![("textDocument", document.identifier())]);
+            server.notify("textDocument/didClose", params);
+        }
+        buffer.document = None;
+    }
+
+    /// Sends each buffer's changes to its language server.
+    fn sync_documents(&mut self) {
+        for buffer in self.buffers.iter_mut() {
+            if let Some(server) = server_of(&mut self.servers, buffer) {
+                let document = buffer.document.as_mut().unwrap();
+                document.sync(server, &buffer.content, &buffer.effects);
+            }
+        }
+    }
+
+    /// Asks the language server of the window's buffer about the primary
+    /// cursor, after sending it the latest changes. Returns whether there is
+    /// a server to ask.
+    fn request_at_cursor(&mut self, window_id: WindowId, method: &str, pending: Pending) -> bool {
+        self.sync_documents();
+        let at = self.cursor_char(window_id, SelectionId(0));
+        let buffer = &self.buffers[self.windows[window_id].buffer];
+        let server = match server_of(&mut self.servers, buffer) {
+            Some(server) => server,
+            None => return false,
+        };
+        let document = buffer.document.as_ref().unwrap();
+        let params = object(vec![
+            ("textDocument", document.identifier()),
+            ("position", lsp::position(&buffer.content, at)),
+        ]);
+        server.request(method, params, pending);
+        true
+    }
+
+    #[throws]
+    pub fn request_definition(&mut self, window_id: WindowId) {
+        let pending = Pending::Definition(window_id);
+        if !self.request_at_cursor(window_id, "textDocument/definition", pending) {
+            throw!(format_err!("no language server for this buffer"));
+        }
+    }
+
+    #[throws]
+    pub fn request_hover(&mut self, window_id: WindowId) {
+        let pending = Pending::Hover(window_id);
+        if !self.request_at_cursor(window_id, "textDocument/hover", pending) {
+            throw!(format_err!("no language server for this buffer"));
+        }
+    }
+
+    /// Handles a message from a language server: a response to one of our
+    /// requests, a request of its own or a notification.
+    #[throws]
+    fn lsp_message(&mut self, incoming: Incoming) {
+        let filetype = incoming.filetype;
+        let message = match incoming.message {
+            Some(message) => message,
+            None => {
+                if let Some(Some(_)) = self.servers.insert(filetype, None) {
+                    for buffer_id in (0..self.buffers.len()).map(BufferId) {
+                        if self.buffers[buffer_id].filetype == Some(filetype) {
+                            self.buffers[buffer_id].document = None;
+                            self.buffers[buffer_id].diagnostics.clear();
+                        }
+                    }
+                    self.editor_dirty = true;
+                    throw!(format_err!("the {} language server exited", filetype));
+                }
+                return;
+            }
+        };
+        let server = match self.servers.get_mut(filetype) {
+            Some(Some(server)) => server,
+            _ => return,
+        };
+        let method = message.get("method").and_then(Value::as_str);
+        let params = message.get("params").unwrap_or(&Value::Null);
+        match (message.get("id"), method) {
+            (Some(id), Some(method)) => {
+                // Settings are asked for per item; we have none to give.
+                let result = match params.get("items").and_then(Value::as_array) {
+                    Some(items) if method == "workspace/configuration" => {
+                        Value::from(vec![Value::Null; items.len()])
+                    }
+                    _ => Value::Null,
+                };
+                server.respond(id.clone(), result);
+            }
+            (Some(id), None) => {
+                let pending = match id.as_u64().and_then(|id| server.take_pending(id)) {
+                    Some(pending) => pending,
+                    None => return,
+                };
+                match (message.get("error"), pending) {
+                    (Some(error), Pending::Initialize) => {
+                        self.servers.insert(filetype, None);
+                        let text = error.get("message").and_then(Value::as_str);
+                        throw!(format_err!(
+                            "the {} language server failed to start: {}",
+                            filetype,
+                            text.unwrap_or("unknown error")
+                        ));
+                    }
+                    (Some(_), Pending::Completion { .. }) => {
+                        self.lsp_response(filetype, pending, &Value::Null)?
+                    }
+                    (Some(error), _) => {
+                        let text = error.get("message").and_then(Value::as_str);
+                        throw!(format_err!("{}", text.unwrap_or("request failed")));
+                    }
+                    (None, _) => {
+                        let result = message.get("result").unwrap_or(&Value::Null);
+                        self.lsp_response(filetype, pending, result)?;
+                    }
+                }
+            }
+            (None, Some("textDocument/publishDiagnostics")) => {
+                let uri = params.get("uri").and_then(Value::as_str);
+                for buffer in self.buffers.iter_mut() {
+                    if let Some(document) = &buffer.document {
+                        if Some(&*document.uri) == uri {
+                            buffer.diagnostics = lsp::diagnostics(params, document);
+                        }
+                    }
+                }
+                self.editor_dirty = true;
+            }
+            (None, Some("window/showMessage")) => {
+                let importance = match params.get("type").and_then(Value::as_u64) {
+                    Some(1) => Importance::Error,
+                    _ => Importance::Info,
+                };
+                let text = params.get("message").and_then(Value::as_str);
+                self.show_message(importance, text.unwrap_or_default().to_owned());
+            }
+            (None, Some("window/logMessage")) => {
+                let text = params.get("message").and_then(Value::as_str);
+                info!("{} server: {}", filetype, text.unwrap_or_default());
+            }
+            (None, _) => {}
+        }
+    }
+
+    #[throws]
+    fn lsp_response(&mut self, filetype: &'static str, pending: Pending, result: &Value) {
+        match pending {
+            Pending::Initialize => {
+                if let Some(Some(server)) = self.servers.get_mut(filetype) {
+                    server.initialized(result);
+                }
+            }
+            Pending::Definition(window_id) => {
+                let (path, range) = lsp::location(result).context("no definition found")?;
+                // Stay in the window that asked if the definition is in its file.
+                let buffer = &self.buffers[self.windows[window_id].buffer];
+                let window_id = if buffer.path.as_deref() == path.canonicalize().ok().as_deref() {
+                    self.focus(window_id);
+                    window_id
+                } else {
+                    self.open_path(&path)?
+                };
+                let content = &self.buffers[self.windows[window_id].buffer].content;
+                let range = lsp::offsets(content, range).context("no definition found")?;
+                self.select_range(window_id, range);
+            }
+            Pending::Hover(window_id) => {
+                let text = result
+                    .get("contents")
+                    .map(lsp::hover_text)
+                    .unwrap_or_default();
+                let lines = text
+                    .trim()
+                    .lines()
+                    .filter(|line| !line.starts_with("```"))
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>();
+                if lines.is_empty() {
+                    throw!(format_err!("nothing to show here"));
+                }
+                self.windows[window_id].hover = Some(lines);
+            }
+            Pending::Completion {
+                window: window_id,
+                revision,
+                forward,
+            } => {
+                let window = &self.windows[window_id];
+                let buffer = &self.buffers[window.buffer];
+                let typing = matches!(window.mode, Mode::Insert | Mode::Append);
+                if !typing || window.words.is_some() || buffer.effects.revision() != revision {
+                    return;
+                }
+                self.open_words(window_id, lsp::completions(result))?;
+                self.cycle_words(window_id, forward);
+            }
+        }
+    }
+
+    /// Shows another buffer in an existing window, keeping its prompts.
+    pub fn show_buffer(&mut self, window_id: WindowId, buffer_id: BufferId) {
+        let window = &mut self.windows[window_id];
+        if window.buffer == buffer_id {
+            return;
+        }
+        let mut shown = Window::new(buffer_id);
+        shown.prompt = take(&mut window.prompt);
+        shown.search_prompt = take(&mut window.search_prompt);
+        *window = shown;
+        self.tabline_dirty = true;
+    }
+
+    /// Finds a buffer by number, exact name or unique part of its name.
+    #[throws]
+    pub fn find_buffer(&self, query: &str) -> BufferId {
+        if let Ok(number) = query.parse::<usize>() {
+            if (1..=self.buffers.len()).contains(&number) {
+                return BufferId(number - 1);
+            }
+        }
+        let names = self.buffers.iter().map(|buffer| &buffer.name);
+        if let Some(i) = names.clone().position(|name| *name == query) {
+            return BufferId(i);
+        }
+        let matches = names
+            .enumerate()
+            .filter(|(_, name)| name.contains(query))
+            .collect::<Vec<_>>();
+        match &*matches {
+            [(i, _)] => BufferId(*i),
+            [] => throw!(format_err!("no buffer matches '{}'", query)),
+            _ => throw!(format_err!(
+                "'{}' matches {}",
+                query,
+                matches
+                    .iter()
+                    .map(|(_, name)| &***name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Shows the next or previous buffer in the focused window.
+    pub fn cycle_buffer(&mut self, forward: bool) {
+        let len = self.buffers.len();
+        let current = self.windows[self.focused].buffer.0;
+        let next = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+        self.show_buffer(self.focused, BufferId(next));
+    }
+
+    /// Splits the focused window, focusing the new window on the same buffer.
+    pub fn split(&mut self, orientation: Orientation) {
+        let window_id = self.split_window(self.focused);
+        self.layout.split(self.focused, window_id, orientation);
+        self.focus(window_id);
+    }
+
+    /// Focuses a window, showing it in place of the focused one if hidden.
+    pub fn focus(&mut self, window_id: WindowId) {
+        if !self.layout.contains(window_id) {
+            self.layout.replace(self.focused, window_id);
+        }
+        self.focused = window_id;
+        self.tabline_dirty = true;
+    }
+
+    /// Focuses the visible window next to the focused one in direction `key`,
+    /// one of `h`, `j`, `k` and `l`.
+    #[throws]
+    pub fn focus_neighbor(&mut self, key: char) {
+        let regions = self
+            .hits
+            .iter()
+            .filter_map(|&(rect, hit)| match hit {
+                Hit::Window(window_id) => Some((window_id, rect)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let current = regions
+            .iter()
+            .find(|(window_id, _)| *window_id == self.focused)
+            .map(|&(_, rect)| rect)
+            .context("focused window isn't visible")?;
+        let overlaps = |a: (u16, u16), b: (u16, u16)| a.0 <= b.1 && b.0 <= a.1;
+        let x = (current.start.x, current.end.x);
+        let y = (current.start.y, current.end.y);
+        let neighbor = regions
+            .iter()
+            .filter(|(_, rect)| match key {
+                'h' => rect.end.x < current.start.x && overlaps(y, (rect.start.y, rect.end.y)),
+                'l' => rect.start.x > current.end.x && overlaps(y, (rect.start.y, rect.end.y)),
+                'k' => rect.end.y < current.start.y && overlaps(x, (rect.start.x, rect.end.x)),
+                'j' => rect.start.y > current.end.y && overlaps(x, (rect.start.x, rect.end.x)),
+                _ => false,
+            })
+            .min_by_key(|(_, rect)| match key {
+                'h' => current.start.x - rect.end.x,
+                'l' => rect.start.x - current.end.x,
+                'k' => current.start.y - rect.end.y,
+                _ => rect.start.y - current.end.y,
+            })
+            .map(|&(window_id, _)| window_id)
+            .ok_or_else(|| format_err!("no window in that direction"))?;
+        self.focus(neighbor);
+    }
+
+    pub fn toggle_bookmark(&mut self, window_id: WindowId) {
+        let window = &self.windows[window_id];
+        let buffer = &mut self.buffers[window.buffer];
+        let line = window.selections[SelectionId(0)].end.line;
+        let start = buffer.content.line_to_char(line.zero_based());
+        match buffer.bookmarks.binary_search(&start) {
+            Ok(i) => {
+                buffer.bookmarks.remove(i);
+            }
+            Err(i) => buffer.bookmarks.insert(i, start),
+        }
+    }
+
+    #[throws]
+    pub fn jump_bookmark(&mut self, window_id: WindowId, forward: bool) {
+        let window = &self.windows[window_id];
+        let buffer = &self.buffers[window.buffer];
+        let line = window.selections[SelectionId(0)].end.line;
+        let start = buffer.content.line_to_char(line.zero_based());
+        let bookmarks = &buffer.bookmarks;
+        let bookmark = if forward {
+            bookmarks
+                .iter()
+                .find(|&&bookmark| bookmark > start)
+                .or_else(|| bookmarks.first())
+        } else {
+            bookmarks
+                .iter()
+                .rev()
+                .find(|&&bookmark| bookmark < start)
+                .or_else(|| bookmarks.last())
+        };
+        let bookmark = *bookmark.context("no bookmarks in this buffer")?;
+        self.select_range(window_id, bookmark..bookmark + 1);
+    }
+
+    /// Sets mark `name` to the primary cursor.
+    pub fn set_mark(&mut self, window_id: WindowId, name: char) {
+        let window = &self.windows[window_id];
+        let buffer = &mut self.buffers[window.buffer];
+        let at = window.selections[SelectionId(0)]
+            .valid(&buffer.content)
+            .end
+            .char_of(&buffer.content);
+        buffer.marks.insert(name, at);
+        self.show_message(Importance::Info, format!("set mark '{}'", name));
+    }
+
+    #[throws]
+    pub fn jump_mark(&mut self, window_id: WindowId, name: char) {
+        let buffer = &self.buffers[self.windows[window_id].buffer];
+        let at = *buffer
+            .marks
+            .get(&name)
+            .ok_or_else(|| format_err!("mark '{}' isn't set", name))?;
+        self.select_range(window_id, at..at + 1);
+        self.windows[window_id].follow = false;
+    }
+
+    /// Saves the selections to register `name` of the buffer.
+    pub fn save_selections(&mut self, window_id: WindowId, name: char) {
+        let window = &self.windows[window_id];
+        let buffer = &mut self.buffers[window.buffer];
+        let content = &buffer.content;
+        let saved = window
+            .selections
+            .iter()
+            .map(|selection| {
+                let selection = selection.valid(content);
+                (
+                    selection.start.char_of(content),
+                    selection.end.char_of(content),
+                )
+            })
+            .collect::<Vec<_>>();
+        let message = format!("saved {} selections to '{}'", saved.len(), name);
+        buffer.saved_selections.insert(name, saved);
+        self.show_message(Importance::Info, message);
+    }
+
+    #[throws]
+    pub fn restore_selections(&mut self, window_id: WindowId, name: char, combine: Combine) {
+        let window = &mut self.windows[window_id];
+        let buffer = &self.buffers[window.buffer];
+        let content = &buffer.content;
+        let last = content.len_chars() - 1;
+        let saved = buffer
+            .saved_selections
+            .get(&name)
+            .ok_or_else(|| format_err!("no selections saved to '{}' in this buffer", name))?
+            .iter()
+            .map(|&(start, end)| Selection {
+                start: Position::from_char(content, start.min(last)),
+                end: Position::from_char(content, end.min(last)),
+            });
+        let selections = match combine {
+            Combine::Replace => saved.collect(),
+            Combine::Append => window.selections.iter().copied().chain(saved).collect(),
+            Combine::Union | Combine::Intersect => {
+                let saved = saved.collect::<Vec<_>>();
+                if saved.len() != window.selections.len() {
+                    throw!(format_err!(
+                        "'{}' has {} selections, not {}",
+                        name,
+                        saved.len(),
+                        window.selections.len()
+                    ));
+                }
+                let mut selections = Vec::new();
+                for (selection, other) in window.selections.iter().zip(saved) {
+                    let range = selection.valid(content).range_of(content);
+                    let other = other.range_of(content);
+                    let (start, end) = match combine {
+                        Combine::Union => (range.start.min(other.start), range.end.max(other.end)),
+                        _ => (range.start.max(other.start), range.end.min(other.end)),
+                    };
+                    if start < end {
+                        selections.push(Selection {
+                            start: Position::from_char(content, start),
+                            end: Position::from_char(content, end - 1),
+                        });
+                    }
+                }
+                selections
+            }
+        };
+        if selections.is_empty() {
+            throw!(format_err!(
+                "no selections overlap those saved to '{}'",
+                name
+            ));
+        }
+        window.selections = selections.into();
+    }
+
+    /// Selects the object around each cursor, leaving selections with no
+    /// such object or an empty one as they are.
+    pub fn select_objects(&mut self, window_id: WindowId, object: TextObject, around: bool) {
+        let window = &mut self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        for selection in window.selections.iter_mut() {
+            let at = selection.valid(content).end.char_of(content);
+            if let Some(range) = object.range(content, at, around).filter(|r| !r.is_empty()) {
+                selection.start = Position::from_char(content, range.start);
+                selection.end = Position::from_char(content, range.end - 1);
+            }
+        }
+    }
+
+    /// Surrounds each selection with the pair of `c`.
+    pub fn surround_add(&mut self, window_id: WindowId, c: char) {
+        let (open, close) = surround_pair(c);
+        for selection_id in self.selections(window_id) {
+            let window = &self.windows[window_id];
+            let buffer_id = window.buffer;
+            let content = &self.buffers[buffer_id].content;
+            let range = window.selections[selection_id]
+                .valid(content)
+                .range_of(content);
+            self.edit(
+                buffer_id,
+                range.end..range.end,
+                close.encode_utf8(&mut [0; 4]),
+            );
+            self.edit(
+                buffer_id,
+                range.start..range.start,
+                open.encode_utf8(&mut [0; 4]),
+            );
+        }
+    }
+
+    /// Replaces the pair of `from` around each selection with the pair of
+    /// `to`, or deletes it without one.
+    #[throws]
+    pub fn surround_change(&mut self, window_id: WindowId, from: char, to: Option<char>) {
+        let window = &self.windows[window_id];
+        let buffer_id = window.buffer;
+        let content = &self.buffers[buffer_id].content;
+        let object = match surround_pair(from) {
+            (open, close) if open == close => TextObject::Quote(open),
+            (open, close) => TextObject::Pair(open, close),
+        };
+        let mut edits = Vec::new();
+        for selection in window.selections.iter() {
+            let range = selection.valid(content).range_of(content);
+            let mut at = range.start;
+            let around = loop {
+                match object.range(content, at, true) {
+                    Some(around) if around.end >= range.end => break around,
+                    Some(around) if around.start > 0 => at = around.start - 1,
+                    _ => throw!(format_err!("no {} around the selection", from)),
+                }
+            };
+            let to = to.map(surround_pair);
+            edits.push((around.start, to.map(|(open, _)| open)));
+            edits.push((around.end - 1, to.map(|(_, close)| close)));
+        }
+        // Edits go from the end so that earlier offsets stay valid.
+        edits.sort_by_key(|&(at, _)| Reverse(at));
+        edits.dedup_by_key(|&mut (at, _)| at);
+        for (at, c) in edits {
+            self.edit(
+                buffer_id,
+                at..at + 1,
+                &c.map_or_else(String::new, String::from),
+            );
+        }
+    }
+
+    /// Focuses a window showing `buffer_id`, opening one if there is none.
+    pub fn focus_buffer(&mut self, buffer_id: BufferId) -> WindowId {
+        let window_id = (0..self.windows.len())
+            .map(WindowId)
+            .find(|&window_id| self.windows[window_id].buffer == buffer_id)
+            .unwrap_or_else(|| {
+                self.windows.push(Window::new(buffer_id));
+                WindowId(self.windows.len() - 1)
+            });
+        self.focus(window_id);
+        window_id
+    }
+
+    /// Moves to the next or previous result of `:find-all`.
+    #[throws]
+    pub fn jump_result(&mut self, forward: bool) {
+        let len = self.results.len();
+        if len == 0 {
+            throw!(format_err!("no results"));
+        }
+        let index = match (self.result_index, forward) {
+            (None, _) => 0,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        };
+        self.result_index = Some(index);
+        let (buffer_id, range, revision) = self.results[index].clone();
+        let effects = &self.buffers[buffer_id].effects;
+        let range = effects
+            .shift(revision, range)
+            .context("buffer changed too much since the search")?;
+        self.results[index] = (buffer_id, range.clone(), effects.revision());
+        let window_id = self.focus_buffer(buffer_id);
+        self.select_range(window_id, range);
+        self.show_message(Importance::Info, format!("result {}/{}", index + 1, len));
+    }
+
+    /// Replaces the location list, jumping to its first entry.
+    #[throws]
+    pub fn set_locations(&mut self, locations: Vec<quickfix::Entry>) {
+        self.locations = locations;
+        self.location_index = None;
+        self.jump_location(true)?;
+    }
+
+    /// Moves to the next or previous entry of the location list.
+    #[throws]
+    pub fn jump_location(&mut self, forward: bool) {
+        let len = self.locations.len();
+        if len == 0 {
+            throw!(format_err!("no locations"));
+        }
+        let index = match (self.location_index, forward) {
+            (None, _) => 0,
+            (Some(i), true) => (i + 1).min(len - 1),
+            (Some(i), false) => i.saturating_sub(1),
+        };
+        self.location_index = Some(index);
+        let entry = self.locations[index].clone();
+        let window_id = self.open_path(&entry.path)?;
+        let content = &self.buffers[self.windows[window_id].buffer].content;
+        // Buffers end in a newline, so the last line ropey counts is empty.
+        let line = (entry.line.max(1) - 1).min(content.len_lines() - 2);
+        let start = content.line_to_char(line);
+        let at = start + (entry.column.max(1) - 1).min(content.line(line).len_chars() - 1);
+        self.select_range(window_id, at..at + 1);
+        self.show_message(
+            Importance::Info,
+            format!("({}/{}) {}", index + 1, len, entry.message),
+        );
+    }
+
+    /// Focuses a buffer showing `path`, opening the file if none does.
+    #[throws]
+    pub fn open_path(&mut self, path: &Path) -> WindowId {
+        let path = path.canonicalize()?;
+        let open = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.path.as_deref() == Some(&*path));
+        match open {
+            Some(i) => self.focus_buffer(BufferId(i)),
+            None => {
+                let name = path.display().to_string();
+                self.open_file(name, path)?
+            }
+        }
+    }
+
+    /// Reads a file into a new buffer and window, in the background if it is
+    /// over `async_load_size`.
+    #[throws]
+    pub fn open_file(&mut self, name: String, path: PathBuf) -> WindowId {
+        let file = File::open(&path).with_context(|| format!("failed to open {}", name))?;
+        let len = file.metadata()?.len();
+        if len > self.config.async_load_size as u64 {
+            let mut buffer = Buffer::new(name, Rope::from("\n"), Some(path.clone()));
+            buffer.loading = Some((0, len));
+            load::spawn(path, BufferId(self.buffers.len()), self.load.0.clone());
+            return self.open(buffer);
+        }
+        let bytes = fs::read(&path).with_context(|| format!("failed to read {}", name))?;
+        let (text, format) = format::decode(bytes);
+        let read_only = OpenOptions::new().write(true).open(&path).is_err();
+        let mtime = modified_time(&path);
+        let mut buffer = Buffer::new(name, Rope::from(text), Some(path));
+        buffer.read_only = read_only;
+        buffer.format = format;
+        buffer.mtime = mtime;
+        if buffer.content.len_bytes() > self.config.large_file_size {
+            buffer.large = true;
+            buffer.filetype = None;
+        }
+        let window_id = self.open(buffer);
+        self.warn_latin1(self.windows[window_id].buffer);
+        window_id
+    }
+
+    /// Warns that a file was read as Latin-1, since a single byte that isn't
+    /// valid UTF-8 is enough for that.
+    fn warn_latin1(&mut self, buffer_id: BufferId) {
+        let buffer = &self.buffers[buffer_id];
+        if buffer.format.encoding == Encoding::Latin1 {
+            let message = format!(
+                "{} isn't valid UTF-8, so it was read as latin-1",
+                buffer.name
+            );
+            self.show_message(Importance::Error, message);
+        }
+    }
+
+    /// Opens files named on the command line in windows of their own, going
+    /// to the given lines, and focuses the first. Files that don't exist are
+    /// created when written.
+    pub fn open_files(&mut self, files: Vec<(String, Option<usize>)>, read_only: bool) {
+        let mut first = None;
+        for (name, line) in files {
+            match self.open_arg(name, line, read_only) {
+                Ok(window_id) => first = first.or(Some(window_id)),
+                Err(err) => {
+                    error!("{:#}", err);
+                    self.show_message(Importance::Error, format!("{:#}", err));
+                }
+            }
+        }
+        if let Some(window_id) = first {
+            self.focus(window_id);
+        }
+    }
+
+    #[throws]
+    fn open_arg(&mut self, name: String, line: Option<usize>, read_only: bool) -> WindowId {
+        let path = env::current_dir()?.join(&name);
+        let window_id = if path.exists() {
+            self.open_file(name, path.canonicalize()?)?
+        } else {
+            self.open(Buffer::new(name, Rope::from("\n"), Some(path)))
+        };
+        self.buffers[self.windows[window_id].buffer].read_only |= read_only;
+        match line {
+            Some(line) => self.goto_line(window_id, line, false)?,
+            None => self.restore_position(window_id),
+        }
+        window_id
+    }
+
+    /// Reloads buffers whose files changed on disk, or reports the change for
+    /// ones with unsaved changes.
+    fn check_files(&mut self) {
+        for buffer_id in (0..self.buffers.len()).map(BufferId) {
+            let buffer = &mut self.buffers[buffer_id];
+            let path = match &buffer.path {
+                Some(path) if buffer.mtime.is_some() && buffer.follow.is_none() => path,
+                _ => continue,
+            };
+            let mtime = modified_time(path);
+            if mtime.is_none() || mtime == buffer.mtime || buffer.disk_changed {
+                continue;
+            }
+            if buffer.modified {
+                buffer.disk_changed = true;
+                let message = format!(
+                    "{} changed on disk (:e! reloads it, :w! overwrites it)",
+                    buffer.name
+                );
+                self.show_message(Importance::Error, message);
+                continue;
+            }
+            match self.reload(buffer_id) {
+                Ok(()) => {
+                    let message = format!("reloaded {}", self.buffers[buffer_id].name);
+                    self.show_message(Importance::Info, message);
+                }
+                Err(err) => self.show_message(Importance::Error, format!("{:#}", err)),
+            }
+        }
+    }
+
+    /// Renames a buffer and its file, if it was written, then runs the
+    /// `rename` hook. Unless `force`, an existing file isn't replaced.
+    #[throws]
+    pub fn rename_buffer(&mut self, buffer_id: BufferId, name: &str, force: bool) {
+        let mut path = PathBuf::from(name);
+        if !force && path.exists() {
+            throw!(format_err!(
+                "{} already exists (:rename! replaces it)",
+                name
+            ));
+        }
+        let buffer = &self.buffers[buffer_id];
+        let old = match &buffer.path {
+            Some(old) => old.display().to_string(),
+            None => buffer.name.clone(),
+        };
+        if let Some(old) = &buffer.path {
+            if old.exists() {
+                fs::rename(old, &path)
+                    .with_context(|| format!("failed to rename {}", old.display()))?;
+                path = path.canonicalize()?;
+            }
+        }
+        // The server only hears of the new name once the file has it.
+        self.detach(buffer_id);
+        let buffer = &mut self.buffers[buffer_id];
+        buffer.filetype = filetype::detect(&path);
+        buffer.name = name.to_owned();
+        let new = path.display().to_string();
+        buffer.path = Some(path);
+        self.attach(buffer_id);
+        self.watch_file(buffer_id);
+        self.tabline_dirty = true;
+        if let Some(hook) = self.config.hooks.get("rename") {
+            if let Err(err) = subprocess::run_with_args(hook, &[&old, &new], "") {
+                self.show_message(Importance::Error, format!("{:#}", err));
+            }
+        }
+    }
+
+    /// Reads a buffer's file again, discarding its changes and history.
+    #[throws]
+    pub fn reload(&mut self, buffer_id: BufferId) {
+        let buffer = &mut self.buffers[buffer_id];
+        let path = buffer
+            .path
+            .clone()
+            .ok_or_else(|| format_err!("buffer '{}' has no file name", buffer.name))?;
+        if buffer.loading.is_some() {
+            throw!(format_err!("buffer '{}' is still loading", buffer.name));
+        }
+        let bytes = fs::read(&path).with_context(|| format!("failed to read {}", buffer.name))?;
+        let (text, format) = format::decode(bytes);
+        let mut content = Rope::from(text);
+        if content.len_chars() == 0 {
+            content.insert_char(0, '\n');
+        }
+        let last = content.len_chars() - 1;
+        for window in self.windows.iter_mut().filter(|w| w.buffer == buffer_id) {
+            for selection in window.selections.iter_mut() {
+                let valid = selection.valid(&buffer.content);
+                let moved = |p: Position| {
+                    Position::from_char(&content, p.char_of(&buffer.content).min(last))
+                };
+                selection.start = moved(valid.start);
+                selection.end = moved(valid.end);
+            }
+        }
+        let old = buffer.content.len_chars();
+        buffer.content = content;
+        buffer.effects.record(0..old, buffer.content.len_chars());
+        buffer.format = format;
+        buffer.mtime = modified_time(&path);
+        buffer.modified = false;
+        buffer.disk_changed = false;
+        buffer.history.clear();
+        buffer.future.clear();
+        buffer.changes.clear();
+        buffer.before = None;
+        buffer.bookmarks.clear();
+        buffer.marks.clear();
+        buffer.saved_selections.clear();
+        buffer.last_edit = None;
+        self.editor_dirty = true;
+    }
+
+    /// Creates another window on the same buffer with the same view.
+    pub fn split_window(&mut self, window_id: WindowId) -> WindowId {
+        let window = &self.windows[window_id];
+        let mut split = Window::new(window.buffer);
+        split.selections = window.selections.to_vec().into();
+        split.top = window.top;
+        split.follow = window.follow;
+        let split_id = WindowId(self.windows.len());
+        self.windows.push(split);
+        split_id
+    }
+
+    /// Merges overlapping and adjacent selections, which edits would
+    /// otherwise apply to twice.
+    fn merge_selections(&mut self) {
+        for window in self.windows.iter_mut() {
+            if window.selections.len() > 1 {
+                let content = &self.buffers[window.buffer].content;
+                location::merge_selections(&mut window.selections, content);
+            }
+        }
+    }
+
+    /// Scrolls the windows whose primary cursor moved or whose buffer changed
+    /// since they were last scrolled, leaving other views where they are.
+    fn scroll_windows(&mut self) {
+        for window in self.windows.iter_mut() {
+            let seen = (
+                window.selections[SelectionId(0)].end,
+                self.buffers[window.buffer].effects.revision(),
+            );
+            if window.scrolled_for != Some(seen) {
+                window.scrolled_for = Some(seen);
+                window.scroll_to_cursor(self.config.scrolloff);
+            }
+        }
+    }
+
+    /// Queues `task` to run once input has been idle, unless already queued.
+    fn schedule(&mut self, task: IdleTask) {
+        if !self.idle.contains(&task) {
+            self.idle.push_back(task);
+        }
+    }
+
+    /// Runs one queued task, so that new input is never kept waiting long.
+    fn run_idle(&mut self) {
+        if let Some(task) = self.idle.pop_front() {
+            match task {
+                IdleTask::SaveRecent => self.save_recent(),
+            }
+        }
+    }
+
+    /// Records the cursor position in every file for the next session.
+    fn save_recent(&mut self) {
+        let mut updated = Vec::new();
+        for (i, buffer) in self.buffers.iter().enumerate() {
+            let path = match &buffer.path {
+                Some(path) if buffer.loading.is_none() => path.clone(),
+                _ => continue,
+            };
+            let window = self.windows.iter().rev().find(|w| w.buffer == BufferId(i));
+            if let Some(window) = window {
+                let position = window.selections[SelectionId(0)].end;
+                updated.push(recent::Entry {
+                    path,
+                    line: position.line.zero_based(),
+                    column: position.column.zero_based(),
+                });
+            }
+        }
+        if let Err(err) = recent::save(&mut self.recent, updated) {
+            error!("{:#}", err);
+        }
+    }
+
+    /// Restores the last known cursor position of a newly opened file.
+    fn restore_position(&mut self, window_id: WindowId) {
+        let window = &self.windows[window_id];
+        let buffer = &self.buffers[window.buffer];
+        let entry = self
+            .recent
+            .iter()
+            .find(|entry| Some(&entry.path) == buffer.path.as_ref());
+        if let Some(entry) = entry {
+            let content = &buffer.content;
+            let line = Line::from_zero_based(entry.line.min(Line::last(content).zero_based()));
+            let column = entry.column.min(line.text_len_of(content));
+            let at = line.char_of(content) + column;
+            self.select_range(window_id, at..at + 1);
+        }
+    }
+
+    /// Opens the URL or file under the primary cursor with the system handler.
+    #[throws]
+    pub fn open_at_cursor(&mut self, window_id: WindowId) {
+        let window = &self.windows[window_id];
+        let buffer = &self.buffers[window.buffer];
+        let position = window.selections[SelectionId(0)].end;
+        let line = position.line.slice_of(&buffer.content).to_string();
+        let token = url::at(&line, position.column.zero_based())
+            .context("no URL or file under the cursor")?;
+        let target = if url::is_url(&token) {
+            token
+        } else {
+            let mut path = match token.strip_prefix("~/") {
+                Some(rest) => PathBuf::from(std::env::var("HOME")?).join(rest),
+                None => PathBuf::from(&token),
+            };
+            if path.is_relative() {
+                if let Some(dir) = buffer.path.as_deref().and_then(|path| path.parent()) {
+                    path = dir.join(path);
+                }
+            }
+            if !path.exists() {
+                throw!(format_err!("'{}' is not a URL or an existing file", token));
+            }
+            path.display().to_string()
+        };
+        let opener = if cfg!(target_os = "macos") {
+            "open"
+        } else {
+            "xdg-open"
+        };
+        let mut child = process::Command::new(opener)
+            .arg(&target)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to run {}", opener))?;
+        thread::spawn(move || child.wait());
+        self.show_message(Importance::Info, format!("opened {}", target));
+    }
+
+    #[throws]
+    pub fn goto_last_edit(&mut self, window_id: WindowId) {
+        let buffer = &self.buffers[self.windows[window_id].buffer];
+        let at = buffer.last_edit.context("no changes in this buffer")?;
+        self.select_range(window_id, at..at + 1);
+    }
+
+    pub fn show_message(&mut self, importance: Importance, message: String) {
+        self.messages.push_back(message.clone());
+        if self.messages.len() > MESSAGES_LEN {
+            self.messages.pop_front();
+        }
+        if let (Importance::Info, Some(captured)) = (importance, &mut self.captured) {
+            captured.push_str(&message);
+            captured.push('\n');
+            return;
+        }
+        self.message = Some((importance, message));
+    }
+
+    /// Shows command output, in a scratch buffer named after `name` if it
+    /// doesn't fit on the status line.
+    pub fn output(&mut self, name: &str, text: String) {
+        if let Some(captured) = &mut self.captured {
+            captured.push_str(&text);
+            if !text.ends_with('\n') {
+                captured.push('\n');
+            }
+        } else if text.trim_end().contains('\n') {
+            self.open(Buffer::new(format!("*{}*", name), Rope::from(text), None));
+        } else {
+            self.show_message(Importance::Info, text);
+        }
+    }
+
+    /// Runs `command`, or the user's shell, on the terminal's main screen and
+    /// waits for it to exit.
+    #[throws]
+    pub fn run_interactive(&mut self, command: Option<&str>) {
+        write!(
+            self.output,
+            "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l{}{}{}",
+            screen::ToMainScreen,
+            cursor::Show,
+            cursor::SteadyBlock
+        )?;
+        self.output.flush()?;
+        self.output.suspend_raw_mode()?;
+        let status = match command {
+            Some(command) => {
+                let status = process::Command::new("sh").arg("-c").arg(command).status();
+                // Leave the output on screen until it has been read.
+                let _ = write!(self.output, "\npress enter to continue");
+                let _ = self.output.flush();
+                let _ = get_tty().and_then(|tty| {
+                    io::BufRead::read_line(&mut io::BufReader::new(tty), &mut String::new())
+                });
+                status
+            }
+            None => {
+                let shell = std::env::var("SHELL").unwrap_or_else(|_| String::from("sh"));
+                process::Command::new(shell).status()
+            }
+        };
+        self.output.activate_raw_mode()?;
+        write!(
+            self.output,
+            "{}{}{}\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h",
+            screen::ToAlternateScreen,
+            cursor::Hide,
+            cursor::SteadyBar
+        )?;
+        self.screen.invalidate();
+        self.tabline_dirty = true;
+        self.editor_dirty = true;
+        self.statusline_dirty = true;
+        let status = status.context("failed to run the command")?;
+        if !status.success() {
+            throw!(format_err!("command failed: {}", status));
+        }
+    }
+
+    pub fn quit(&mut self) {
+        self.exit.0.send(()).unwrap();
+    }
+
+    /// Fails if any buffer with a file has changes that weren't written.
+    #[throws]
+    pub fn check_saved(&self) {
+        check_saved(self.buffers.iter())?;
+    }
+
+    /// Writes the window's buffer to its file, or to `path`, which becomes the
+    /// buffer's file if it had none. Unless `force`, a file changed on disk
+    /// since it was read isn't overwritten.
+    #[throws]
+    pub fn write_buffer(&mut self, window_id: WindowId, path: Option<&str>, force: bool) {
+        let buffer_id = self.windows[window_id].buffer;
+        let buffer = &mut self.buffers[buffer_id];
+        let named = buffer.path.is_some();
+        let own = path.is_none() || !named;
+        let (path, format, lossy) = buffer.write(path, force, &self.config)?;
+        let bytes = buffer.content.len_bytes();
+        if !named {
+            self.tabline_dirty = true;
+        }
+        if own {
+            self.attach(buffer_id);
+            self.watch_file(buffer_id);
+            self.sync_documents();
+            let buffer = &self.buffers[buffer_id];
+            if let Some(server) = server_of(&mut self.servers, buffer) {
+                let document = buffer.document.as_ref().unwrap();
+                let params = object(vec![("textDocument", document.identifier())]);
+                server.notify("textDocument/didSave", params);
+            }
+        }
+        if lossy {
+            self.show_message(
+                Importance::Error,
+                format!(
+                    "wrote {} with chars {} can't represent replaced by '?'",
+                    path.display(),
+                    format.encoding.name()
+                ),
+            );
+        } else {
+            self.show_message(
+                Importance::Info,
+                format!("wrote {} bytes to {}", bytes, path.display()),
+            );
+        }
+    }
+
+    pub fn set_mode(&mut self, window: WindowId, mode: Mode) {
+        self.windows[window].mode = mode;
+        match mode {
+            Mode::Normal => {
+                self.windows[window].snippet = None;
+                self.windows[window].words = None;
+            }
+            Mode::Insert => {}
+            Mode::Append => {}
+            Mode::Goto { .. } => {}
+            Mode::View => {}
+            Mode::Object { .. } => {}
+            Mode::Register => {}
+            Mode::Replace => {}
+            Mode::SetMark => {}
+            Mode::JumpMark => {}
+            Mode::CombineSelections { .. } => {}
+            Mode::Surround => {}
+            Mode::SurroundAdd => {}
+            Mode::SurroundDelete => {}
+            Mode::SurroundChange { .. } => {}
+            Mode::Window => {}
+            Mode::Command => self.windows[window].completion = None,
+            Mode::Search => {}
+            Mode::Select => {}
+            Mode::Keep { .. } => {}
+            Mode::Pipe => {}
+            Mode::Palette | Mode::Characters => self.filter_palette(window),
+        }
+    }
+
+    pub fn selections(&self, window: WindowId) -> impl Iterator<Item = SelectionId> {
+        let window = &self.windows[window];
+        (0..window.selections.len()).map(SelectionId)
+    }
+
+    pub fn insert_after(&mut self, window_id: WindowId, selection_id: SelectionId, text: &str) {
+        let window = &self.windows[window_id];
+        let buffer_id = window.buffer;
+        let content = &self.buffers[buffer_id].content;
+        let end = window.selections[selection_id].end.char_of(content) + 1;
+        self.edit(buffer_id, end..end, text);
+        let content = &self.buffers[buffer_id].content;
+        let len = text.chars().count();
+        let selection = &mut self.windows[window_id].selections[selection_id];
+        selection.end = Position::from_char(content, end + len.max(1) - 1);
+    }
+
+    pub fn open_line(&mut self, window_id: WindowId, selection_id: SelectionId, above: bool) {
+        let window = &self.windows[window_id];
+        let buffer_id = window.buffer;
+        let content = &self.buffers[buffer_id].content;
+        let selection = window.selections[selection_id].ordered();
+        let line = if above {
+            selection.start.line
+        } else {
+            selection.end.line
+        };
+        let indent = line_indent(content, line);
+        let (at, new_line) = if above {
+            (line.char_of(content), line)
+        } else {
+            (
+                line.char_of(content) + line.slice_of(content).len_chars(),
+                line + 1usize,
+            )
+        };
+        self.edit(buffer_id, at..at, &format!("{}\n", indent));
+        let position = Position {
+            line: new_line,
+            column: Column::from_zero_based(indent.chars().count()),
+        };
+        let selection = &mut self.windows[window_id].selections[selection_id];
+        selection.start = position;
+        selection.end = position;
+    }
+
+    #[throws(MovementError)]
+    pub fn type_char(&mut self, window_id: WindowId, c: char) {
+        for selection_id in self.selections(window_id) {
+            self.type_char_at(window_id, selection_id, c)?;
+        }
+    }
+
+    #[throws(MovementError)]
+    pub fn type_char_at(&mut self, window_id: WindowId, selection_id: SelectionId, c: char) {
+        let mut text = [0; 4];
+        let text = c.encode_utf8(&mut text);
+        match self.windows[window_id].mode {
+            Mode::Append => self.insert_after(window_id, selection_id, text),
+            _ => self.insert_before(window_id, selection_id, text),
+        }
+    }
+
+    /// Breaks the line at each cursor, starting the new line with the
+    /// indentation before the cursor, and a level more after a block opener.
+    pub fn insert_newline(&mut self, window_id: WindowId) {
+        for selection_id in self.selections(window_id) {
+            let cursor = self.cursor_char(window_id, selection_id);
+            let buffer = &self.buffers[self.windows[window_id].buffer];
+            let content = &buffer.content;
+            let line = Line::from_zero_based(content.char_to_line(cursor));
+            let before = content.slice(line.char_of(content)..cursor).to_string();
+            let mut indent = before
+                .chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>();
+            let openers = buffer.filetype.map_or(&[][..], filetype::block_openers);
+            if self.config.smart_indent
+                && before.trim_end().ends_with(|c: char| openers.contains(&c))
+            {
+                indent.push_str(&self.indent_unit());
+            }
+            let text = format!("\n{}", indent);
+            match self.windows[window_id].mode {
+                Mode::Append => self.insert_after(window_id, selection_id, &text),
+                _ => self.insert_before(window_id, selection_id, &text),
+            }
+        }
+    }
+
+    /// Types `c` at each cursor, also inserting the closing char after an
+    /// opening bracket or quote, and stepping over a closing char that is
+    /// already next.
+    #[throws(MovementError)]
+    pub fn type_paired(&mut self, window_id: WindowId, c: char) {
+        for selection_id in self.selections(window_id) {
+            let cursor = self.cursor_char(window_id, selection_id);
+            let window = &self.windows[window_id];
+            let buffer_id = window.buffer;
+            let content = &self.buffers[buffer_id].content;
+            let selection = window.selections[selection_id];
+            let append = matches!(window.mode, Mode::Append);
+            // Insert mode selections longer than a char are typed over as is.
+            if !append && selection.start != selection.end {
+                self.type_char_at(window_id, selection_id, c)?;
+                continue;
+            }
+            let next = (cursor < content.len_chars()).then(|| content.char(cursor));
+            let prev = cursor.checked_sub(1).map(|i| content.char(i));
+            if next == Some(c) && PAIRS.iter().any(|&(_, close)| close == c) {
+                let last = content.len_chars() - 1;
+                let selection = &mut self.windows[window_id].selections[selection_id];
+                if append {
+                    selection.end = Position::from_char(content, cursor.min(last));
+                } else {
+                    selection.start = Position::from_char(content, (cursor + 1).min(last));
+                    selection.end = selection.start;
+                }
+                continue;
+            }
+            let pair = PAIRS.iter().find(|&&(open, _)| open == c);
+            let (open, close) = match pair {
+                // A quote straight after a word is more likely an apostrophe.
+                Some(&(open, close)) if open != close || !prev.is_some_and(is_word_char) => {
+                    (open, close)
+                }
+                _ => {
+                    self.type_char_at(window_id, selection_id, c)?;
+                    continue;
+                }
+            };
+            let text = format!("{}{}", open, close);
+            if append {
+                self.insert_after(window_id, selection_id, &text);
+            } else {
+                self.insert_before(window_id, selection_id, &text);
+            }
+            let content = &self.buffers[buffer_id].content;
+            let selection = &mut self.windows[window_id].selections[selection_id];
+            if append {
+                selection.end = Position::from_char(content, cursor);
+            } else {
+                selection.start = Position::from_char(content, cursor + 1);
+                selection.end = selection.start;
+            }
+        }
+    }
+
+    #[throws(MovementError)]
+    pub fn backspace(&mut self, window_id: WindowId) {
+        if self.config.auto_pairs && self.delete_empty_pairs(window_id) {
+            return;
+        }
+        if !self.replace_snippet_placeholder(window_id) {
+            self.move_selections(window_id, Movement::Left, false)?;
+            self.delete_selections(window_id);
+        }
+    }
+
+    /// Opens the word completion popup and puts in its first or last word,
+    /// or puts in the next or previous one if it is open.
+    #[throws]
+    pub fn complete_word(&mut self, window_id: WindowId, forward: bool) {
+        if self.windows[window_id].words.is_none() {
+            // With a language server, the popup opens once it answers.
+            let revision = self.buffers[self.windows[window_id].buffer]
+                .effects
+                .revision();
+            let pending = Pending::Completion {
+                window: window_id,
+                revision,
+                forward,
+            };
+            if self.request_at_cursor(window_id, "textDocument/completion", pending) {
+                return;
+            }
+            self.open_words(window_id, Vec::new())?;
+        }
+        self.cycle_words(window_id, forward);
+    }
+
+    /// Opens the word completion popup with the words from `server` first.
+    #[throws]
+    fn open_words(&mut self, window_id: WindowId, server: Vec<String>) {
+        let prefix = self.word_prefix(window_id);
+        let words = self.word_candidates(window_id, &server, &prefix);
+        if words.is_empty() {
+            throw!(format_err!("no words to complete"));
+        }
+        self.windows[window_id].words = Some(complete::Words {
+            candidates: words.clone(),
+            index: None,
+            server,
+            collected: words,
+            prefix,
+        });
+    }
+
+    /// Puts in the next or previous word of the open popup.
+    fn cycle_words(&mut self, window_id: WindowId, forward: bool) {
+        let words = self.windows[window_id].words.as_mut().unwrap();
+        let len = words.candidates.len();
+        let index = match (words.index, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        };
+        words.index = Some(index);
+        let word = words.candidates[index].clone();
+        for selection_id in self.selections(window_id) {
+            let cursor = self.cursor_char(window_id, selection_id);
+            let window = &self.windows[window_id];
+            let buffer_id = window.buffer;
+            let content = &self.buffers[buffer_id].content;
+            let start = word_start(content, cursor);
+            self.edit(buffer_id, start..cursor, &word);
+            if let Mode::Append = self.windows[window_id].mode {
+                let content = &self.buffers[buffer_id].content;
+                let end = start + word.chars().count() - 1;
+                self.windows[window_id].selections[selection_id].end =
+                    Position::from_char(content, end);
+            }
+        }
+    }
+
+    /// The word before the primary cursor.
+    fn word_prefix(&self, window_id: WindowId) -> String {
+        let content = &self.buffers[self.windows[window_id].buffer].content;
+        let at = self.cursor_char(window_id, SelectionId(0));
+        content.slice(word_start(content, at)..at).to_string()
+    }
+
+    /// Words starting with `prefix`, from `server` first, then the window's
+    /// buffer and then the other open ones, skipping large files.
+    fn word_candidates(&self, window_id: WindowId, server: &[String], prefix: &str) -> Vec<String> {
+        let buffer_id = self.windows[window_id].buffer;
+        let others = (0..self.buffers.len())
+            .map(BufferId)
+            .filter(|&other| other != buffer_id);
+        let texts = Some(buffer_id)
+            .into_iter()
+            .chain(others)
+            .map(|buffer_id| &self.buffers[buffer_id])
+            .filter(|buffer| !buffer.large)
+            .map(|buffer| &buffer.content);
+        complete::words(server, texts, prefix)
+    }
+
+    /// Narrows the word completion popup to the word being typed, closing it
+    /// if nothing matches.
+    fn filter_words(&mut self, window_id: WindowId) {
+        let mut words = match self.windows[window_id].words.take() {
+            Some(words) => words,
+            None => return,
+        };
+        let prefix = self.word_prefix(window_id);
+        // Deleting some of the word may match words that weren't collected.
+        if !prefix.starts_with(&words.prefix) {
+            words.collected = self.word_candidates(window_id, &words.server, &prefix);
+            words.prefix = prefix.clone();
+        }
+        words.candidates = words
+            .collected
+            .iter()
+            .filter(|word| word.len() > prefix.len() && word.starts_with(&prefix))
+            .cloned()
+            .collect();
+        words.index = None;
+        if !words.candidates.is_empty() {
+            self.windows[window_id].words = Some(words);
+        }
+    }
+
+    /// Deletes the empty pair around each Insert mode cursor, if every cursor
+    /// is in one. Returns whether it did.
+    fn delete_empty_pairs(&mut self, window_id: WindowId) -> bool {
+        let window = &self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let in_pair = |selection: &Selection| {
+            let cursor = selection.start.char_of(content);
+            selection.start == selection.end
+                && cursor > 0
+                && PAIRS
+                    .iter()
+                    .any(|&pair| pair == (content.char(cursor - 1), content.char(cursor)))
+        };
+        if !matches!(window.mode, Mode::Insert) || !window.selections.iter().all(in_pair) {
+            return false;
+        }
+        for selection_id in self.selections(window_id) {
+            let window = &self.windows[window_id];
+            let content = &self.buffers[window.buffer].content;
+            let cursor = window.selections[selection_id].start.char_of(content);
+            self.edit(window.buffer, cursor - 1..cursor + 1, "");
+        }
+        true
+    }
+
+    pub fn cursor_char(&self, window_id: WindowId, selection_id: SelectionId) -> usize {
+        let window = &self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let selection = window.selections[selection_id];
+        match window.mode {
+            Mode::Append => selection.end.char_of(content) + 1,
+            _ => selection.start.char_of(content),
+        }
+    }
+
+    pub fn expand_snippet(&mut self, window_id: WindowId, selection_id: SelectionId) -> bool {
+        let cursor = self.cursor_char(window_id, selection_id);
+        let window = &self.windows[window_id];
+        let buffer = &self.buffers[window.buffer];
+        let content = &buffer.content;
+        let mut start = cursor;
+        while start > 0 && is_word_char(content.char(start - 1)) {
+            start -= 1;
+        }
+        let trigger = content.slice(start..cursor).to_string();
+        let template = match self.config.snippet(buffer.filetype, &trigger) {
+            Some(template) if !trigger.is_empty() => template,
+            _ => return false,
+        };
+        let indent = line_indent(content, Line::from_zero_based(content.char_to_line(cursor)));
+        let expansion = snippet::expand(template, &indent);
+        let buffer_id = window.buffer;
+        let mode = window.mode;
+        self.edit(buffer_id, start..cursor, &expansion.text);
+        if let Mode::Append = mode {
+            let content = &self.buffers[buffer_id].content;
+            let len = expansion.text.chars().count();
+            let selection = &mut self.windows[window_id].selections[selection_id];
+            selection.end = Position::from_char(content, start + len.max(1) - 1);
+        }
+        if expansion.fields.is_empty() {
+            return true;
+        }
+        let window = &mut self.windows[window_id];
+        let session = window.snippet.get_or_insert_with(|| snippet::Session {
+            fields: Vec::new(),
+            current: None,
+            replace: false,
+        });
+        for (i, field) in expansion.fields.into_iter().enumerate() {
+            let field = field.into_iter().map(|r| r.start + start..r.end + start);
+            match session.fields.get_mut(i) {
+                Some(mirrors) => mirrors.extend(field),
+                None => session.fields.push(field.collect()),
+            }
+        }
+        true
+    }
+
+    pub fn jump_snippet_field(&mut self, window_id: WindowId, forward: bool) {
+        let window = &mut self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let session = match &mut window.snippet {
+            Some(session) => session,
+            None => return,
+        };
+        let next = match (session.current, forward) {
+            (None, _) => 0,
+            (Some(current), true) => current + 1,
+            (Some(current), false) => current.saturating_sub(1),
+        };
+        let field = match session.fields.get(next) {
+            Some(field) => field.clone(),
+            None => {
+                window.snippet = None;
+                return;
+            }
+        };
+        session.current = Some(next);
+        session.replace = field.iter().any(|range| !range.is_empty());
+        if next + 1 == session.fields.len() && !session.replace {
+            window.snippet = None;
+        }
+        let last = content.len_chars() - 1;
+        window.selections = field
+            .into_iter()
+            .map(|range| Selection {
+                start: Position::from_char(content, range.start.min(last)),
+                end: Position::from_char(content, range.end.max(range.start + 1).min(last + 1) - 1),
+            })
+            .collect::<Vec<_>>()
+            .into();
+        window.mode = Mode::Insert;
+    }
+
+    pub fn replace_snippet_placeholder(&mut self, window_id: WindowId) -> bool {
+        let field = match &mut self.windows[window_id].snippet {
+            Some(snippet::Session {
+                fields,
+                current: Some(current),
+                replace: replace @ true,
+            }) => {
+                *replace = false;
+                fields[*current].clone()
+            }
+            _ => return false,
+        };
+        for (i, range) in field.into_iter().enumerate() {
+            if !range.is_empty() {
+                self.delete_selection(window_id, SelectionId(i));
+            }
+        }
+        true
+    }
+
+    #[throws(MovementError)]
+    pub fn move_selection(
+        &mut self,
+        window_id: WindowId,
+        selection_id: SelectionId,
+        movement: Movement,
+        drag: bool,
+    ) {
+        let window = &mut self.windows[window_id];
+        let buffer = &mut self.buffers[window.buffer];
+        let selection = &mut window.selections[selection_id];
+        selection.end.move_to(&buffer.content, movement)?;
+        match movement {
+            Movement::Up | Movement::RowUp(..) | Movement::FileStart => window.follow = false,
+            Movement::FileEnd => window.follow = buffer.follow.is_some(),
+            _ => {}
+        }
+        if !drag {
+            selection.start = selection.end;
+        }
+    }
+
+    /// Moves by buffer line, or by screen row in `display_lines` filetypes
+    /// and when wrapping at words.
+    fn vertical_movement(&self, window_id: WindowId, up: bool) -> Movement {
+        let window = &self.windows[window_id];
+        let filetype = self.buffers[window.buffer].filetype;
+        let wrap = self.config.wrap;
+        let rows = self.config.display_lines(filetype) || wrap == Wrap::Words;
+        match (rows, up) {
+            (true, true) => Movement::RowUp(window.text_width, wrap),
+            (true, false) => Movement::RowDown(window.text_width, wrap),
+            (false, true) => Movement::Up,
+            (false, false) => Movement::Down,
+        }
+    }
+
+    #[throws(MovementError)]
+    pub fn move_selections(&mut self, window_id: WindowId, movement: Movement, drag: bool) {
+        for selection_id in self.selections(window_id) {
+            self.move_selection(window_id, selection_id, movement, drag)?;
+        }
+    }
+
+    /// Repeats a movement, stopping early once it can't go further.
+    #[throws(MovementError)]
+    pub fn move_selections_times(
+        &mut self,
+        window_id: WindowId,
+        movement: Movement,
+        drag: bool,
+        times: usize,
+    ) {
+        for i in 0..times {
+            match self.move_selections(window_id, movement, drag) {
+                Ok(()) => {}
+                Err(_) if i > 0 => break,
+                Err(err) => throw!(err),
+            }
+        }
+    }
+
+    #[throws(MovementError)]
+    pub fn shift_selection(
+        &mut self,
+        window_id: WindowId,
+        selection_id: SelectionId,
+        movement: Movement,
+    ) {
+        let window = &mut self.windows[window_id];
+        let buffer = &mut self.buffers[window.buffer];
+        let selection = &mut window.selections[selection_id];
+        selection.start.move_to(&buffer.content, movement)?;
+        selection.end.move_to(&buffer.content, movement)?;
+    }
+
+    #[throws(MovementError)]
+    pub fn shift_selections(&mut self, window_id: WindowId, movement: Movement) {
+        for selection_id in self.selections(window_id) {
+            self.shift_selection(window_id, selection_id, movement)?;
+        }
+    }
+
+    /// Closes the undo step of every buffer edited since the last call, unless
+    /// an insert session is still in progress.
+    fn commit_changes(&mut self) {
+        let focused = &self.windows[self.focused];
+        if let Mode::Insert | Mode::Append = focused.mode {
+            return;
+        }
+        for (i, buffer) in self.buffers.iter_mut().enumerate() {
+            if buffer.changes.is_empty() {
+                continue;
+            }
+            let after = Some(focused.selections.to_vec()).filter(|_| focused.buffer == BufferId(i));
+            buffer.history.push_back(Modification {
+                changes: take(&mut buffer.changes),
+                before: buffer.before.take(),
+                after,
+            });
+            let len = if buffer.large {
+                LARGE_HISTORY_LEN
+            } else {
+                HISTORY_LEN
+            };
+            while buffer.history.len() > len {
+                buffer.history.pop_front();
+            }
+            buffer.future.clear();
+        }
+    }
+
+    /// Fails for read-only buffers.
+    #[throws]
+    fn check_writable(&self, buffer_id: BufferId) {
+        let buffer = &self.buffers[buffer_id];
+        if buffer.read_only {
+            throw!(format_err!("buffer '{}' is read-only", buffer.name));
+        }
+    }
+
+    #[throws]
+    pub fn undo(&mut self, window_id: WindowId) {
+        self.commit_changes();
+        let buffer_id = self.windows[window_id].buffer;
+        self.check_writable(buffer_id)?;
+        let modification = self.buffers[buffer_id]
+            .history
+            .pop_back()
+            .context("nothing to undo")?;
+        for change in modification.changes.iter().rev() {
+            let end = change.at + change.inserted.chars().count();
+            self.edit(buffer_id, change.at..end, &change.removed);
+        }
+        self.buffers[buffer_id].changes.clear();
+        if let Some(before) = &modification.before {
+            self.windows[window_id].selections = before.clone().into();
+        }
+        self.buffers[buffer_id].future.push(modification);
+    }
+
+    #[throws]
+    pub fn redo(&mut self, window_id: WindowId) {
+        let buffer_id = self.windows[window_id].buffer;
+        self.check_writable(buffer_id)?;
+        let modification = self.buffers[buffer_id]
+            .future
+            .pop()
+            .context("nothing to redo")?;
+        for change in modification.changes.iter() {
+            let end = change.at + change.removed.chars().count();
+            self.edit(buffer_id, change.at..end, &change.inserted);
+        }
+        self.buffers[buffer_id].changes.clear();
+        if let Some(after) = &modification.after {
+            self.windows[window_id].selections = after.clone().into();
+        }
+        self.buffers[buffer_id].history.push_back(modification);
+    }
+
+    pub fn edit(&mut self, buffer_id: BufferId, range: Range<usize>, text: &str) {
+        if self.buffers[buffer_id].loading.is_some() {
+            let message = String::from("can't edit a buffer that is still loading");
+            self.show_message(Importance::Error, message);
+            return;
+        }
+        if let Err(err) = self.check_writable(buffer_id) {
+            self.show_message(Importance::Error, err.to_string());
+            return;
+        }
+        let buffer = &mut self.buffers[buffer_id];
+        let mut offsets = Vec::new();
+        for window in self.windows.iter().filter(|w| w.buffer == buffer_id) {
+            for selection in window.selections.iter() {
+                offsets.push((
+                    selection.start.char_of(&buffer.content),
+                    selection.end.char_of(&buffer.content),
+                ));
+            }
+        }
+        if buffer.changes.is_empty() {
+            let focused = &self.windows[self.focused];
+            buffer.before =
+                Some(focused.selections.to_vec()).filter(|_| focused.buffer == buffer_id);
+        }
+        let removed = buffer.content.slice(range.clone()).to_string();
+        let mut recorded = text.to_owned();
+        buffer.content.remove(range.clone());
+        buffer.content.insert(range.start, text);
+        let inserted = text.chars().count();
+        buffer.effects.record(range.clone(), inserted);
+        if buffer.content.len_chars() == 0
+            || buffer.content.char(buffer.content.len_chars() - 1) != '\n'
+        {
+            let end = buffer.content.len_chars();
+            buffer.content.insert_char(end, '\n');
+            buffer.effects.record(end..end, 1);
+            recorded.push('\n');
+        }
+        buffer.changes.push(Change {
+            at: range.start,
+            removed,
+            inserted: recorded,
+        });
+        let last = buffer.content.len_chars() - 1;
+        buffer.last_edit = Some(range.start.min(last));
+        buffer.modified = true;
+        let shift = |offset: usize| {
+            if offset >= range.end {
+                offset - range.len() + inserted
+            } else if offset >= range.start {
+                range.start
+            } else {
+                offset
+            }
+            .min(last)
+        };
+        let content = &buffer.content;
+        for bookmark in buffer.bookmarks.iter_mut() {
+            *bookmark = content.line_to_char(content.char_to_line(shift(*bookmark)));
+        }
+        buffer.bookmarks.dedup();
+        for mark in buffer.marks.values_mut() {
+            *mark = shift(*mark);
+        }
+        for saved in buffer.saved_selections.values_mut() {
+            for (start, end) in saved.iter_mut() {
+                *start = shift(*start);
+                *end = shift(*end);
+            }
+        }
+        let mut offsets = offsets.into_iter();
+        for window in self.windows.iter_mut().filter(|w| w.buffer == buffer_id) {
+            if let Some(session) = &mut window.snippet {
+                session.shift(&range, inserted);
+            }
+            for selection in window.selections.iter_mut() {
+                let (start, end) = offsets.next().unwrap();
+                selection.start = Position::from_char(&buffer.content, shift(start));
+                selection.end = Position::from_char(&buffer.content, shift(end));
+            }
+        }
+    }
+
+    pub fn replace_selection(
+        &mut self,
+        window_id: WindowId,
+        selection_id: SelectionId,
+        text: &str,
+    ) {
+        let window = &mut self.windows[window_id];
+        let buffer_id = window.buffer;
+        let content = &self.buffers[buffer_id].content;
+        let range = window.selections[selection_id]
+            .valid(content)
+            .range_of(content);
+        self.edit(buffer_id, range.clone(), text);
+        let content = &self.buffers[buffer_id].content;
+        let len = text.chars().count();
+        let selection = &mut self.windows[window_id].selections[selection_id];
+        selection.start = Position::from_char(content, range.start);
+        selection.end = Position::from_char(
+            content,
+            (range.start + len.max(1) - 1).min(content.len_chars() - 1),
+        );
+    }
+
+    /// Lists the commands, or the characters, fuzzily matching the palette
+    /// prompt.
+    fn filter_palette(&mut self, window_id: WindowId) {
+        let window = &mut self.windows[window_id];
+        let pattern = &window.palette_prompt.text;
+        let candidates = match window.mode {
+            Mode::Characters => {
+                let names = unicode::names().collect::<Vec<_>>();
+                complete::fuzzy(names.iter().map(|name| &**name), pattern)
+            }
+            _ => complete::fuzzy(self.commands.keys().map(|name| &**name), pattern),
+        };
+        window.completion = Some(complete::Completion {
+            start: 0,
+            candidates,
+            index: 0,
+        });
+    }
+
+    fn cycle_palette(&mut self, window_id: WindowId, forward: bool) {
+        if let Some(completion) = &mut self.windows[window_id].completion {
+            let len = completion.candidates.len().max(1);
+            completion.index = (completion.index + if forward { 1 } else { len - 1 }) % len;
+        }
+    }
+
+    /// Runs the chosen palette command, or opens the command prompt with its
+    /// name if it needs arguments.
+    #[throws]
+    fn run_palette(&mut self, window_id: WindowId, text: &str) {
+        let completion = self.windows[window_id].completion.take();
+        let name = match &completion {
+            Some(completion) if !completion.candidates.is_empty() => {
+                completion.candidates[completion.index].clone()
+            }
+            _ => throw!(format_err!("no command matches '{}'", text)),
+        };
+        let needs_arguments = self.commands[&name]
+            .arguments
+            .iter()
+            .any(|arg| matches!(arg.arity, Arity::Required | Arity::Rest));
+        if needs_arguments {
+            self.set_mode(window_id, Mode::Command);
+            let prompt = &mut self.windows[window_id].prompt;
+            prompt.text = format!("{} ", name);
+            prompt.end();
+        } else {
+            self.command_line(&name)?;
+        }
+    }
+
+    /// Inserts the chosen palette character.
+    #[throws]
+    fn pick_character(&mut self, window_id: WindowId, text: &str) {
+        let completion = self.windows[window_id].completion.take();
+        let c = completion
+            .and_then(|completion| {
+                let candidate = completion.candidates.get(completion.index)?;
+                candidate.chars().next()
+            })
+            .ok_or_else(|| format_err!("no character named '{}'", text))?;
+        self.type_char(window_id, c)?;
+    }
+
+    /// Completes the word before the command prompt's cursor, or replaces the
+    /// last completion with the next candidate.
+    fn complete_command(&mut self, window_id: WindowId) {
+        let window = &mut self.windows[window_id];
+        match &mut window.completion {
+            Some(completion) => {
+                completion.index = (completion.index + 1) % completion.candidates.len();
+            }
+            None => {
+                let (before, _) = window.prompt.split();
+                let commands = &self.commands;
+                let names = commands.keys().map(|name| &**name);
+                let source = |name: &str, index| {
+                    commands
+                        .get(name)
+                        .map_or(Source::Nothing, |cmd| cmd.source(index))
+                };
+                window.completion = complete::complete(before, names, source);
+            }
+        }
+        let completion = match &window.completion {
+            Some(completion) => completion,
+            None => return,
+        };
+        let candidate = &completion.candidates[completion.index];
+        let (_, after) = window.prompt.split();
+        let before = format!("{}{}", &window.prompt.text[..completion.start], candidate);
+        let text = format!("{}{}", before, after);
+        window.prompt.text = text;
+        window.prompt.cursor = before.chars().count();
+        // A single candidate is final, so the next tab completes from it.
+        if completion.candidates.len() == 1 {
+            window.completion = None;
+        }
+    }
+
+    /// Adds a typed key to the change being collected for `.`, starting one
+    /// if the editor was idle in normal mode.
+    fn record_change_key(&mut self, key: Key) {
+        let buffer = self.windows[self.focused].buffer;
+        let revision = self.buffers[buffer].effects.revision();
+        self.change
+            .get_or_insert_with(|| ChangeKeys {
+                keys: Vec::new(),
+                buffer,
+                revision,
+                repeatable: true,
+            })
+            .keys
+            .push(key);
+    }
+
+    /// Keeps the collected keys for `.` once back in normal mode with nothing
+    /// pending, if they changed the buffer.
+    fn finish_change(&mut self) {
+        if !self.waiting() {
+            return;
+        }
+        if let Some(change) = self.change.take() {
+            if change.repeatable
+                && self.buffers[change.buffer].effects.revision() != change.revision
+            {
+                self.last_change = change.keys;
+            }
+        }
+    }
+
+    /// Whether the focused window is in normal mode with no count or register
+    /// typed, waiting for a command.
+    fn waiting(&self) -> bool {
+        let window = &self.windows[self.focused];
+        matches!(window.mode, Mode::Normal) && window.count.is_none() && window.register.is_none()
+    }
+
+    /// Keeps the keys being collected from replacing the last change, for
+    /// commands like undo that change the buffer but shouldn't be repeated.
+    fn not_repeatable(&mut self) {
+        if let Some(change) = &mut self.change {
+            change.repeatable = false;
+        }
+    }
+
+    /// Starts recording keys into register `name`, or stops the recording
+    /// in progress and stores it.
+    pub fn record_macro(&mut self, name: char) {
+        match self.recording.take() {
+            Some((name, mut keys, start)) => {
+                // Drop the command that stopped the recording, with any
+                // count or register typed before it.
+                keys.truncate(start);
+                let register = Register {
+                    values: vec![keys::format(&keys)],
+                    block: false,
+                };
+                self.set_register(name, register);
+                self.show_message(Importance::Info, format!("recorded macro {}", name));
+            }
+            None => self.recording = Some((name, Vec::new(), 0)),
+        }
+    }
+
+    /// Feeds the keys in register `name` through the editor as if typed.
+    #[throws]
+    pub fn replay_macro(&mut self, name: char) {
+        let register = self
+            .register_contents(name)
+            .ok_or_else(|| format_err!("register {} is empty", name))?;
+        self.replay_keys(keys::parse(&register.values.concat())?)?;
+    }
+
+    #[throws]
+    fn replay_keys(&mut self, keys: Vec<Key>) {
+        if self.replaying >= MACRO_DEPTH {
+            throw!(format_err!("macros nested more than {} deep", MACRO_DEPTH));
+        }
+        self.replaying += 1;
+        let result = keys
+            .into_iter()
+            .try_for_each(|key| self.event(Event::Key(key)));
+        self.replaying -= 1;
+        result?;
+    }
+
+    /// Converts the case of every selection, which then covers the new text.
+    pub fn convert_case(&mut self, window_id: WindowId, case: Case) {
+        for selection_id in self.selections(window_id) {
+            let text = self.selection_text(window_id, selection_id);
+            let converted = case.apply(&text);
+            if converted != text {
+                self.replace_selection(window_id, selection_id, &converted);
+            }
+        }
+    }
+
+    /// Replaces every selected char but newlines with `c`.
+    pub fn replace_chars(&mut self, window_id: WindowId, c: char) {
+        for selection_id in self.selections(window_id) {
+            let window = &self.windows[window_id];
+            let content = &self.buffers[window.buffer].content;
+            let text = window.selections[selection_id]
+                .valid(content)
+                .slice_of(content)
+                .chars()
+                .map(|old| if old == '\n' { old } else { c })
+                .collect::<String>();
+            self.replace_selection(window_id, selection_id, &text);
+        }
+    }
+
+    pub fn insert_before(&mut self, window_id: WindowId, selection_id: SelectionId, text: &str) {
+        let window = &self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let start = window.selections[selection_id]
+            .ordered()
+            .start
+            .char_of(content);
+        self.edit(window.buffer, start..start, text);
+    }
+
+    pub fn selection_text(&self, window_id: WindowId, selection_id: SelectionId) -> String {
+        let window = &self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        window.selections[selection_id]
+            .valid(content)
+            .slice_of(content)
+            .to_string()
+    }
+
+    /// Replaces each selection with the output of `command` given its text.
+    #[throws]
+    pub fn pipe_selections(&mut self, window_id: WindowId, command: &str) {
+        for selection_id in self.selections(window_id) {
+            let text = self.selection_text(window_id, selection_id);
+            let mut output = subprocess::run(command, &text)?;
+            if text.ends_with('\n') && !output.ends_with('\n') {
+                output.push('\n');
+            }
+            self.replace_selection(window_id, selection_id, &output);
+        }
+    }
+
+    #[throws]
+    pub fn calc_selections(&mut self, window_id: WindowId) {
+        for selection_id in self.selections(window_id) {
+            let text = self.selection_text(window_id, selection_id);
+            let mut result = calc::format(calc::eval(&text)?);
+            if text.ends_with('\n') {
+                result.push('\n');
+            }
+            self.replace_selection(window_id, selection_id, &result);
+        }
+    }
+
+    pub fn selected_lines(&self, window_id: WindowId) -> Vec<Line> {
+        let window = &self.windows[window_id];
+        let mut lines = window
+            .selections
+            .iter()
+            .flat_map(|selection| {
+                let selection = selection.ordered();
+                (selection.start.line.one_based()..=selection.end.line.one_based())
+                    .map(Line::from_one_based)
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+
+    /// One level of indentation.
+    fn indent_unit(&self) -> String {
+        if self.config.indent_tabs {
+            String::from("\t")
+        } else {
+            " ".repeat(self.config.indent_width.max(1))
+        }
+    }
+
+    /// Adds `levels` of indentation to every non-empty selected line, or
+    /// removes up to that many.
+    pub fn indent_lines(&mut self, window_id: WindowId, deeper: bool, levels: usize) {
+        let buffer_id = self.windows[window_id].buffer;
+        let width = self.config.indent_width.max(1);
+        let unit = self.indent_unit();
+        for line in self.selected_lines(window_id).into_iter().rev() {
+            let content = &self.buffers[buffer_id].content;
+            let start = line.char_of(content);
+            if deeper {
+                if line.text_len_of(content) > 0 {
+                    self.edit(buffer_id, start..start, &unit.repeat(levels));
+                }
+                continue;
+            }
+            let mut chars = line.slice_of(content).chars().peekable();
+            let mut len = 0;
+            for _ in 0..levels {
+                if chars.next_if_eq(&'\t').is_some() {
+                    len += 1;
+                    continue;
+                }
+                let mut spaces = 0;
+                while spaces < width && chars.next_if_eq(&' ').is_some() {
+                    spaces += 1;
+                }
+                len += spaces;
+                if spaces == 0 {
+                    break;
+                }
+            }
+            if len > 0 {
+                self.edit(buffer_id, start..start + len, "");
+            }
+        }
+    }
+
+    /// Extends each selection to whole lines and then by `times - 1` more
+    /// lines, or by `times` if it already covered whole lines, downwards or
+    /// upwards.
+    pub fn select_lines(&mut self, window_id: WindowId, up: bool, times: usize) {
+        let window = &mut self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let last = Line::last(content);
+        let line_start = |line: Line| Position {
+            line,
+            column: Column::from_one_based(1),
+        };
+        let line_end = |line: Line| Position::from_char(content, line.range_of(content).end - 1);
+        for selection in window.selections.iter_mut() {
+            let ordered = selection.ordered();
+            let (mut top, mut bottom) = (ordered.start.line.min(last), ordered.end.line.min(last));
+            let whole = ordered.start == line_start(top) && ordered.end == line_end(bottom);
+            let extra = if whole { times } else { times - 1 };
+            if up {
+                top = Line::from_one_based(top.one_based().saturating_sub(extra).max(1));
+                *selection = Selection {
+                    start: line_end(bottom),
+                    end: line_start(top),
+                };
+            } else {
+                bottom = (bottom + extra).min(last);
+                *selection = Selection {
+                    start: line_start(top),
+                    end: line_end(bottom),
+                };
+            }
+        }
+    }
+
+    /// Joins the lines of each selection, or a single line with the next, by
+    /// replacing each newline and the indentation after it with a space. The
+    /// spaces are selected afterwards.
+    pub fn join_lines(&mut self, window_id: WindowId) {
+        let buffer_id = self.windows[window_id].buffer;
+        let content = &self.buffers[buffer_id].content;
+        let last = Line::last(content);
+        let mut lines = Vec::new();
+        for selection in self.windows[window_id].selections.iter() {
+            let selection = selection.ordered();
+            let end = selection
+                .end
+                .line
+                .max(selection.start.line + 1usize)
+                .min(last);
+            lines.extend(
+                (selection.start.line.one_based()..end.one_based()).map(Line::from_one_based),
+            );
+        }
+        lines.sort();
+        lines.dedup();
+        if lines.is_empty() {
+            return;
+        }
+        // The start and removed length of each join, in the text before any.
+        let joins = lines
+            .iter()
+            .map(|&line| {
+                let next = line + 1usize;
+                let indent = next
+                    .slice_of(content)
+                    .chars()
+                    .take_while(|&c| c == ' ' || c == '\t')
+                    .count();
+                (next.char_of(content) - 1, indent + 1)
+            })
+            .collect::<Vec<_>>();
+        for &(start, len) in joins.iter().rev() {
+            self.edit(buffer_id, start..start + len, " ");
+        }
+        let content = &self.buffers[buffer_id].content;
+        let mut removed = 0;
+        let mut selections = Vec::new();
+        for (start, len) in joins {
+            let position = Position::from_char(content, start - removed);
+            selections.push(Selection {
+                start: position,
+                end: position,
+            });
+            removed += len - 1;
+        }
+        self.windows[window_id].selections = selections.into();
+    }
+
+    pub fn replace_lines(&mut self, buffer_id: BufferId, lines: &[Line], text: &[String]) {
+        for (line, text) in lines.iter().zip(text).rev() {
+            let content = &self.buffers[buffer_id].content;
+            let start = line.char_of(content);
+            self.edit(buffer_id, start..start + line.text_len_of(content), text);
+        }
+    }
+
+    pub fn yank_selections(&mut self, window_id: WindowId, name: char) {
+        let values = self
+            .selections(window_id)
+            .map(|selection_id| self.selection_text(window_id, selection_id))
+            .collect();
+        let block = self.is_block(window_id);
+        self.set_register(name, Register { values, block });
+    }
+
+    /// Stores `register` as `name`. Register `+` is also copied to the system
+    /// clipboard, one value per line.
+    pub fn set_register(&mut self, name: char, register: Register) {
+        if name == '+' && !clipboard::set(&register.values.join("\n")) {
+            warn!("no system clipboard, keeping register + internal");
+        }
+        self.registers.insert(name, register);
+    }
+
+    pub fn is_block(&self, window_id: WindowId) -> bool {
+        let selections = &self.windows[window_id].selections;
+        let mut selections = selections.iter().map(|s| s.ordered()).collect::<Vec<_>>();
+        selections.sort_by_key(|s| s.start);
+        selections.len() > 1
+            && selections.iter().all(|s| s.start.line == s.end.line)
+            && selections.windows(2).all(|pair| {
+                pair[1].start.line == pair[0].start.line + 1usize
+                    && pair[1].start.column == pair[0].start.column
+            })
+    }
+
+    pub fn selection_index(&self, window_id: WindowId, selection_id: SelectionId) -> usize {
+        let selections = &self.windows[window_id].selections;
+        let start = selections[selection_id].ordered().start;
+        selections
+            .iter()
+            .filter(|s| s.ordered().start < start)
+            .count()
+    }
+
+    pub fn rotate_selections(&mut self, window_id: WindowId, forward: bool) {
+        let selections = &mut self.windows[window_id].selections;
+        let primary = selections[SelectionId(0)];
+        selections.sort_by_key(|s| s.ordered().start);
+        let len = selections.len();
+        let index = selections
+            .iter()
+            .position(|s| s.start == primary.start && s.end == primary.end)
+            .unwrap_or(0);
+        let next = if forward {
+            (index + 1) % len
+        } else {
+            (index + len - 1) % len
+        };
+        selections.rotate_left(next);
+    }
+
+    /// Adds `times` copies of each selection on the following lines, or the
+    /// preceding ones, skipping lines too short to hold them unless
+    /// `copy_clamp` is set.
+    pub fn copy_selections(&mut self, window_id: WindowId, down: bool, times: usize) {
+        let window = &mut self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let last = Line::last(content);
+        let mut copies = Vec::<Selection>::new();
+        for selection in window.selections.iter() {
+            let ordered = selection.ordered();
+            let mut offset = 0;
+            let mut added = 0;
+            while added < times {
+                offset += 1;
+                if down && ordered.end.line + offset > last
+                    || !down && offset >= ordered.start.line.one_based()
+                {
+                    break;
+                }
+                let shift = |position: Position| Position {
+                    line: if down {
+                        position.line + offset
+                    } else {
+                        position.line - offset
+                    },
+                    column: position.column,
+                };
+                let copy = Selection {
+                    start: shift(selection.start),
+                    end: shift(selection.end),
+                };
+                let copy = if self.config.copy_clamp {
+                    copy.valid(content)
+                } else if copy.start.is_valid(content) && copy.end.is_valid(content) {
+                    copy
+                } else {
+                    continue;
+                };
+                let duplicate = window
+                    .selections
+                    .iter()
+                    .chain(&copies)
+                    .any(|s| s.start == copy.start && s.end == copy.end);
+                if !duplicate {
+                    copies.push(copy);
+                }
+                added += 1;
+            }
+        }
+        window.selections.extend(copies);
+    }
+
+    pub fn rotate_contents(&mut self, window_id: WindowId, forward: bool) {
+        let mut ids = self.selections(window_id).collect::<Vec<_>>();
+        ids.sort_by_key(|&id| self.windows[window_id].selections[id].ordered().start);
+        let mut texts = ids
+            .iter()
+            .map(|&id| self.selection_text(window_id, id))
+            .collect::<Vec<_>>();
+        if forward {
+            texts.rotate_right(1);
+        } else {
+            texts.rotate_left(1);
+        }
+        for (id, text) in ids.into_iter().zip(texts) {
+            self.replace_selection(window_id, id, &text);
+        }
+    }
+
+    #[throws]
+    pub fn register_values(&self, window_id: WindowId, name: char) -> Vec<String> {
+        match name {
+            '#' => self
+                .selections(window_id)
+                .map(|selection_id| (self.selection_index(window_id, selection_id) + 1).to_string())
+                .collect(),
+            _ => self
+                .register_contents(name)
+                .map(|register| register.values)
+                .ok_or_else(|| format_err!("register {} is empty", name))?,
+        }
+    }
+
+    /// The contents of register `name`, where `+` reads the system clipboard
+    /// if there is one.
+    pub fn register_contents(&self, name: char) -> Option<Register> {
+        match name.to_digit(10) {
+            Some(n) if n > 0 => self.deleted.get(n as usize - 1).cloned(),
+            _ if name == '+' => match clipboard::get() {
+                Some(text) => Some(Register {
+                    values: vec![text],
+                    block: false,
+                }),
+                None => self.registers.get(&name).cloned(),
+            },
+            _ => self.registers.get(&name).cloned(),
+        }
+    }
+
+    #[throws]
+    pub fn insert_register(&mut self, window_id: WindowId, name: char) {
+        let values = self.register_values(window_id, name)?;
+        for selection_id in self.selections(window_id) {
+            let value = &values[selection_id.0 % values.len()];
+            match self.windows[window_id].mode {
+                Mode::Append => self.insert_after(window_id, selection_id, value),
+                _ => self.insert_before(window_id, selection_id, value),
+            }
+        }
+    }
+
+    #[throws]
+    pub fn paste(
+        &mut self,
+        window_id: WindowId,
+        name: char,
+        before: bool,
+        strategy: PasteStrategy,
+    ) {
+        let register = self
+            .register_contents(name)
+            .ok_or_else(|| format_err!("register {} is empty", name))?;
+        if register.block {
+            return self.paste_block(window_id, &register.values, before);
+        }
+        let concatenated = match strategy {
+            PasteStrategy::Concatenate => {
+                let mut text = String::new();
+                for (i, value) in register.values.iter().enumerate() {
+                    text.push_str(value);
+                    if i + 1 < register.values.len() && !value.ends_with('\n') {
+                        text.push('\n');
+                    }
+                }
+                Some(text)
+            }
+            _ => None,
+        };
+        for selection_id in self.selections(window_id) {
+            let value = match (&concatenated, strategy) {
+                (Some(text), _) => text,
+                (None, PasteStrategy::First) => &register.values[0],
+                _ => &register.values[selection_id.0 % register.values.len()],
+            };
+            if before {
+                self.insert_before(window_id, selection_id, value);
+            } else {
+                self.insert_after(window_id, selection_id, value);
+            }
+        }
+    }
+
+    pub fn paste_block(&mut self, window_id: WindowId, values: &[String], before: bool) {
+        let window = &self.windows[window_id];
+        let buffer_id = window.buffer;
+        let selection = window.selections[SelectionId(0)].ordered();
+        let anchor = if before {
+            selection.start
+        } else {
+            selection.end
+        };
+        let content = &self.buffers[buffer_id].content;
+        let column = (anchor.column.zero_based() + usize::from(!before))
+            .min(anchor.line.text_len_of(content));
+        for (k, value) in values.iter().enumerate() {
+            let line = anchor.line + k;
+            let content = &self.buffers[buffer_id].content;
+            if line.zero_based() + 1 >= content.len_lines() {
+                let end = content.len_chars();
+                self.edit(buffer_id, end..end, "\n");
+            }
+            let content = &self.buffers[buffer_id].content;
+            let len = line.text_len_of(content);
+            let mut text = " ".repeat(column.saturating_sub(len));
+            text.push_str(value.trim_end_matches('\n'));
+            let at = line.char_of(content) + len.min(column);
+            self.edit(buffer_id, at..at, &text);
+        }
+    }
+
+    /// Moves the selections, or with `drag` their ends, to the start of
+    /// one-based `line`. Lines past the end go to the last line with an error.
+    #[throws]
+    pub fn goto_line(&mut self, window_id: WindowId, line: usize, drag: bool) {
+        let window = &mut self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let last = Line::last(content).one_based();
+        let target = Position {
+            line: Line::from_one_based(line.clamp(1, last)),
+            column: Column::from_one_based(1),
+        };
+        for selection in window.selections.iter_mut() {
+            selection.end = target;
+            if !drag {
+                selection.start = target;
+            }
+        }
+        window.follow = false;
+        if line > last {
+            throw!(format_err!(
+                "line {} is past the end of the file, which has {} lines",
+                line,
+                last
+            ));
+        }
+    }
+
+    #[throws]
+    pub fn transfer_lines(&mut self, window_id: WindowId, args: &[&str], copy: bool) {
+        let buffer_id = self.windows[window_id].buffer;
+        let content = &self.buffers[buffer_id].content;
+        let current = self.windows[window_id].selections[SelectionId(0)]
+            .end
+            .line
+            .one_based();
+        let last = Line::last(content).one_based();
+        let (start, end) = match args {
+            [_] => {
+                let lines = self.selected_lines(window_id);
+                (lines[0].one_based(), lines[lines.len() - 1].one_based())
+            }
+            [range, _] => address::parse_range(range, current, last)?,
+            _ => throw!(format_err!("expected [range] <address>")),
+        };
+        let target = address::parse(args[args.len() - 1], current, last)?;
+        if !copy && target >= start && target < end {
+            throw!(format_err!("cannot move lines into themselves"));
+        }
+        if !copy && (target == end || target + 1 == start) {
+            return;
+        }
+        let start_char = Line::from_one_based(start).char_of(content);
+        let end_char = (Line::from_one_based(end) + 1usize)
+            .char_of(content)
+            .max(start_char);
+        let mut text = content.slice(start_char..end_char).to_string();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        let target_char = if target == 0 {
+            0
+        } else {
+            let line = Line::from_one_based(target);
+            line.char_of(content) + line.slice_of(content).len_chars()
+        };
+        if copy {
+            self.edit(buffer_id, target_char..target_char, &text);
+            return;
+        }
+        let moved = self.windows[window_id]
+            .selections
+            .iter()
+            .map(|s| (s.start.char_of(content), s.end.char_of(content)))
+            .collect::<Vec<_>>();
+        let len = text.chars().count();
+        let new_start = if target_char > start_char {
+            self.edit(buffer_id, target_char..target_char, &text);
+            self.edit(buffer_id, start_char..end_char, "");
+            target_char - len
+        } else {
+            self.edit(buffer_id, start_char..end_char, "");
+            self.edit(buffer_id, target_char..target_char, &text);
+            target_char
+        };
+        let content = &self.buffers[buffer_id].content;
+        let selections = &mut self.windows[window_id].selections;
+        for (selection, (s, e)) in selections.iter_mut().zip(moved) {
+            if s >= start_char && e < end_char {
+                selection.start = Position::from_char(content, s - start_char + new_start);
+                selection.end = Position::from_char(content, e - start_char + new_start);
+            }
+        }
+    }
+
+    pub fn selection_ranges(&self, window_id: WindowId) -> Vec<Range<usize>> {
+        let window = &self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        window
+            .selections
+            .iter()
+            .map(|selection| selection.valid(content).range_of(content))
+            .collect()
+    }
+
+    #[throws]
+    pub fn search(&mut self, window_id: WindowId, pattern: &str) {
+        let selections = self.selection_ranges(window_id);
+        self.windows[window_id].search = Some(Search::new(pattern, "", selections)?);
+        self.search_next(window_id, true, 1)?;
+    }
+
+    /// Replaces the selections with the non-empty matches of `pattern` inside
+    /// them.
+    #[throws]
+    pub fn select_matches(&mut self, window_id: WindowId, pattern: &str) {
+        let search = Search::new(pattern, "s", self.selection_ranges(window_id))?;
+        let window = &mut self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let text = content.to_string();
+        let selections = search
+            .captures(&text, content)
+            .into_iter()
+            .map(|captures| captures.range())
+            .filter(|range| !range.is_empty())
+            .map(|range| Selection {
+                start: Position::from_byte(content, range.start),
+                end: Position::from_byte(content, range.end - 1),
+            })
+            .collect::<Vec<_>>();
+        if selections.is_empty() {
+            throw!(format_err!("no match for '{}'", pattern));
+        }
+        window.selections = selections.into();
+    }
+
+    /// Keeps only the selections whose text matches `pattern`, or only those
+    /// that don't, failing if none would be left.
+    #[throws]
+    pub fn keep_selections(&mut self, window_id: WindowId, pattern: &str, matching: bool) {
+        let regex = Regex::new(pattern)?;
+        let window = &mut self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let kept = window
+            .selections
+            .iter()
+            .filter(|s| regex.is_match(&s.valid(content).slice_of(content).to_string()) == matching)
+            .copied()
+            .collect::<Vec<_>>();
+        if kept.is_empty() {
+            throw!(format_err!("no selections left"));
+        }
+        window.selections = kept.into();
+    }
+
+    /// Selects the `times`th match after or before the primary selection,
+    /// wrapping around the buffer.
+    #[throws]
+    pub fn search_next(&mut self, window_id: WindowId, forward: bool, times: usize) {
+        let window = &self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let search = window.search.as_ref().context("no previous search")?;
+        let selection = window.selections[SelectionId(0)].ordered();
+        let ranges = search.ranges(content);
+        if ranges.is_empty() {
+            throw!(format_err!("no match for '{}'", search.pattern));
+        }
+        let len = ranges.len();
+        let steps = (times.max(1) - 1) % len;
+        let index = if forward {
+            let cursor = selection.end.char_of(content);
+            let next = ranges
+                .iter()
+                .position(|range| range.start > cursor)
+                .unwrap_or(0);
+            (next + steps) % len
+        } else {
+            let cursor = selection.start.char_of(content);
+            let prev = ranges
+                .iter()
+                .rposition(|range| range.start < cursor)
+                .unwrap_or(len - 1);
+            (prev + len - steps) % len
+        };
+        self.select_range(window_id, ranges[index].clone());
+    }
+
+    /// Finds the matches of the search prompt's pattern and selects the first
+    /// one after the cursor the search started from. Large buffers are skipped.
+    fn preview_search(&mut self, window_id: WindowId) {
+        let window = &mut self.windows[window_id];
+        let buffer = &self.buffers[window.buffer];
+        if buffer.large {
+            return;
+        }
+        let selections = &window.selections;
+        let state = window.search_state.get_or_insert_with(|| SearchState {
+            saved: selections.to_vec(),
+            matches: Vec::new(),
+        });
+        state.matches = Search::new(&window.search_prompt.text, "", Vec::new())
+            .map(|search| search.ranges(&buffer.content))
+            .unwrap_or_default();
+        let saved = state.saved.clone();
+        let cursor = saved[0].ordered().end.char_of(&buffer.content);
+        let first = state
+            .matches
+            .iter()
+            .find(|range| range.start > cursor)
+            .or_else(|| state.matches.first())
+            .cloned();
+        match first {
+            Some(range) => self.select_range(window_id, range),
+            None => self.windows[window_id].selections = saved.into(),
+        }
+    }
+
+    /// Drops the search preview, restoring the selections from before it.
+    fn end_search_preview(&mut self, window_id: WindowId) {
+        let window = &mut self.windows[window_id];
+        if let Some(state) = window.search_state.take() {
+            window.selections = state.saved.into();
+        }
+    }
+
+    pub fn select_range(&mut self, window_id: WindowId, range: Range<usize>) {
+        let window = &mut self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let last = content.len_chars() - 1;
+        window.selections = vec![Selection {
+            start: Position::from_char(content, range.start.min(last)),
+            end: Position::from_char(content, (range.end.max(range.start + 1) - 1).min(last)),
+        }]
+        .into();
+    }
+
+    #[throws]
+    pub fn substitute(&mut self, window_id: WindowId, args: &[&str]) -> usize {
+        let (pattern, replacement, flags) = match args {
+            [pattern, replacement] => (pattern, replacement, ""),
+            [pattern, replacement, flags] => (pattern, replacement, *flags),
+            _ => throw!(format_err!("expected <pattern> <replacement> [flags]")),
+        };
+        let preserve_case = flags.contains('p');
+        let mut flags = flags.replace('p', "");
+        if preserve_case && !flags.contains('i') {
+            flags.push('i');
+        }
+        let search = Search::new(pattern, &flags, self.selection_ranges(window_id))?;
+        let buffer_id = self.windows[window_id].buffer;
+        let content = &self.buffers[buffer_id].content;
+        let text = content.to_string();
+        let matches = search.captures(&text, content);
+        for captures in matches.iter().rev() {
+            let range = captures.range();
+            let mut value = substitute::expand(replacement, captures, &text);
+            if preserve_case {
+                value = substitute::preserve_case(&text[range.clone()], &value);
+            }
+            let content = &self.buffers[buffer_id].content;
+            let range = content.byte_to_char(range.start)..content.byte_to_char(range.end);
+            self.edit(buffer_id, range, &value);
+        }
+        matches.len()
+    }
+
+    pub fn delete_selection(&mut self, window_id: WindowId, selection_id: SelectionId) {
+        let window = &self.windows[window_id];
+        let content = &self.buffers[window.buffer].content;
+        let range = window.selections[selection_id]
+            .valid(content)
+            .range_of(content);
+        self.edit(window.buffer, range, "");
+    }
+
+    /// Keeps the texts of the selections about to be deleted by `d` or `c` in
+    /// the ring of recent deletions.
+    fn record_deleted(&mut self, window_id: WindowId) {
+        let values = self
+            .selections(window_id)
+            .map(|selection_id| self.selection_text(window_id, selection_id))
+            .collect();
+        let block = self.is_block(window_id);
+        self.deleted.push_front(Register { values, block });
+        self.deleted.truncate(DELETED_LEN);
+    }
+
+    pub fn delete_selections(&mut self, window_id: WindowId) {
+        for selection_id in self.selections(window_id) {
+            self.delete_selection(window_id, selection_id);
+        }
+    }
+
+    pub fn flip_selection(&mut self, window_id: WindowId, selection_id: SelectionId) {
+        let window = &mut self.windows[window_id];
+        let selection = &mut window.selections[selection_id];
+        selection.flip();
+    }
+
+    pub fn order_selections(&mut self, window_id: WindowId) {
+        for selection_id in self.selections(window_id) {
+            self.order_selection(window_id, selection_id);
+        }
+    }
+
+    pub fn order_selection(&mut self, window_id: WindowId, selection_id: SelectionId) {
+        let window = &mut self.windows[window_id];
+        let selection = &mut window.selections[selection_id];
+        selection.order();
+    }
+
+    pub fn flip_selections(&mut self, window_id: WindowId) {
+        for selection_id in self.selections(window_id) {
+            self.flip_selection(window_id, selection_id);
+        }
+    }
+
+    pub fn for_each_selection<F>(&mut self, window_id: WindowId, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&mut Self, WindowId, SelectionId) -> Result<(), Error>,
+    {
+        let mut errors = Vec::new();
+        for selection_id in self.selections(window_id) {
+            if let Err(e) = f(self, window_id, selection_id) {
+                errors.push(e);
+            }
+        }
+        errors.pop().map_or(Ok(()), Err)
+    }
+}
+
+impl Drop for Edot {
+    fn drop(&mut self) {
+        let _ = write!(
+            self.output,
+            "{}{}{}",
+            cursor::Show,
+            cursor::SteadyBlock,
+            screen::ToMainScreen
+        );
+        if self.title.is_some() {
+            let _ = write!(self.output, "\x1b[23;2t");
+        }
+        if self.cursor_color.is_some() {
+            // Restore the terminal's own cursor color.
+            let _ = write!(self.output, "\x1b]112\x07");
+        }
+    }
+}
+
+pub struct Window {
+    buffer: BufferId,
+    mode: Mode,
+    /// The first selection is the primary one, which is drawn differently,
+    /// scrolled to and used by single-selection commands; `(` and `)` rotate it.
+    selections: IdVec<SelectionId, Selection>,
+    prompt: Prompt,
+    search_prompt: Prompt,
+    shell_prompt: Prompt,
+    palette_prompt: Prompt,
+    /// The register chosen with `"` for the next command.
+    register: Option<char>,
+    /// A count typed before the next command.
+    count: Option<usize>,
+    top: Line,
+    /// Display columns scrolled past on the left when lines aren't wrapped.
+    left: usize,
+    /// Number of lines the window was last drawn with.
+    height: usize,
+    /// Columns available for text after the gutter, also set when drawing.
+    text_width: usize,
+    follow: bool,
+    unicode: Option<UnicodeInput>,
+    snippet: Option<snippet::Session>,
+    register_pending: bool,
+    search: Option<Search>,
+    /// Command line completion candidates, while tab is being pressed.
+    completion: Option<complete::Completion>,
+    /// The word completion popup in Insert mode, while it is open.
+    words: Option<complete::Words>,
+    /// The lines of a language server hover, shown until the next key.
+    hover: Option<Vec<String>>,
+    /// The search being typed, previewed until it is entered or cancelled.
+    search_state: Option<SearchState>,
+    /// The text rows last drawn, for mapping mouse clicks to positions.
+    rows: Vec<Row>,
+    /// The screen column text starts at after the gutter.
+    text_x: u16,
+    /// The primary cursor and buffer revision the view was last scrolled for.
+    scrolled_for: Option<(Position, usize)>,
+    /// What the window was last drawn from, and where its cursor was then.
+    drawn: Option<Drawn>,
+    cursor: Option<Point>,
+}
+
+impl Window {
+    fn new(buffer: BufferId) -> Self {
+        let position = Position {
+            line: Line::from_one_based(1),
+            column: Column::from_one_based(1),
+        };
+        Self {
+            buffer,
+            mode: Mode::Normal,
+            selections: vec![Selection {
+                start: position,
+                end: position,
+            }]
+            .into(),
+            prompt: Prompt::default(),
+            search_prompt: Prompt::default(),
+            shell_prompt: Prompt::default(),
+            palette_prompt: Prompt::default(),
+            register: None,
+            count: None,
+            top: Line::from_one_based(1),
+            height: 1,
+            left: 0,
+            text_width: 1,
+            follow: false,
+            unicode: None,
+            snippet: None,
+            register_pending: false,
+            search: None,
+            completion: None,
+            words: None,
+            hover: None,
+            search_state: None,
+            rows: Vec::new(),
+            text_x: 1,
+            scrolled_for: None,
+            drawn: None,
+            cursor: None,
+        }
+    }
+
+    /// The position drawn at screen cell `x`, `y`, or the nearest one on the
+    /// same row, or on the closest row when `y` is outside the window.
+    fn position_at(&self, content: &Rope, x: u16, y: u16) -> Option<Position> {
+        let row = self
+            .rows
+            .iter()
+            .rev()
+            .find(|row| row.y <= y)
+            .or_else(|| self.rows.first())?;
+        let line = Line::from_zero_based(row.line);
+        let slice = line.slice_of(content);
+        let len = slice.len_chars().max(1);
+        let offset = usize::from(x.saturating_sub(self.text_x)) + self.left;
+        let mut column = row.column;
+        let mut used = 0;
+        for c in slice.chars().skip(row.column) {
+            let width = unicode::width(c);
+            if width > 0 && used + width > offset {
+                break;
+            }
+            used += width;
+            column += 1;
+        }
+        Some(Position {
+            line,
+            column: Column::from_zero_based(column.min(len - 1)),
+        })
+    }
+
+    /// Scrolls by `lines`, down if positive, keeping the last line visible.
+    fn scroll_by(&mut self, content: &Rope, lines: isize) {
+        let last = Line::last(content).one_based();
+        let top = self.top.one_based().saturating_add_signed(lines);
+        self.top = Line::from_one_based(top.clamp(1, last));
+        if lines < 0 {
+            self.follow = false;
+        }
+    }
+
+    fn prompt_mut(&mut self) -> &mut Prompt {
+        match self.mode {
+            Mode::Search | Mode::Select => &mut self.search_prompt,
+            Mode::Keep { .. } => &mut self.search_prompt,
+            Mode::Pipe => &mut self.shell_prompt,
+            Mode::Palette | Mode::Characters => &mut self.palette_prompt,
+            _ => &mut self.prompt,
+        }
+    }
+
+    fn cursor_line(&self) -> usize {
+        self.selections[SelectionId(0)].end.line.zero_based()
+    }
+
+    fn scrolloff(&self, scrolloff: usize) -> usize {
+        scrolloff.min(self.height.saturating_sub(1) / 2)
+    }
+
+    /// Scrolls sideways just enough to keep the primary cursor off the
+    /// columns that clipped lines are marked in.
+    fn scroll_sideways(&mut self, content: &Rope) {
+        let end = self.selections[SelectionId(0)].valid(content).end;
+        let mut chars = end.line.slice_of(content).chars();
+        let column = chars
+            .by_ref()
+            .take(end.column.zero_based())
+            .map(unicode::width)
+            .sum::<usize>();
+        let width = chars.next().map_or(1, unicode::width).max(1);
+        if self.left > 0 && column <= self.left {
+            self.left = column.saturating_sub(1);
+        }
+        if column + width + 1 > self.left + self.text_width {
+            self.left = (column + width + 1).saturating_sub(self.text_width);
+        }
+    }
+
+    /// Scrolls just enough to keep the primary cursor `scrolloff` lines away
+    /// from the window edges.
+    fn scroll_to_cursor(&mut self, scrolloff: usize) {
+        let scrolloff = self.scrolloff(scrolloff);
+        let line = self.cursor_line();
+        let top = self
+            .top
+            .zero_based()
+            .min(line.saturating_sub(scrolloff))
+            .max((line + scrolloff + 1).saturating_sub(self.height));
+        self.top = Line::from_zero_based(top);
+    }
+
+    /// Counts selections entirely above and below the visible lines.
+    fn offscreen_selections(&self) -> (usize, usize) {
+        let top = self.top.zero_based();
+        let bottom = top + self.height;
+        let mut counts = (0, 0);
+        for selection in self.selections.iter().map(|s| s.ordered()) {
+            if selection.end.line.zero_based() < top {
+                counts.0 += 1;
+            } else if selection.start.line.zero_based() >= bottom {
+                counts.1 += 1;
+            }
+        }
+        counts
+    }
+
+    fn align_view(&mut self, view: View, scrolloff: usize) {
+        let scrolloff = self.scrolloff(scrolloff);
+        let line = self.cursor_line();
+        let top = match view {
+            View::Top => line.saturating_sub(scrolloff),
+            View::Center => line.saturating_sub(self.height / 2),
+            View::Bottom => (line + scrolloff + 1).saturating_sub(self.height),
+        };
+        self.top = Line::from_zero_based(top);
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Redirect {
+    Register(char),
+    Buffer,
+}
+
+/// What a screen region shows, for mouse clicks.
+#[derive(Debug, Copy, Clone)]
+pub enum Hit {
+    Tab(WindowId),
+    Window(WindowId),
+    Mode,
+    /// A split separator, with the region of the layout it divides.
+    Separator(Rect),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Case {
+    Swap,
+    Lower,
+    Upper,
+}
+
+impl Case {
+    /// Converts `text`, where a char may become several, like `ß` to `SS`.
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Case::Swap => {
+                let mut swapped = String::with_capacity(text.len());
+                for c in text.chars() {
+                    if c.is_lowercase() {
+                        swapped.extend(c.to_uppercase());
+                    } else if c.is_uppercase() {
+                        swapped.extend(c.to_lowercase());
+                    } else {
+                        swapped.push(c);
+                    }
+                }
+                swapped
+            }
+            Case::Lower => text.to_lowercase(),
+            Case::Upper => text.to_uppercase(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum View {
+    Top,
+    Center,
+    Bottom,
+}
+
+id!(SelectionId);
+
+pub struct Buffer {
+    path: Option<PathBuf>,
+    name: String,
+    filetype: Option<&'static str>,
+    content: Rope,
+    history: VecDeque<Modification>,
+    /// Undone steps, most recent last.
+    future: Vec<Modification>,
+    /// Edits not yet grouped into a step, with the selections before them.
+    changes: Vec<Change>,
+    before: Option<Vec<Selection>>,
+    follow: Option<Sender<()>>,
+    macro_register: Option<char>,
+    /// Character offsets of the starts of bookmarked lines, in order.
+    bookmarks: Vec<usize>,
+    /// Character offsets set with `m`, by name.
+    marks: HashMap<char, usize>,
+    /// Selections saved with `Z`, as the char offsets of their ends, by
+    /// register.
+    saved_selections: HashMap<char, Vec<(usize, usize)>>,
+    /// Character offset of the most recent edit, for `g.`.
+    last_edit: Option<usize>,
+    /// Whether there are changes since the file was last read or written.
+    modified: bool,
+    /// Set for files over `large_file_size`; expensive features skip these.
+    large: bool,
+    /// Bytes read and the file size, while the file is read in the background.
+    loading: Option<(u64, u64)>,
+    /// How the file was stored, kept when writing it.
+    format: Format,
+    /// When the file was last modified as of reading or writing it.
+    mtime: Option<SystemTime>,
+    /// Set once the file is found to have changed on disk while the buffer
+    /// had unsaved changes, so that it is reported once.
+    disk_changed: bool,
+    /// Set for files opened with `-R` or `:view` and ones that can't be
+    /// written, which can't be edited either.
+    read_only: bool,
+    effects: effect::Log,
+    highlights: highlight::Cache,
+    /// The buffer as its language server last saw it, once it is open there.
+    document: Option<lsp::Document>,
+    /// What the language server last reported, mapped to the current text
+    /// through `effects`.
+    diagnostics: Vec<lsp::Diagnostic>,
+}
+
+impl Buffer {
+    fn new(name: String, content: Rope, path: Option<PathBuf>) -> Self {
+        Self {
+            filetype: path.as_deref().and_then(filetype::detect),
+            path,
+            name,
+            content,
+            history: VecDeque::new(),
+            future: Vec::new(),
+            changes: Vec::new(),
+            before: None,
+            follow: None,
+            macro_register: None,
+            bookmarks: Vec::new(),
+            marks: HashMap::new(),
+            saved_selections: HashMap::new(),
+            last_edit: None,
+            modified: false,
+            large: false,
+            loading: None,
+            format: Format::default(),
+            mtime: None,
+            disk_changed: false,
+            read_only: false,
+            effects: effect::Log::default(),
+            highlights: highlight::Cache::default(),
+            document: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Writes the buffer to its file, or to `path`, which becomes its file if
+    /// it had none, returning where it went, in what format, and whether any
+    /// chars couldn't be represented. Only writing its own file marks the
+    /// buffer as saved.
+    #[throws]
+    fn write(
+        &mut self,
+        path: Option<&str>,
+        force: bool,
+        config: &Config,
+    ) -> (PathBuf, Format, bool) {
+        if self.loading.is_some() {
+            throw!(format_err!("buffer '{}' is still loading", self.name));
+        }
+        if self.read_only && path.is_none() {
+            throw!(format_err!("buffer '{}' is read-only", self.name));
+        }
+        let own = path.is_none() || self.path.is_none();
+        if let (true, false, Some(seen), Some(path)) = (own, force, self.mtime, &self.path) {
+            if modified_time(path) != Some(seen) {
+                throw!(format_err!(
+                    "{} changed on disk since it was read (:w! overwrites it, :e! reloads it)",
+                    self.name
+                ));
+            }
+        }
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => self
+                .path
+                .clone()
+                .ok_or_else(|| format_err!("buffer '{}' has no file name", self.name))?,
+        };
+        let format = Format {
+            final_newline: self.format.final_newline || config.final_newline,
+            ..self.format
+        };
+        let lossy = save::write(&self.content, format, &path, config.backup)?;
+        if self.path.is_none() {
+            self.name = path.display().to_string();
+            self.filetype = filetype::detect(&path);
+            self.path = Some(path.canonicalize()?);
+        }
+        if own {
+            self.modified = false;
+            self.mtime = modified_time(&path);
+            self.disk_changed = false;
+        }
+        (path, format, lossy)
+    }
+
+    /// Whether drawing should highlight this buffer.
+    fn highlighted(&self, config: &Config) -> bool {
+        config.highlight && !self.large && self.filetype.and_then(highlight::syntax).is_some()
+    }
+
+    /// Highlights the lines before zero-based `end` that aren't cached yet.
+    fn highlight(&mut self, end: usize) {
+        if let Some(syntax) = self.filetype.and_then(highlight::syntax) {
+            self.highlights
+                .update(&self.content, &self.effects, syntax, end);
+        }
+    }
+}
+
+/// Fails if any buffer with a file has changes that weren't written.
+#[throws]
+fn check_saved<'a>(buffers: impl Iterator<Item = &'a Buffer>) {
+    let unsaved = buffers
+        .filter(|buffer| buffer.modified && buffer.path.is_some())
+        .map(|buffer| &*buffer.name)
+        .collect::<Vec<_>>();
+    if !unsaved.is_empty() {
+        throw!(format_err!(
+            "unsaved changes in {} (use :q! to discard them)",
+            unsaved.join(", ")
+        ));
+    }
+}
+
+/// One undo step, with the selections of the editing window around it.
+#[derive(Debug, Clone)]
+pub struct Modification {
+    changes: Vec<Change>,
+    before: Option<Vec<Selection>>,
+    after: Option<Vec<Selection>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Change {
+    at: usize,
+    removed: String,
+    inserted: String,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Mode {
+    Normal,
+    Insert,
+    Append,
+    Goto {
+        drag: bool,
+    },
+    View,
+    Object {
+        around: bool,
+    },
+    Register,
+    /// Waiting for the char to replace selected chars with.
+    Replace,
+    /// Waiting for the name of a mark to set or jump to.
+    SetMark,
+    JumpMark,
+    /// Waiting for how to combine the selections saved to `register` with
+    /// the current ones: `a` appends, `u` unites and `i` intersects them.
+    CombineSelections {
+        register: char,
+    },
+    Surround,
+    /// Waiting for the char whose pair to surround selections with.
+    SurroundAdd,
+    /// Waiting for the char whose pair around selections to delete.
+    SurroundDelete,
+    /// Waiting for the char whose pair around selections to change, then for
+    /// the char of the pair to change it to.
+    SurroundChange {
+        from: Option<char>,
+    },
+    Window,
+    Command,
+    Search,
+    Select,
+    /// Prompting for a regex to filter the selections by.
+    Keep {
+        matching: bool,
+    },
+    /// Prompting for a command to pipe the selections through.
+    Pipe,
+    /// Filtering the command list to run one.
+    Palette,
+    /// Filtering the character names to insert one.
+    Characters,
+}
+
+/// How restored selections replace or combine with the current ones.
+#[derive(Debug, Copy, Clone)]
+pub enum Combine {
+    Replace,
+    /// Adds the saved selections after the current ones.
+    Append,
+    /// Extends each selection to cover the saved one at the same index.
+    Union,
+    /// Shrinks each selection to the part the saved one at the same index
+    /// covers, dropping it if they don't overlap.
+    Intersect,
+}
+
+impl Mode {
+    /// The keymap mode the mode's keys are looked up in, if any.
+    fn keymap(self) -> Option<&'static str> {
+        match self {
+            Mode::Normal => Some("normal"),
+            Mode::Goto { .. } => Some("goto"),
+            Mode::View => Some("view"),
+            Mode::Window => Some("window"),
+            Mode::Object { .. } => Some("object"),
+            Mode::Surround => Some("surround"),
+            _ => None,
+        }
+    }
+
+    /// The OSC 12 cursor color for the mode, or `None` for prompt modes.
+    fn cursor_color(self) -> Option<&'static str> {
+        match self {
+            Mode::Normal => Some("white"),
+            Mode::Insert | Mode::Append => Some("green"),
+            Mode::Goto { .. }
+            | Mode::View
+            | Mode::Object { .. }
+            | Mode::Register
+            | Mode::Replace
+            | Mode::SetMark
+            | Mode::JumpMark
+            | Mode::CombineSelections { .. }
+            | Mode::Surround
+            | Mode::SurroundAdd
+            | Mode::SurroundDelete
+            | Mode::SurroundChange { .. }
+            | Mode::Window => Some("yellow"),
+            Mode::Command
+            | Mode::Search
+            | Mode::Select
+            | Mode::Keep { .. }
+            | Mode::Pipe
+            | Mode::Palette
+            | Mode::Characters => None,
+        }
+    }
+}
+
+/// Keys typed since the editor was last idle in normal mode.
+#[derive(Debug)]
+struct ChangeKeys {
+    keys: Vec<Key>,
+    /// The buffer focused when the keys started, and its revision then.
+    buffer: BufferId,
+    revision: usize,
+    repeatable: bool,
+}
+
+/// Everything a window's text area is drawn from, so that it is only drawn
+/// again when some of it changes.
+#[derive(Debug, Eq, PartialEq)]
+struct Drawn {
+    region: Rect,
+    revision: usize,
+    filetype: Option<&'static str>,
+    top: Line,
+    left: usize,
+    selections: Vec<Selection>,
+    focused: bool,
+    matches: Vec<Range<usize>>,
+    bookmarks: Vec<usize>,
+    /// Zero-based lines with diagnostics, with the most severe for each.
+    diagnostics: Vec<(usize, Severity)>,
+}
+
+/// Where a screen row of a window starts in the text, as zero-based line and
+/// column.
+#[derive(Debug, Copy, Clone)]
+struct Row {
+    y: u16,
+    line: usize,
+    column: usize,
+}
+
+/// A search pattern being typed, with its matches highlighted and the primary
+/// selection moved to the first one.
+pub struct SearchState {
+    /// The selections from before the search, restored if it is cancelled.
+    saved: Vec<Selection>,
+    /// Character ranges of the matches, empty while the pattern is invalid.
+    matches: Vec<Range<usize>>,
+}
+
+pub enum UnicodeInput {
+    Codepoint(String),
+    Digraph(Option<char>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Register {
+    values: Vec<String>,
+    block: bool,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Importance {
+    Error,
+    Info,
+}
+
+pub struct Context<'a> {
+    editor: &'a mut Edot,
+    window: WindowId,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Arity {
+    Required,
+    Optional,
+    /// Zero or more words.
+    Variadic,
+    /// One or more words.
+    Rest,
+}
+
+/// One argument in a command's schema.
+#[derive(Debug, Copy, Clone)]
+pub struct Argument {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub complete: Source,
+    /// Whether the command line passes the rest of the line as typed,
+    /// instead of splitting it into words, for shell commands.
+    pub raw: bool,
+}
+
+impl Argument {
+    const fn new(name: &'static str, arity: Arity) -> Self {
+        Argument {
+            name,
+            arity,
+            complete: Source::Nothing,
+            raw: false,
+        }
+    }
+
+    pub const fn required(name: &'static str) -> Self {
+        Self::new(name, Arity::Required)
+    }
+
+    pub const fn optional(name: &'static str) -> Self {
+        Self::new(name, Arity::Optional)
+    }
+
+    pub const fn variadic(name: &'static str) -> Self {
+        Self::new(name, Arity::Variadic)
+    }
+
+    pub const fn rest(name: &'static str) -> Self {
+        Self::new(name, Arity::Rest)
+    }
+
+    pub const fn completing(self, complete: Source) -> Self {
+        Argument { complete, ..self }
+    }
+
+    pub const fn raw(self) -> Self {
+        Argument { raw: true, ..self }
+    }
+
+    fn usage(&self) -> String {
+        match self.arity {
+            Arity::Required => format!("<{}>", self.name),
+            Arity::Optional => format!("[{}]", self.name),
+            Arity::Variadic => format!("[{}...]", self.name),
+            Arity::Rest => format!("<{}...>", self.name),
+        }
+    }
+}
+
+pub trait Command: Sized {
+    const DESCRIPTION: &'static str;
+    /// The arguments, in order. Only the last may be variadic or rest, and
+    /// only a sole argument may be raw.
+    const ARGUMENTS: &'static [Argument] = &[];
+
+    fn run(cx: Context, args: &[&str]) -> Result;
+}
+
+pub struct CommandDesc {
+    description: &'static str,
+    arguments: &'static [Argument],
+    run: fn(cx: Context, args: &[&str]) -> Result,
+}
+
+impl CommandDesc {
+    fn of<T: Command>() -> Self {
+        Self {
+            description: T::DESCRIPTION,
+            arguments: T::ARGUMENTS,
+            run: T::run,
+        }
+    }
+
+    /// The argument list as shown in usage messages, like `<path> [flags]`.
+    fn usage(&self) -> String {
+        self.arguments
+            .iter()
+            .map(Argument::usage)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether the command takes the rest of the line as typed.
+    fn raw(&self) -> bool {
+        matches!(self.arguments, [arg] if arg.raw)
+    }
+
+    /// Whether `count` arguments fit the schema.
+    fn accepts(&self, count: usize) -> bool {
+        let min = self
+            .arguments
+            .iter()
+            .filter(|arg| matches!(arg.arity, Arity::Required | Arity::Rest))
+            .count();
+        let unbounded = self
+            .arguments
+            .iter()
+            .any(|arg| matches!(arg.arity, Arity::Variadic | Arity::Rest));
+        count >= min && (unbounded || count <= self.arguments.len())
+    }
+
+    /// How to complete the argument at `index`.
+    fn source(&self, index: usize) -> Source {
+        match self.arguments.get(index).or_else(|| {
+            self.arguments
+                .last()
+                .filter(|arg| matches!(arg.arity, Arity::Variadic | Arity::Rest))
+        }) {
+            Some(arg) => arg.complete,
+            None => Source::Nothing,
+        }
+    }
+}
+
+enum Quit {}
+
+impl Command for Quit {
+    const DESCRIPTION: &'static str = "quits the editor, unless files have unsaved changes";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.check_saved()?;
+        cx.editor.quit();
+    }
+}
+
+enum ForceQuit {}
+
+impl Command for ForceQuit {
+    const DESCRIPTION: &'static str = "quits the editor, discarding unsaved changes";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.quit();
+    }
+}
+
+enum WriteQuit {}
+
+impl Command for WriteQuit {
+    const DESCRIPTION: &'static str = "write the buffer and quit";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("path").completing(Source::Path)];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor
+            .write_buffer(cx.window, args.first().copied(), false)?;
+        cx.editor.check_saved()?;
+        cx.editor.quit();
+    }
+}
+
+enum Exit {}
+
+impl Command for Exit {
+    const DESCRIPTION: &'static str = "write the buffer if it is modified and quit";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        if cx.editor.buffers[cx.editor.windows[cx.window].buffer].modified {
+            cx.editor.write_buffer(cx.window, None, false)?;
+        }
+        cx.editor.check_saved()?;
+        cx.editor.quit();
+    }
+}
+
+enum Edit {}
+
+impl Command for Edit {
+    const DESCRIPTION: &'static str = "open a file";
+    const ARGUMENTS: &'static [Argument] = &[Argument::required("path").completing(Source::Path)];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let name = String::from(args[0]);
+        let path = PathBuf::from(&name).canonicalize()?;
+        let window_id = cx.editor.open_file(name, path)?;
+        cx.editor.restore_position(window_id);
+    }
+}
+
+enum ReloadFile {}
+
+impl Command for ReloadFile {
+    const DESCRIPTION: &'static str = "read the buffer's file again, discarding unsaved changes";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        let buffer_id = cx.editor.windows[cx.window].buffer;
+        cx.editor.reload(buffer_id)?;
+        let message = format!("reloaded {}", cx.editor.buffers[buffer_id].name);
+        cx.editor.show_message(Importance::Info, message);
+    }
+}
+
+enum ViewCommand {}
+
+impl Command for ViewCommand {
+    const DESCRIPTION: &'static str = "open a file read-only, or make the buffer read-only";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("path").completing(Source::Path)];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let window_id = match args.first() {
+            Some(&name) => {
+                let path = PathBuf::from(name).canonicalize()?;
+                let window_id = cx.editor.open_file(String::from(name), path)?;
+                cx.editor.restore_position(window_id);
+                window_id
+            }
+            None => cx.window,
+        };
+        cx.editor.buffers[cx.editor.windows[window_id].buffer].read_only = true;
+        cx.editor.statusline_dirty = true;
+    }
+}
+
+enum Deleted {}
+
+impl Command for Deleted {
+    const DESCRIPTION: &'static str =
+        "list recently deleted texts, or paste one after the selections";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("index")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        if let Some(arg) = args.first() {
+            let index = arg
+                .parse::<u32>()
+                .ok()
+                .filter(|index| (1..=DELETED_LEN as u32).contains(index))
+                .ok_or_else(|| format_err!("invalid index '{}'", arg))?;
+            let name = char::from_digit(index, 10).unwrap();
+            let strategy = cx.editor.config.paste;
+            cx.editor.paste(cx.window, name, false, strategy)?;
+            return;
+        }
+        let list = cx
+            .editor
+            .deleted
+            .iter()
+            .enumerate()
+            .map(|(i, register)| {
+                let text = register.values.join(" ").replace('\n', "␤");
+                format!("{}: {}", i + 1, text.chars().take(20).collect::<String>())
+            })
+            .collect::<Vec<_>>();
+        if list.is_empty() {
+            throw!(format_err!("nothing has been deleted"));
+        }
+        cx.editor.show_message(Importance::Info, list.join("  "));
+    }
+}
+
+enum Bookmarks {}
+
+impl Command for Bookmarks {
+    const DESCRIPTION: &'static str = "list bookmarks in all buffers, or jump to one from the list";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("index")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let bookmarks = cx
+            .editor
+            .buffers
+            .iter()
+            .enumerate()
+            .flat_map(|(i, buffer)| {
+                buffer
+                    .bookmarks
+                    .iter()
+                    .map(move |&bookmark| (BufferId(i), bookmark))
+            })
+            .collect::<Vec<_>>();
+        if bookmarks.is_empty() {
+            throw!(format_err!("no bookmarks"));
+        }
+        if let Some(arg) = args.first() {
+            let (buffer_id, bookmark) = arg
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| bookmarks.get(index.checked_sub(1)?))
+                .copied()
+                .ok_or_else(|| format_err!("invalid index '{}'", arg))?;
+            let window_id = cx.editor.focus_buffer(buffer_id);
+            cx.editor.select_range(window_id, bookmark..bookmark + 1);
+            return;
+        }
+        let mut list = String::new();
+        for (index, &(buffer_id, bookmark)) in bookmarks.iter().enumerate() {
+            let buffer = &cx.editor.buffers[buffer_id];
+            let line = buffer.content.char_to_line(bookmark);
+            list.push_str(&format!(
+                "{}\t{}:{}\t{}",
+                index + 1,
+                buffer.name,
+                line + 1,
+                buffer.content.line(line)
+            ));
+        }
+        cx.editor.open(Buffer::new(
+            "*bookmarks*".to_owned(),
+            Rope::from(list),
+            None,
+        ));
+    }
+}
+
+enum Split {}
+
+impl Command for Split {
+    const DESCRIPTION: &'static str = "split the window into two stacked windows";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.split(Orientation::Horizontal);
+    }
+}
+
+enum VerticalSplit {}
+
+impl Command for VerticalSplit {
+    const DESCRIPTION: &'static str = "split the window into two side by side";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.split(Orientation::Vertical);
+    }
+}
+
+enum SplitHere {}
+
+impl Command for SplitHere {
+    const DESCRIPTION: &'static str = "open another window on the current buffer";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        let window_id = cx.editor.split_window(cx.window);
+        cx.editor.focus(window_id);
+    }
+}
+
+enum WriteFile {}
+
+impl Command for WriteFile {
+    const DESCRIPTION: &'static str = "write the buffer to its file or to a path";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("path").completing(Source::Path)];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor
+            .write_buffer(cx.window, args.first().copied(), false)?;
+    }
+}
+
+enum ForceWrite {}
+
+impl Command for ForceWrite {
+    const DESCRIPTION: &'static str = "write the buffer even if its file changed on disk";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.write_buffer(cx.window, None, true)?;
+    }
+}
+
+enum Read {}
+
+impl Command for Read {
+    const DESCRIPTION: &'static str =
+        "insert a file, or the output of a !command, below the primary selection";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::rest("source").completing(Source::Path).raw()];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let arg = args.join(" ");
+        let mut text = match arg.strip_prefix('!') {
+            Some(command) => subprocess::run(command, "")?,
+            None => {
+                let path = shlex(&arg)
+                    .ok_or_else(|| format_err!("failed to parse file name '{}'", arg))?
+                    .join(" ");
+                fs::read_to_string(&path).with_context(|| format!("failed to read {}", path))?
+            }
+        };
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        let window = &cx.editor.windows[cx.window];
+        let content = &cx.editor.buffers[window.buffer].content;
+        let line = window.selections[SelectionId(0)].end.line;
+        let at = content.line_to_char(line.zero_based() + 1);
+        cx.editor.edit(window.buffer, at..at, &text);
+    }
+}
+
+enum BufferCommand {}
+
+impl Command for BufferCommand {
+    const DESCRIPTION: &'static str = "show a buffer in this window, by number or name";
+    const ARGUMENTS: &'static [Argument] = &[Argument::required("buffer")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let buffer_id = cx.editor.find_buffer(args[0])?;
+        cx.editor.show_buffer(cx.window, buffer_id);
+    }
+}
+
+enum BufferNext {}
+
+impl Command for BufferNext {
+    const DESCRIPTION: &'static str = "show the next buffer in this window";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.cycle_buffer(true);
+    }
+}
+
+enum BufferPrev {}
+
+impl Command for BufferPrev {
+    const DESCRIPTION: &'static str = "show the previous buffer in this window";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.cycle_buffer(false);
+    }
+}
+
+enum Buffers {}
+
+impl Command for Buffers {
+    const DESCRIPTION: &'static str = "list open buffers";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        let current = cx.editor.windows[cx.window].buffer;
+        let mut text = String::new();
+        for (i, buffer) in cx.editor.buffers.iter().enumerate() {
+            text.push_str(&format!(
+                "{}{} {}{}\n",
+                if BufferId(i) == current { '%' } else { ' ' },
+                i + 1,
+                buffer.name,
+                if buffer.modified { " [+]" } else { "" },
+            ));
+        }
+        cx.editor.output("buffers", text);
+    }
+}
+
+enum Help {}
+
+impl Command for Help {
+    const DESCRIPTION: &'static str = "list commands with their arguments, or describe one";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::optional("command").completing(Source::Command)];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let commands = &cx.editor.commands;
+        let describe = |name: &str, cmd: &CommandDesc| {
+            let signature = format!("{} {}", name, cmd.usage());
+            format!("{}\n    {}\n", signature.trim_end(), cmd.description)
+        };
+        let text = match args.first() {
+            Some(name) => {
+                let cmd = commands
+                    .get(*name)
+                    .ok_or_else(|| format_err!("command '{}' doesn't exist", name))?;
+                describe(name, cmd)
+            }
+            None => {
+                let mut names = commands.keys().collect::<Vec<_>>();
+                names.sort();
+                names
+                    .into_iter()
+                    .map(|name| describe(name, &commands[name]))
+                    .collect()
+            }
+        };
+        cx.editor.output("help", text);
+    }
+}
+
+enum Messages {}
+
+impl Command for Messages {
+    const DESCRIPTION: &'static str = "show recent status line messages";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        let mut text = String::new();
+        for message in &cx.editor.messages {
+            text.push_str(message);
+            text.push('\n');
+        }
+        cx.editor.output("messages", text);
+    }
+}
+
+enum ReloadConfig {}
+
+impl Command for ReloadConfig {
+    const DESCRIPTION: &'static str = "load the config file again";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.reload_config()?;
+    }
+}
+
+enum Map {}
+
+impl Command for Map {
+    const DESCRIPTION: &'static str = "bind a key to an action";
+    const ARGUMENTS: &'static [Argument] = &[
+        Argument::required("mode").completing(Source::Mode),
+        Argument::required("key"),
+        Argument::required("action").completing(Source::Action),
+    ];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor.config.keymap.map(args[0], args[1], args[2])?;
+    }
+}
+
+enum Unmap {}
+
+impl Command for Unmap {
+    const DESCRIPTION: &'static str = "remove a key binding";
+    const ARGUMENTS: &'static [Argument] = &[
+        Argument::required("mode").completing(Source::Mode),
+        Argument::required("key"),
+    ];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor.config.keymap.unmap(args[0], args[1])?;
+    }
+}
+
+enum Grep {}
+
+impl Command for Grep {
+    const DESCRIPTION: &'static str = "fill the location list with grep matches";
+    const ARGUMENTS: &'static [Argument] = &[
+        Argument::required("pattern"),
+        Argument::variadic("paths").completing(Source::Path),
+    ];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let paths = if args.len() > 1 { &args[1..] } else { &["."] };
+        let output = process::Command::new("grep")
+            .args(["-rnH", "--", args[0]])
+            .args(paths)
+            .stdin(Stdio::null())
+            .output()
+            .context("failed to run grep")?;
+        let dir = std::env::current_dir()?;
+        let locations = quickfix::parse(&String::from_utf8_lossy(&output.stdout), &dir);
+        if locations.is_empty() {
+            throw!(format_err!("no match for '{}'", args[0]));
+        }
+        cx.editor.set_locations(locations)?;
+    }
+}
+
+enum ShellCommand {}
+
+impl Command for ShellCommand {
+    const DESCRIPTION: &'static str = "run a shell command and show its output";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::rest("command").completing(Source::Path).raw()];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let command = args.join(" ");
+        let output = subprocess::run(&command, "")?;
+        if output.is_empty() {
+            cx.editor
+                .show_message(Importance::Info, format!("'{}' succeeded", command));
+        } else {
+            cx.editor.output(&format!("!{}", command), output);
+        }
+    }
+}
+
+enum Shell {}
+
+impl Command for Shell {
+    const DESCRIPTION: &'static str =
+        "suspend the editor to run a shell, or an interactive command";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::variadic("command").completing(Source::Path).raw()];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let command = args.join(" ");
+        cx.editor
+            .run_interactive(Some(&*command).filter(|command| !command.is_empty()))?;
+    }
+}
+
+enum Make {}
+
+impl Command for Make {
+    const DESCRIPTION: &'static str = "run the build command and list its errors";
+    const ARGUMENTS: &'static [Argument] = &[Argument::variadic("args").completing(Source::Path)];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor.make(args);
+    }
+}
+
+enum LocationNext {}
+
+impl Command for LocationNext {
+    const DESCRIPTION: &'static str = "go to the next location list entry";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.jump_location(true)?;
+    }
+}
+
+enum LocationPrev {}
+
+impl Command for LocationPrev {
+    const DESCRIPTION: &'static str = "go to the previous location list entry";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.jump_location(false)?;
+    }
+}
+
+enum LocationList {}
+
+impl Command for LocationList {
+    const DESCRIPTION: &'static str = "list the location list entries";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        if cx.editor.locations.is_empty() {
+            throw!(format_err!("no locations"));
+        }
+        let mut text = String::new();
+        for (i, entry) in cx.editor.locations.iter().enumerate() {
+            text.push_str(&format!(
+                "{}{}:{}:{}: {}\n",
+                if Some(i) == cx.editor.location_index {
+                    "> "
+                } else {
+                    "  "
+                },
+                entry.path.display(),
+                entry.line,
+                entry.column,
+                entry.message,
+            ));
+        }
+        cx.editor.output("locations", text);
+    }
+}
+
+enum Definition {}
+
+impl Command for Definition {
+    const DESCRIPTION: &'static str = "go to the definition of the symbol under the cursor";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.request_definition(cx.window)?;
+    }
+}
+
+enum Hover {}
+
+impl Command for Hover {
+    const DESCRIPTION: &'static str = "show the language server's hover for the cursor";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.request_hover(cx.window)?;
+    }
+}
+
+enum Diagnostics {}
+
+impl Command for Diagnostics {
+    const DESCRIPTION: &'static str = "fill the location list with language server diagnostics";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        let mut locations = Vec::new();
+        for buffer in cx.editor.buffers.iter() {
+            let path = match &buffer.path {
+                Some(path) => path,
+                None => continue,
+            };
+            let content = &buffer.content;
+            let last = content.len_chars() - 1;
+            for diagnostic in &buffer.diagnostics {
+                let range = buffer
+                    .effects
+                    .shift(diagnostic.revision, diagnostic.range.clone());
+                let start = match range {
+                    Some(range) => range.start.min(last),
+                    None => continue,
+                };
+                let line = content.char_to_line(start);
+                let severity = match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Information => "info",
+                    Severity::Hint => "hint",
+                };
+                locations.push(quickfix::Entry {
+                    path: path.clone(),
+                    line: line + 1,
+                    column: start - content.line_to_char(line) + 1,
+                    message: format!("{}: {}", severity, diagnostic.message),
+                });
+            }
+        }
+        if locations.is_empty() {
+            throw!(format_err!("no diagnostics"));
+        }
+        cx.editor.set_locations(locations)?;
+    }
+}
+
+enum PerfReport {}
+
+impl Command for PerfReport {
+    const DESCRIPTION: &'static str = "show recent event handling and draw times";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        let report = cx.editor.perf.report();
+        cx.editor.output("perf", report);
+    }
+}
+
+enum KeymapDump {}
+
+impl Command for KeymapDump {
+    const DESCRIPTION: &'static str = "list the key bindings of every mode";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        let dump = cx.editor.config.keymap.dump();
+        cx.editor.output("keymap", dump);
+    }
+}
+
+enum Rename {}
+
+impl Command for Rename {
+    const DESCRIPTION: &'static str = "rename the current buffer and its file";
+    const ARGUMENTS: &'static [Argument] = &[Argument::required("path").completing(Source::Path)];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let buffer_id = cx.editor.windows[cx.window].buffer;
+        cx.editor.rename_buffer(buffer_id, args[0], false)?;
+    }
+}
+
+enum ForceRename {}
+
+impl Command for ForceRename {
+    const DESCRIPTION: &'static str = "rename the current buffer and its file, replacing any file";
+    const ARGUMENTS: &'static [Argument] = &[Argument::required("path").completing(Source::Path)];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let buffer_id = cx.editor.windows[cx.window].buffer;
+        cx.editor.rename_buffer(buffer_id, args[0], true)?;
     }
+}
 
-    #[throws(MovementError)]
-    pub fn move_selections(&mut self, window_id: WindowId, movement: Movement, drag: bool) {
-        for selection_id in self.selections(window_id) {
-            self.move_selection(window_id, selection_id, movement, drag)?;
+enum Follow {}
+
+impl Command for Follow {
+    const DESCRIPTION: &'static str = "toggle following appended data, like tail -f";
+
+    #[throws]
+    fn run(cx: Context, _args: &[&str]) {
+        let window = &mut cx.editor.windows[cx.window];
+        let buffer_id = window.buffer;
+        let buffer = &mut cx.editor.buffers[buffer_id];
+        if buffer.follow.take().is_some() {
+            window.follow = false;
+            return;
         }
+        let path = buffer
+            .path
+            .clone()
+            .ok_or_else(|| format_err!("buffer '{}' has no file", buffer.name))?;
+        let offset = buffer.content.len_bytes() as u64;
+        buffer.follow = Some(follow::spawn(
+            path,
+            offset,
+            buffer_id,
+            cx.editor.follow.0.clone(),
+        ));
+        window.follow = true;
     }
+}
 
-    #[throws(MovementError)]
-    pub fn shift_selection(
-        &mut self,
-        window_id: WindowId,
-        selection_id: SelectionId,
-        movement: Movement,
-    ) {
-        let window = &mut self.windows[window_id];
-        let buffer = &mut self.buffers[window.buffer];
-        let selection = &mut window.selections[selection_id];
-        selection.start.move_to(&buffer.content, movement)?;
-        selection.end.move_to(&buffer.content, movement)?;
-    }
+enum Unicode {}
 
-    #[throws(MovementError)]
-    pub fn shift_selections(&mut self, window_id: WindowId, movement: Movement) {
-        for selection_id in self.selections(window_id) {
-            self.shift_selection(window_id, selection_id, movement)?;
+impl Command for Unicode {
+    const DESCRIPTION: &'static str =
+        "insert a character by name or U+codepoint, picking from those that match";
+    const ARGUMENTS: &'static [Argument] = &[Argument::variadic("name")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let query = args.join(" ");
+        if !query.is_empty() {
+            if let Some(c) = unicode::lookup(&query)? {
+                return cx.editor.type_char(cx.window, c)?;
+            }
         }
+        cx.editor.windows[cx.window].palette_prompt.text = query;
+        cx.editor.windows[cx.window].palette_prompt.end();
+        cx.editor.set_mode(cx.window, Mode::Characters);
     }
+}
 
-    pub fn delete_selection(&mut self, window_id: WindowId, selection_id: SelectionId) {
-        let window = &mut self.windows[window_id];
-        let buffer = &mut self.buffers[window.buffer];
-        let selection = &mut window.selections[selection_id];
-        selection.remove_from(&mut buffer.content);
-    }
+enum Calc {}
 
-    pub fn delete_selections(&mut self, window_id: WindowId) {
-        for selection_id in self.selections(window_id) {
-            self.delete_selection(window_id, selection_id);
+impl Command for Calc {
+    const DESCRIPTION: &'static str = "evaluate an expression, or each selection without one";
+    const ARGUMENTS: &'static [Argument] = &[Argument::variadic("expression")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        if args.is_empty() {
+            return cx.editor.calc_selections(cx.window)?;
+        }
+        let result = calc::format(calc::eval(&args.join(" "))?);
+        for selection_id in cx.editor.selections(cx.window) {
+            cx.editor.insert_before(cx.window, selection_id, &result);
         }
     }
+}
 
-    pub fn flip_selection(&mut self, window_id: WindowId, selection_id: SelectionId) {
-        let window = &mut self.windows[window_id];
-        let selection = &mut window.selections[selection_id];
-        selection.flip();
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn line_texts(content: &Rope, lines: &[Line]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| {
+            line.slice_of(content)
+                .to_string()
+                .trim_end_matches('\n')
+                .to_owned()
+        })
+        .collect()
+}
+
+/// The register a macro key uses, `@` unless another was chosen with `"`.
+fn macro_register(register: char) -> char {
+    if register == '"' {
+        '@'
+    } else {
+        register
     }
+}
 
-    pub fn order_selections(&mut self, window_id: WindowId) {
-        for selection_id in self.selections(window_id) {
-            self.order_selection(window_id, selection_id);
-        }
+/// How many word completions the popup shows at once.
+const WORDS_ROWS: usize = 8;
+
+/// The style of popups over the text.
+const POPUP: Style = Style {
+    fg: None,
+    bg: Some(Color::LightBlack),
+    bold: false,
+    underline: false,
+    invert: false,
+};
+
+/// The start of the word that ends at char `end`.
+fn word_start(content: &Rope, end: usize) -> usize {
+    let mut start = end;
+    while start > 0 && is_word_char(content.char(start - 1)) {
+        start -= 1;
     }
+    start
+}
 
-    pub fn order_selection(&mut self, window_id: WindowId, selection_id: SelectionId) {
-        let window = &mut self.windows[window_id];
-        let selection = &mut window.selections[selection_id];
-        selection.order();
+/// The zero-based lines of a buffer's diagnostics in its current text, in
+/// order, with the most severe one on each.
+fn diagnostic_lines(buffer: &Buffer) -> Vec<(usize, Severity)> {
+    let last = buffer.content.len_chars() - 1;
+    let mut lines = buffer
+        .diagnostics
+        .iter()
+        .filter_map(|diagnostic| {
+            let range = buffer
+                .effects
+                .shift(diagnostic.revision, diagnostic.range.clone())?;
+            let line = buffer.content.char_to_line(range.start.min(last));
+            Some((line, diagnostic.severity))
+        })
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.dedup_by_key(|&mut (line, _)| line);
+    lines
+}
+
+/// The running language server a buffer is open in, if any.
+fn server_of<'a>(
+    servers: &'a mut HashMap<&'static str, Option<lsp::Server>>,
+    buffer: &Buffer,
+) -> Option<&'a mut lsp::Server> {
+    let filetype = buffer.filetype.filter(|_| buffer.document.is_some())?;
+    servers.get_mut(filetype)?.as_mut()
+}
+
+/// The brackets and quotes `auto_pairs` closes.
+const PAIRS: &[(char, char)] = &[
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+];
+
+/// The pair a surround char stands for: brackets by either half, `b` for
+/// parentheses, `B` for braces, and any other char for two of itself.
+fn surround_pair(c: char) -> (char, char) {
+    match c {
+        '(' | ')' | 'b' => ('(', ')'),
+        '[' | ']' => ('[', ']'),
+        '{' | '}' | 'B' => ('{', '}'),
+        '<' | '>' => ('<', '>'),
+        _ => (c, c),
     }
+}
 
-    pub fn flip_selections(&mut self, window_id: WindowId) {
-        for selection_id in self.selections(window_id) {
-            self.flip_selection(window_id, selection_id);
-        }
+fn line_indent(content: &Rope, line: Line) -> String {
+    line.slice_of(content)
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+enum AlignTable {}
+
+impl Command for AlignTable {
+    const DESCRIPTION: &'static str = "align columns of the selected lines on a separator";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("separator")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let buffer_id = cx.editor.windows[cx.window].buffer;
+        let lines = cx.editor.selected_lines(cx.window);
+        let text = line_texts(&cx.editor.buffers[buffer_id].content, &lines);
+        let aligned = align::table(&text, args.first().copied());
+        cx.editor.replace_lines(buffer_id, &lines, &aligned);
     }
+}
 
-    pub fn for_each_selection<F>(&mut self, window_id: WindowId, mut f: F) -> Result<(), Error>
-    where
-        F: FnMut(&mut Self, WindowId, SelectionId) -> Result<(), Error>,
-    {
-        let mut errors = Vec::new();
-        for selection_id in self.selections(window_id) {
-            if let Err(e) = f(self, window_id, selection_id) {
-                errors.push(e);
+enum AlignRegex {}
+
+impl Command for AlignRegex {
+    const DESCRIPTION: &'static str = "align the selected lines on the first match of a regex";
+    const ARGUMENTS: &'static [Argument] = &[Argument::required("pattern")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let regex = Regex::new(args[0])?;
+        let buffer_id = cx.editor.windows[cx.window].buffer;
+        let lines = cx.editor.selected_lines(cx.window);
+        let text = line_texts(&cx.editor.buffers[buffer_id].content, &lines);
+        let aligned = align::on_regex(&text, &regex);
+        cx.editor.replace_lines(buffer_id, &lines, &aligned);
+    }
+}
+
+enum Goto {}
+
+impl Command for Goto {
+    const DESCRIPTION: &'static str = "go to a line number or address, also with :<line>";
+    const ARGUMENTS: &'static [Argument] = &[Argument::required("line")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let line = match args[0].parse::<usize>() {
+            Ok(line) => line,
+            Err(_) => {
+                let window = &cx.editor.windows[cx.window];
+                let content = &cx.editor.buffers[window.buffer].content;
+                let current = window.selections[SelectionId(0)].end.line.one_based();
+                address::parse(args[0], current, Line::last(content).one_based())?
             }
-        }
-        errors.pop().map_or(Ok(()), Err)
+        };
+        cx.editor.goto_line(cx.window, line, false)?;
     }
 }
 
-impl Drop for Edot {
-    fn drop(&mut self) {
-        let _ = write!(
-            self.output,
-            "{}{}{}",
-            cursor::Show,
-            cursor::SteadyBlock,
-            screen::ToMainScreen
+enum MoveLines {}
+
+impl Command for MoveLines {
+    const DESCRIPTION: &'static str = "move the selected lines, or a range, after an address";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::optional("range"), Argument::required("address")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor.transfer_lines(cx.window, args, false)?;
+    }
+}
+
+enum CopyLines {}
+
+impl Command for CopyLines {
+    const DESCRIPTION: &'static str = "copy the selected lines, or a range, after an address";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::optional("range"), Argument::required("address")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor.transfer_lines(cx.window, args, true)?;
+    }
+}
+
+#[throws]
+fn register_arg(args: &[&str]) -> Option<char> {
+    let arg = match args.first() {
+        Some(arg) => arg,
+        None => return None,
+    };
+    let mut chars = arg.chars();
+    match (chars.next(), chars.next()) {
+        (Some(name), None) => Some(name),
+        _ => throw!(format_err!("register names are a single character")),
+    }
+}
+
+enum MacroEdit {}
+
+impl Command for MacroEdit {
+    const DESCRIPTION: &'static str = "edit a macro register in a scratch buffer";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("register")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let name = register_arg(args)?.unwrap_or('@');
+        let text = match cx.editor.registers.get(&name) {
+            Some(register) => keys::format(&keys::parse(&register.values.concat())?),
+            None => String::new(),
+        };
+        let mut buffer = Buffer::new(
+            format!("*macro {}*", name),
+            Rope::from(format!("{}\n", text)),
+            None,
         );
+        buffer.macro_register = Some(name);
+        cx.editor.open(buffer);
     }
 }
 
-pub struct Window {
-    buffer: BufferId,
-    mode: Mode,
-    selections: IdVec<SelectionId, Selection>,
-    command: String,
-    top: Line,
+enum MacroStore {}
+
+impl Command for MacroStore {
+    const DESCRIPTION: &'static str =
+        "store the current buffer as key notation into a macro register";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("register")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let buffer = &cx.editor.buffers[cx.editor.windows[cx.window].buffer];
+        let name = register_arg(args)?
+            .or(buffer.macro_register)
+            .context("no register given")?;
+        let keys = keys::parse(&buffer.content.to_string())?;
+        cx.editor.registers.insert(
+            name,
+            Register {
+                values: vec![keys::format(&keys)],
+                block: false,
+            },
+        );
+        cx.editor.show_message(
+            Importance::Info,
+            format!("stored {} keys in register {}", keys.len(), name),
+        );
+    }
 }
 
-id!(SelectionId);
+enum InsertIndex {}
 
-pub struct Buffer {
-    path: Option<PathBuf>,
-    name: String,
-    content: Rope,
-    history: VecDeque<Modification>,
+impl Command for InsertIndex {
+    const DESCRIPTION: &'static str = "insert a counter at each selection";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::optional("start"), Argument::optional("step")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let number = |i: usize, default: i64| -> Result<i64> {
+            args.get(i).map_or(Ok(default), |arg| {
+                arg.parse()
+                    .map_err(|_| format_err!("invalid number '{}'", arg))
+            })
+        };
+        let start = number(0, 1)?;
+        let step = number(1, 1)?;
+        for selection_id in cx.editor.selections(cx.window) {
+            let index = cx.editor.selection_index(cx.window, selection_id) as i64;
+            let value = (start + index * step).to_string();
+            cx.editor.insert_before(cx.window, selection_id, &value);
+        }
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub enum Modification {}
+enum RotateSelections {}
 
-#[derive(Debug, Copy, Clone)]
-pub enum Mode {
-    Normal,
-    Insert,
-    Append,
-    Goto { drag: bool },
-    Command,
+impl Command for RotateSelections {
+    const DESCRIPTION: &'static str = "make the next selection primary, or the previous with '-'";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("direction")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor
+            .rotate_selections(cx.window, args.first() != Some(&"-"));
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub enum Importance {
-    Error,
+enum RotateContents {}
+
+impl Command for RotateContents {
+    const DESCRIPTION: &'static str = "rotate the text of the selections, backwards with '-'";
+    const ARGUMENTS: &'static [Argument] = &[Argument::optional("direction")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor
+            .rotate_contents(cx.window, args.first() != Some(&"-"));
+    }
 }
 
-pub struct Context<'a> {
-    editor: &'a mut Edot,
-    window: WindowId,
+enum Substitute {}
+
+impl Command for Substitute {
+    const DESCRIPTION: &'static str =
+        "replace regex matches, with flags i, w, s and p to preserve case";
+    const ARGUMENTS: &'static [Argument] = &[
+        Argument::required("pattern"),
+        Argument::required("replacement"),
+        Argument::optional("flags"),
+    ];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let count = cx.editor.substitute(cx.window, args)?;
+        cx.editor
+            .show_message(Importance::Info, format!("{} substitutions", count));
+    }
 }
 
-pub trait Command: Sized {
-    const DESCRIPTION: &'static str;
-    const REQUIRED_ARGUMENTS: usize = 0;
+enum SearchCommand {}
 
-    fn run(cx: Context, args: &[&str]) -> Result;
+impl Command for SearchCommand {
+    const DESCRIPTION: &'static str = "select the next match, with flags i to ignore case, w for whole words and s to stay in the selections";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::required("pattern"), Argument::optional("flags")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let flags = args.get(1).copied().unwrap_or("");
+        let selections = cx.editor.selection_ranges(cx.window);
+        cx.editor.windows[cx.window].search = Some(Search::new(args[0], flags, selections)?);
+        cx.editor.search_next(cx.window, true, 1)?;
+    }
 }
 
-pub struct CommandDesc {
-    description: &'static str,
-    required_arguments: usize,
-    run: fn(cx: Context, args: &[&str]) -> Result,
+enum Keep {}
+
+impl Command for Keep {
+    const DESCRIPTION: &'static str = "keep selections matching a regex";
+    const ARGUMENTS: &'static [Argument] = &[Argument::required("pattern")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor.keep_selections(cx.window, args[0], true)?;
+    }
 }
 
-impl CommandDesc {
-    fn of<T: Command>() -> Self {
-        Self {
-            description: T::DESCRIPTION,
-            required_arguments: T::REQUIRED_ARGUMENTS,
-            run: T::run,
+enum KeepNot {}
+
+impl Command for KeepNot {
+    const DESCRIPTION: &'static str = "drop selections matching a regex";
+    const ARGUMENTS: &'static [Argument] = &[Argument::required("pattern")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        cx.editor.keep_selections(cx.window, args[0], false)?;
+    }
+}
+
+enum FindAll {}
+
+impl Command for FindAll {
+    const DESCRIPTION: &'static str =
+        "collect matches in every buffer for find-next, with flags i and w";
+    const ARGUMENTS: &'static [Argument] =
+        &[Argument::required("pattern"), Argument::optional("flags")];
+
+    #[throws]
+    fn run(cx: Context, args: &[&str]) {
+        let flags = args.get(1).copied().unwrap_or("");
+        if flags.contains('s') {
+            throw!(format_err!("find-all doesn't support the s flag"));
+        }
+        let search = Search::new(args[0], flags, Vec::new())?;
+        let mut results = Vec::new();
+        let mut buffers = 0;
+        for (i, buffer) in cx.editor.buffers.iter().enumerate() {
+            let ranges = search.ranges(&buffer.content);
+            if !ranges.is_empty() {
+                buffers += 1;
+            }
+            let revision = buffer.effects.revision();
+            results.extend(
+                ranges
+                    .into_iter()
+                    .map(|range| (BufferId(i), range, revision)),
+            );
         }
+        if results.is_empty() {
+            throw!(format_err!("no match for '{}'", args[0]));
+        }
+        let len = results.len();
+        cx.editor.results = results;
+        cx.editor.result_index = None;
+        cx.editor.jump_result(true)?;
+        cx.editor.show_message(
+            Importance::Info,
+            format!("{} matches in {} buffers", len, buffers),
+        );
     }
 }
 
-enum Quit {}
+enum FindNext {}
 
-impl Command for Quit {
-    const DESCRIPTION: &'static str = "quits the editor";
+impl Command for FindNext {
+    const DESCRIPTION: &'static str = "go to the next find-all result";
 
     #[throws]
     fn run(cx: Context, _args: &[&str]) {
-        cx.editor.quit();
+        cx.editor.jump_result(true)?;
     }
 }
 
-enum Edit {}
+enum FindPrev {}
 
-impl Command for Edit {
-    const DESCRIPTION: &'static str = "open a file";
-    const REQUIRED_ARGUMENTS: usize = 1;
+impl Command for FindPrev {
+    const DESCRIPTION: &'static str = "go to the previous find-all result";
 
     #[throws]
-    fn run(cx: Context, args: &[&str]) {
-        let name = String::from(args[0]);
-        let path = PathBuf::from(&name).canonicalize()?;
-        let reader = File::open(&path)?;
-        let buffer = Buffer {
-            path: Some(path),
-            name,
-            content: Rope::from_reader(reader)?,
-            history: VecDeque::new(),
-        };
-        let buffer_id = BufferId(cx.editor.buffers.len());
-        cx.editor.buffers.push(buffer);
-        let window = Window {
-            buffer: buffer_id,
-            command: String::new(),
-            mode: Mode::Normal,
-            selections: vec![Selection {
-                // TODO move this out
-                start: Position {
-                    line: Line::from_one_based(1),
-                    column: Column::from_one_based(1),
-                },
-                end: Position {
-                    line: Line::from_one_based(1),
-                    column: Column::from_one_based(1),
-                },
-            }]
-            .into(),
-            top: Line::from_one_based(1),
-        };
-        let window_id = WindowId(cx.editor.windows.len());
-        cx.editor.windows.push(window);
-        cx.editor.focused = window_id;
+    fn run(cx: Context, _args: &[&str]) {
+        cx.editor.jump_result(false)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writing_a_copy_keeps_changes_unsaved() {
+        let dir = env::temp_dir().join(format!("edot-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("file.txt");
+        let copy = dir.join("copy.txt");
+        fs::write(&file, "old\n").unwrap();
+        let config = Config::default();
+        let mut buffer = Buffer::new(
+            String::from("file.txt"),
+            Rope::from("new\n"),
+            Some(file.clone()),
+        );
+        buffer.modified = true;
+        buffer.write(copy.to_str(), false, &config).unwrap();
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "new\n");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old\n");
+        assert!(check_saved(Some(&buffer).into_iter()).is_err());
+        buffer.write(None, false, &config).unwrap();
+        assert!(check_saved(Some(&buffer).into_iter()).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,76 @@
+use std::{collections::VecDeque, ops::Range};
+
+/// How many effects a buffer keeps for subscribers that fall behind.
+const LEN: usize = 1000;
+
+/// One change to a buffer's text, with offsets in chars.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Effect {
+    /// The buffer revision this change produced.
+    pub revision: usize,
+    /// The range that was deleted, in the text before the change.
+    pub removed: Range<usize>,
+    /// The number of chars inserted at `removed.start`.
+    pub inserted: usize,
+}
+
+impl Effect {
+    /// Maps an offset in the text before the change to the text after it.
+    /// Offsets inside the deleted range move to its start.
+    pub fn shift(&self, offset: usize) -> usize {
+        if offset >= self.removed.end {
+            offset - self.removed.len() + self.inserted
+        } else if offset >= self.removed.start {
+            self.removed.start
+        } else {
+            offset
+        }
+    }
+}
+
+/// The recent changes to a buffer, for incremental consumers such as
+/// highlighters that remember the revision they last saw.
+#[derive(Debug, Default)]
+pub struct Log {
+    revision: usize,
+    effects: VecDeque<Effect>,
+}
+
+impl Log {
+    /// The revision of the current text, increasing with every change.
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    pub fn record(&mut self, removed: Range<usize>, inserted: usize) {
+        self.revision += 1;
+        if self.effects.len() == LEN {
+            self.effects.pop_front();
+        }
+        self.effects.push_back(Effect {
+            revision: self.revision,
+            removed,
+            inserted,
+        });
+    }
+
+    /// Returns the changes made after `revision`, oldest first, or `None` if
+    /// some of them were already dropped and the consumer must start over.
+    pub fn since(&self, revision: usize) -> Option<impl Iterator<Item = &Effect>> {
+        let oldest = self
+            .effects
+            .front()
+            .map_or(self.revision, |e| e.revision - 1);
+        if revision < oldest {
+            return None;
+        }
+        Some(self.effects.iter().filter(move |e| e.revision > revision))
+    }
+
+    /// Maps a range seen at `revision` to the current text.
+    pub fn shift(&self, revision: usize, range: Range<usize>) -> Option<Range<usize>> {
+        Some(self.since(revision)?.fold(range, |range, effect| {
+            effect.shift(range.start)..effect.shift(range.end)
+        }))
+    }
+}
@@ -0,0 +1,42 @@
+use std::path::Path;
+
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("c", "c"),
+    ("h", "c"),
+    ("cc", "cpp"),
+    ("cpp", "cpp"),
+    ("hpp", "cpp"),
+    ("go", "go"),
+    ("html", "html"),
+    ("java", "java"),
+    ("js", "javascript"),
+    ("json", "json"),
+    ("md", "markdown"),
+    ("py", "python"),
+    ("rs", "rust"),
+    ("sh", "sh"),
+    ("toml", "toml"),
+    ("ts", "typescript"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+];
+
+/// The chars that open a block when they end a line, so that the next line
+/// is indented a level deeper.
+pub fn block_openers(filetype: &str) -> &'static [char] {
+    match filetype {
+        "python" | "yaml" => &[':'],
+        "c" | "cpp" | "go" | "java" | "javascript" | "json" | "rust" | "sh" | "typescript" => {
+            &['{']
+        }
+        _ => &[],
+    }
+}
+
+pub fn detect(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?;
+    EXTENSIONS
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, filetype)| *filetype)
+}
@@ -0,0 +1,97 @@
+use crate::edot::BufferId;
+use crossbeam_channel::{unbounded, Sender, TryRecvError};
+use log::{error, info};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::PathBuf,
+    str, thread,
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct Appended {
+    pub buffer: BufferId,
+    pub text: String,
+    /// Set once the file was truncated, so `text` replaces the buffer's
+    /// content instead of following it.
+    pub reset: bool,
+}
+
+/// Watches `path` for data appended past `offset`, sending it as it arrives
+/// and starting over from the beginning when the file is truncated.
+/// The watcher stops once the returned sender is dropped.
+pub fn spawn(
+    path: PathBuf,
+    mut offset: u64,
+    buffer: BufferId,
+    sender: Sender<Appended>,
+) -> Sender<()> {
+    let (stop, stopped) = unbounded();
+    thread::spawn(move || {
+        let mut pending = Vec::new();
+        let mut reset = false;
+        // Whether the buffer was reset to nothing, holding only a newline
+        // that the next text should replace.
+        let mut empty = false;
+        loop {
+            match stopped.try_recv() {
+                Err(TryRecvError::Empty) => {}
+                _ => return,
+            }
+            thread::sleep(POLL_INTERVAL);
+            let len = match path.metadata() {
+                Ok(metadata) => metadata.len(),
+                Err(err) => {
+                    error!("follow {}: {}", path.display(), err);
+                    continue;
+                }
+            };
+            if len < offset {
+                info!("follow {}: file truncated", path.display());
+                offset = 0;
+                pending.clear();
+                reset = true;
+            }
+            if len == offset && !reset {
+                continue;
+            }
+            let read = File::open(&path).and_then(|mut file| {
+                file.seek(SeekFrom::Start(offset))?;
+                file.take(len - offset).read_to_end(&mut pending)
+            });
+            match read {
+                Ok(n) => offset += n as u64,
+                Err(err) => {
+                    error!("follow {}: {}", path.display(), err);
+                    continue;
+                }
+            }
+            let valid = match str::from_utf8(&pending) {
+                Ok(text) => text.len(),
+                Err(err) if err.error_len().is_none() => err.valid_up_to(),
+                Err(_) => pending.len(),
+            };
+            if valid == 0 && !reset {
+                continue;
+            }
+            let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+            pending.drain(..valid);
+            reset |= empty;
+            empty = reset && text.is_empty();
+            if sender
+                .send(Appended {
+                    buffer,
+                    text,
+                    reset,
+                })
+                .is_err()
+            {
+                return;
+            }
+            reset = false;
+        }
+    });
+    stop
+}
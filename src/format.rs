@@ -0,0 +1,199 @@
+use ropey::Rope;
+use std::{
+    convert::TryFrom,
+    io::{self, Write},
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    /// UTF-8 starting with a byte order mark.
+    Utf8Bom,
+    Utf16Le,
+    /// UTF-16 little-endian starting with a byte order mark.
+    Utf16LeBom,
+    Utf16Be,
+    /// UTF-16 big-endian starting with a byte order mark.
+    Utf16BeBom,
+    /// Taken for anything that isn't valid UTF-8 or UTF-16, since every byte
+    /// is a valid char in it.
+    Latin1,
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf8Bom => "utf-8 bom",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16LeBom => "utf-16le bom",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Utf16BeBom => "utf-16be bom",
+            Encoding::Latin1 => "latin-1",
+        }
+    }
+}
+
+/// How a file's text was stored, so that it can be written back the same
+/// way. Buffers always hold `\n` line endings and a final newline.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Format {
+    pub encoding: Encoding,
+    pub line_ending: LineEnding,
+    /// Whether the file ended in a line ending.
+    pub final_newline: bool,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format {
+            encoding: Encoding::Utf8,
+            line_ending: LineEnding::Lf,
+            final_newline: true,
+        }
+    }
+}
+
+/// Guesses the encoding of `bytes` from a byte order mark, from zero bytes
+/// in every other position for UTF-16 without one, or else from whether it
+/// is valid UTF-8.
+fn detect(bytes: &[u8]) -> Encoding {
+    match bytes {
+        [0xef, 0xbb, 0xbf, ..] => return Encoding::Utf8Bom,
+        [0xff, 0xfe, ..] => return Encoding::Utf16LeBom,
+        [0xfe, 0xff, ..] => return Encoding::Utf16BeBom,
+        _ => {}
+    }
+    let sample = &bytes[..bytes.len().min(1024) & !1];
+    let zeros = |offset| {
+        sample
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    if !sample.is_empty() && even == 0 && odd * 4 > sample.len() {
+        Encoding::Utf16Le
+    } else if !sample.is_empty() && odd == 0 && even * 4 > sample.len() {
+        Encoding::Utf16Be
+    } else if std::str::from_utf8(bytes).is_ok() {
+        Encoding::Utf8
+    } else {
+        Encoding::Latin1
+    }
+}
+
+/// Decodes a file, detecting its encoding and taking the line ending from its
+/// first line, and converts its line endings to `\n`, adding a final one if
+/// it is missing. Text that isn't valid in the detected encoding is replaced
+/// with U+FFFD.
+pub fn decode(bytes: Vec<u8>) -> (String, Format) {
+    let encoding = detect(&bytes);
+    let utf16 = |bytes: &[u8], unit: fn([u8; 2]) -> u16| {
+        let units = bytes
+            .chunks(2)
+            .map(|pair| unit([pair[0], *pair.get(1).unwrap_or(&0)]))
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    };
+    let text = match encoding {
+        Encoding::Utf8 => String::from_utf8(bytes)
+            .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()),
+        Encoding::Utf8Bom => String::from_utf8_lossy(&bytes[3..]).into_owned(),
+        Encoding::Utf16Le => utf16(&bytes, u16::from_le_bytes),
+        Encoding::Utf16LeBom => utf16(&bytes[2..], u16::from_le_bytes),
+        Encoding::Utf16Be => utf16(&bytes, u16::from_be_bytes),
+        Encoding::Utf16BeBom => utf16(&bytes[2..], u16::from_be_bytes),
+        Encoding::Latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+    };
+    let line_ending = match text.find('\n') {
+        Some(i) if text[..i].ends_with('\r') => LineEnding::Crlf,
+        _ => LineEnding::Lf,
+    };
+    let format = Format {
+        encoding,
+        line_ending,
+        final_newline: text.ends_with('\n'),
+    };
+    let mut text = match line_ending {
+        LineEnding::Lf => text,
+        LineEnding::Crlf => text.replace("\r\n", "\n"),
+    };
+    if !format.final_newline {
+        text.push('\n');
+    }
+    (text, format)
+}
+
+/// Writes `text` in `encoding`, returning whether any chars couldn't be
+/// represented and were written as `?`.
+fn write_encoded(output: &mut impl Write, text: &str, encoding: Encoding) -> io::Result<bool> {
+    let mut lossy = false;
+    match encoding {
+        Encoding::Utf8 | Encoding::Utf8Bom => output.write_all(text.as_bytes())?,
+        Encoding::Utf16Le | Encoding::Utf16LeBom | Encoding::Utf16Be | Encoding::Utf16BeBom => {
+            let mut bytes = Vec::with_capacity(text.len() * 2);
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&match encoding {
+                    Encoding::Utf16Le | Encoding::Utf16LeBom => unit.to_le_bytes(),
+                    _ => unit.to_be_bytes(),
+                });
+            }
+            output.write_all(&bytes)?;
+        }
+        Encoding::Latin1 => {
+            let bytes = text
+                .chars()
+                .map(|c| {
+                    u8::try_from(u32::from(c)).unwrap_or_else(|_| {
+                        lossy = true;
+                        b'?'
+                    })
+                })
+                .collect::<Vec<_>>();
+            output.write_all(&bytes)?;
+        }
+    }
+    Ok(lossy)
+}
+
+/// Writes `content` in `format`, returning whether any chars couldn't be
+/// represented in its encoding.
+pub fn encode(content: &Rope, format: Format, output: &mut impl Write) -> io::Result<bool> {
+    let encoding = format.encoding;
+    match encoding {
+        Encoding::Utf8Bom => output.write_all(&[0xef, 0xbb, 0xbf])?,
+        Encoding::Utf16LeBom => output.write_all(&[0xff, 0xfe])?,
+        Encoding::Utf16BeBom => output.write_all(&[0xfe, 0xff])?,
+        Encoding::Utf8 | Encoding::Utf16Le | Encoding::Utf16Be | Encoding::Latin1 => {}
+    }
+    let mut end = content.len_chars();
+    if !format.final_newline && end > 0 && content.char(end - 1) == '\n' {
+        end -= 1;
+    }
+    let mut lossy = false;
+    for chunk in content.slice(..end).chunks() {
+        match format.line_ending {
+            LineEnding::Lf => lossy |= write_encoded(output, chunk, encoding)?,
+            LineEnding::Crlf => {
+                let mut lines = chunk.split('\n');
+                if let Some(first) = lines.next() {
+                    lossy |= write_encoded(output, first, encoding)?;
+                }
+                for line in lines {
+                    write_encoded(output, "\r\n", encoding)?;
+                    lossy |= write_encoded(output, line, encoding)?;
+                }
+            }
+        }
+    }
+    Ok(lossy)
+}
@@ -0,0 +1,360 @@
+use crate::{effect, screen::Color};
+use ropey::Rope;
+use std::ops::Range;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Style {
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+impl Style {
+    pub fn color(self) -> Color {
+        match self {
+            Style::Keyword => Color::Magenta,
+            Style::String => Color::Green,
+            Style::Comment => Color::LightBlack,
+            Style::Number => Color::Cyan,
+        }
+    }
+}
+
+/// The lexical rules of a filetype, enough to find keywords, strings and
+/// comments one line at a time.
+#[derive(Debug)]
+pub struct Syntax {
+    keywords: &'static [&'static str],
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum",
+    "extern", "float", "for", "goto", "if", "int", "long", "return", "short", "signed", "sizeof",
+    "static", "struct", "switch", "typedef", "union", "unsigned", "void", "volatile", "while",
+];
+
+const SYNTAXES: &[(&str, Syntax)] = &[
+    (
+        "rust",
+        Syntax {
+            keywords: &[
+                "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else",
+                "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match",
+                "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct",
+                "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+            ],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            quotes: &['"'],
+        },
+    ),
+    (
+        "c",
+        Syntax {
+            keywords: C_KEYWORDS,
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            quotes: &['"', '\''],
+        },
+    ),
+    (
+        "cpp",
+        Syntax {
+            keywords: C_KEYWORDS,
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            quotes: &['"', '\''],
+        },
+    ),
+    (
+        "go",
+        Syntax {
+            keywords: &[
+                "break",
+                "case",
+                "chan",
+                "const",
+                "continue",
+                "default",
+                "defer",
+                "else",
+                "fallthrough",
+                "for",
+                "func",
+                "go",
+                "goto",
+                "if",
+                "import",
+                "interface",
+                "map",
+                "package",
+                "range",
+                "return",
+                "select",
+                "struct",
+                "switch",
+                "type",
+                "var",
+            ],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            quotes: &['"', '\'', '`'],
+        },
+    ),
+    (
+        "java",
+        Syntax {
+            keywords: &[
+                "abstract",
+                "boolean",
+                "break",
+                "case",
+                "catch",
+                "class",
+                "else",
+                "extends",
+                "final",
+                "for",
+                "if",
+                "implements",
+                "import",
+                "int",
+                "interface",
+                "new",
+                "package",
+                "private",
+                "protected",
+                "public",
+                "return",
+                "static",
+                "switch",
+                "this",
+                "throw",
+                "throws",
+                "try",
+                "void",
+                "while",
+            ],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            quotes: &['"', '\''],
+        },
+    ),
+    (
+        "javascript",
+        Syntax {
+            keywords: JS_KEYWORDS,
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            quotes: &['"', '\'', '`'],
+        },
+    ),
+    (
+        "typescript",
+        Syntax {
+            keywords: JS_KEYWORDS,
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            quotes: &['"', '\'', '`'],
+        },
+    ),
+    (
+        "python",
+        Syntax {
+            keywords: &[
+                "and", "as", "assert", "break", "class", "continue", "def", "del", "elif", "else",
+                "except", "False", "finally", "for", "from", "if", "import", "in", "is", "lambda",
+                "None", "not", "or", "pass", "raise", "return", "True", "try", "while", "with",
+                "yield",
+            ],
+            line_comment: Some("#"),
+            block_comment: None,
+            quotes: &['"', '\''],
+        },
+    ),
+    (
+        "sh",
+        Syntax {
+            keywords: &[
+                "case", "do", "done", "elif", "else", "esac", "fi", "for", "function", "if", "in",
+                "local", "return", "then", "while",
+            ],
+            line_comment: Some("#"),
+            block_comment: None,
+            quotes: &['"', '\''],
+        },
+    ),
+    (
+        "toml",
+        Syntax {
+            keywords: &["true", "false"],
+            line_comment: Some("#"),
+            block_comment: None,
+            quotes: &['"', '\''],
+        },
+    ),
+    (
+        "yaml",
+        Syntax {
+            keywords: &["true", "false", "null"],
+            line_comment: Some("#"),
+            block_comment: None,
+            quotes: &['"', '\''],
+        },
+    ),
+    (
+        "json",
+        Syntax {
+            keywords: &["true", "false", "null"],
+            line_comment: None,
+            block_comment: None,
+            quotes: &['"'],
+        },
+    ),
+];
+
+const JS_KEYWORDS: &[&str] = &[
+    "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "else",
+    "export", "extends", "false", "finally", "for", "function", "if", "import", "in", "let", "new",
+    "null", "return", "switch", "this", "throw", "true", "try", "typeof", "var", "while",
+];
+
+pub fn syntax(filetype: &str) -> Option<&'static Syntax> {
+    SYNTAXES
+        .iter()
+        .find(|(name, _)| *name == filetype)
+        .map(|(_, syntax)| syntax)
+}
+
+fn starts_with(chars: &[char], i: usize, pattern: &str) -> bool {
+    let mut rest = chars[i..].iter();
+    pattern.chars().all(|c| rest.next() == Some(&c))
+}
+
+#[derive(Debug)]
+struct Highlighted {
+    /// Char column ranges of the line, in order.
+    spans: Vec<(Range<usize>, Style)>,
+    /// Whether a block comment is still open at the end of the line.
+    in_comment: bool,
+}
+
+fn highlight_line(syntax: &Syntax, line: &str, mut in_comment: bool) -> Highlighted {
+    let chars = line.chars().collect::<Vec<_>>();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        if in_comment {
+            // Lines cached for another syntax may end in a comment this one
+            // doesn't have.
+            let end = match syntax.block_comment {
+                Some((_, end)) => end,
+                None => {
+                    in_comment = false;
+                    continue;
+                }
+            };
+            while i < chars.len() && !starts_with(&chars, i, end) {
+                i += 1;
+            }
+            if i < chars.len() {
+                i += end.chars().count();
+                in_comment = false;
+            }
+            spans.push((start..i, Style::Comment));
+        } else if let Some((open, _)) = syntax
+            .block_comment
+            .filter(|(open, _)| starts_with(&chars, i, open))
+        {
+            i += open.chars().count();
+            in_comment = true;
+            spans.push((start..i, Style::Comment));
+        } else if syntax
+            .line_comment
+            .is_some_and(|comment| starts_with(&chars, i, comment))
+        {
+            let end = chars.len() - usize::from(chars.last() == Some(&'\n'));
+            spans.push((start..end, Style::Comment));
+            break;
+        } else if syntax.quotes.contains(&chars[i]) {
+            let quote = chars[i];
+            i += 1;
+            while i < chars.len() && chars[i] != quote && chars[i] != '\n' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+            spans.push((start..i, Style::String));
+        } else if is_word(chars[i]) {
+            while i < chars.len() && is_word(chars[i]) {
+                i += 1;
+            }
+            let word = chars[start..i].iter().collect::<String>();
+            if chars[start].is_ascii_digit() {
+                spans.push((start..i, Style::Number));
+            } else if syntax.keywords.contains(&&*word) {
+                spans.push((start..i, Style::Keyword));
+            }
+        } else {
+            i += 1;
+        }
+    }
+    Highlighted { spans, in_comment }
+}
+
+/// Highlighted lines from the start of a buffer up to the furthest line drawn,
+/// dropped from the first changed line onwards after edits, and all of them
+/// when the syntax changes.
+#[derive(Debug, Default)]
+pub struct Cache {
+    revision: usize,
+    syntax: Option<&'static Syntax>,
+    lines: Vec<Highlighted>,
+}
+
+impl Cache {
+    /// Brings the cache up to date with `log` and highlights lines up to `end`.
+    pub fn update(&mut self, rope: &Rope, log: &effect::Log, syntax: &'static Syntax, end: usize) {
+        if !self
+            .syntax
+            .is_some_and(|cached| std::ptr::eq(cached, syntax))
+        {
+            self.syntax = Some(syntax);
+            self.lines.clear();
+        }
+        match log.since(self.revision) {
+            Some(effects) => {
+                let len = rope.len_chars();
+                let first = effects
+                    .map(|effect| rope.char_to_line(effect.removed.start.min(len)))
+                    .min();
+                if let Some(first) = first {
+                    self.lines.truncate(first);
+                }
+            }
+            None => self.lines.clear(),
+        }
+        self.revision = log.revision();
+        let end = end.min(rope.len_lines());
+        while self.lines.len() < end {
+            let in_comment = self.lines.last().is_some_and(|line| line.in_comment);
+            let line = rope.line(self.lines.len()).to_string();
+            self.lines.push(highlight_line(syntax, &line, in_comment));
+        }
+    }
+
+    /// The style of the char at zero-based `line` and `column`, if highlighted.
+    pub fn style(&self, line: usize, column: usize) -> Option<Style> {
+        self.lines
+            .get(line)?
+            .spans
+            .iter()
+            .find(|(range, _)| range.contains(&column))
+            .map(|(_, style)| *style)
+    }
+}
@@ -0,0 +1,416 @@
+use crate::Error;
+use anyhow::format_err;
+use fehler::{throw, throws};
+use std::{
+    fmt::{self, Display, Write},
+    iter::Peekable,
+    str::Chars,
+};
+
+/// A JSON value, as spoken to language servers. Objects keep their keys in
+/// the order they were written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Number(n) if n >= 0.0 && n.fract() == 0.0 => Some(n as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+/// An object with `fields`, in order.
+pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, Value)>) -> Value {
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect(),
+    )
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(values: Vec<Value>) -> Self {
+        Value::Array(values)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Value::Number(_) => f.write_str("null"),
+            Value::String(s) => write_string(f, s),
+            Value::Array(values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_char(']')
+            }
+            Value::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// How deeply arrays and objects may nest, so that a misbehaving server
+/// can't overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+#[throws]
+pub fn parse(text: &str) -> Value {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.chars.next() {
+        throw!(format_err!("unexpected '{}' after JSON value", c));
+    }
+    value
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    /// The number of arrays and objects the parser is inside.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    #[throws]
+    fn expect(&mut self, c: char) {
+        if !self.eat(c) {
+            throw!(format_err!("expected '{}' in JSON", c));
+        }
+    }
+
+    #[throws]
+    fn value(&mut self) -> Value {
+        self.skip_whitespace();
+        if let Some('{') | Some('[') = self.chars.peek() {
+            if self.depth == MAX_DEPTH {
+                throw!(format_err!("JSON nested too deeply"));
+            }
+            self.depth += 1;
+            let value = self.container()?;
+            self.depth -= 1;
+            return value;
+        }
+        match self.chars.peek() {
+            Some('"') => Value::String(self.string()?),
+            Some('t') => self.word("true", Value::Bool(true))?,
+            Some('f') => self.word("false", Value::Bool(false))?,
+            Some('n') => self.word("null", Value::Null)?,
+            Some(_) => self.number()?,
+            None => throw!(format_err!("unexpected end of JSON")),
+        }
+    }
+
+    #[throws]
+    fn container(&mut self) -> Value {
+        match self.chars.peek() {
+            Some('{') => {
+                self.chars.next();
+                let mut fields = Vec::new();
+                if !self.eat('}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        fields.push((key, self.value()?));
+                        if self.eat('}') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                Value::Object(fields)
+            }
+            Some('[') => {
+                self.chars.next();
+                let mut values = Vec::new();
+                if !self.eat(']') {
+                    loop {
+                        values.push(self.value()?);
+                        if self.eat(']') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                Value::Array(values)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[throws]
+    fn word(&mut self, word: &str, value: Value) -> Value {
+        for expected in word.chars() {
+            if self.chars.next() != Some(expected) {
+                throw!(format_err!("expected '{}' in JSON", word));
+            }
+        }
+        value
+    }
+
+    #[throws]
+    fn number(&mut self) -> Value {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                break;
+            }
+            text.push(c);
+            self.chars.next();
+        }
+        match text.parse() {
+            Ok(n) => Value::Number(n),
+            Err(_) => throw!(format_err!("invalid number '{}' in JSON", text)),
+        }
+    }
+
+    #[throws]
+    fn string(&mut self) -> String {
+        if self.chars.next() != Some('"') {
+            throw!(format_err!("expected a string in JSON"));
+        }
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => break s,
+                Some('\\') => match self.chars.next() {
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('u') => {
+                        let mut unit = self.hex()?;
+                        if let 0xd800..=0xdbff = unit {
+                            // Unpaired surrogates become replacement characters.
+                            let mut ahead = self.chars.clone();
+                            if ahead.next() == Some('\\') && ahead.next() == Some('u') {
+                                self.chars = ahead;
+                                let low = self.hex()?;
+                                if let 0xdc00..=0xdfff = low {
+                                    unit = 0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00);
+                                } else {
+                                    s.push(char::REPLACEMENT_CHARACTER);
+                                    unit = low;
+                                }
+                            }
+                        }
+                        s.push(char::from_u32(unit).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    Some(c) => s.push(c),
+                    None => throw!(format_err!("unterminated string in JSON")),
+                },
+                Some(c) => s.push(c),
+                None => throw!(format_err!("unterminated string in JSON")),
+            }
+        }
+    }
+
+    #[throws]
+    fn hex(&mut self) -> u32 {
+        let digits = (0..4).filter_map(|_| self.chars.next()).collect::<String>();
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            throw!(format_err!("invalid escape '\\u{}' in JSON", digits));
+        }
+        u32::from_str_radix(&digits, 16)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(json: &str) -> String {
+        match parse(json).unwrap() {
+            Value::String(s) => s,
+            value => panic!("{:?} is not a string", value),
+        }
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(
+            parse(r#" {"a": [1, -2.5e1, true, false, null], "b": {}} "#).unwrap(),
+            object(vec![
+                (
+                    "a",
+                    Value::Array(vec![
+                        Value::Number(1.0),
+                        Value::Number(-25.0),
+                        Value::Bool(true),
+                        Value::Bool(false),
+                        Value::Null,
+                    ])
+                ),
+                ("b", object(vec![])),
+            ])
+        );
+        assert_eq!(parse("[]").unwrap(), Value::Array(vec![]));
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        for json in &[
+            "", "[1,]", "{\"a\"}", "[1] 2", "tru", "\"abc", "{1: 2}", "[1 2]",
+        ] {
+            assert!(parse(json).is_err(), "{} parsed", json);
+        }
+    }
+
+    #[test]
+    fn limits_depth() {
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(parse(&"[{\"a\":".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn unescapes_strings() {
+        assert_eq!(
+            string(r#""a\"b\\c\/d\n\r\t\b\f""#),
+            "a\"b\\c/d\n\r\t\u{8}\u{c}"
+        );
+        assert_eq!(string(r#""\u00e9\u4E2D""#), "é中");
+        assert!(parse(r#""\u12""#).is_err());
+        assert!(parse(r#""\u+123""#).is_err());
+    }
+
+    #[test]
+    fn joins_surrogate_pairs() {
+        assert_eq!(string(r#""\ud83d\ude00""#), "😀");
+        assert_eq!(string(r#""\ud83dx""#), "\u{fffd}x");
+        assert_eq!(string(r#""\ud83d\u0041""#), "\u{fffd}A");
+        assert_eq!(string(r#""\ude00""#), "\u{fffd}");
+    }
+
+    #[test]
+    fn round_trips() {
+        let value = object(vec![
+            ("text", Value::from("a\"\\\n\u{1}é😀")),
+            ("n", Value::from(42usize)),
+            ("x", Value::Number(0.5)),
+            ("list", Value::from(vec![Value::Null, Value::from(true)])),
+        ]);
+        assert_eq!(parse(&value.to_string()).unwrap(), value);
+    }
+}
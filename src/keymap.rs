@@ -0,0 +1,831 @@
+use crate::{
+    edot::{Case, View},
+    keys,
+    layout::Orientation,
+    location::{Movement, TextObject},
+    Error,
+};
+use anyhow::format_err;
+use fehler::{throw, throws};
+use std::collections::HashMap;
+use termion::event::Key;
+
+/// Modes whose keys can be remapped.
+pub const MODES: &[&str] = &["normal", "goto", "view", "window", "object", "surround"];
+
+/// What a mapped key does.
+#[derive(Debug, Copy, Clone)]
+pub enum Action {
+    Register,
+    Insert,
+    Append,
+    AppendLineEnd,
+    Open {
+        above: bool,
+    },
+    Change {
+        yank: bool,
+    },
+    Delete {
+        yank: bool,
+    },
+    Yank,
+    Replace,
+    Paste {
+        before: bool,
+    },
+    Undo,
+    Redo,
+    /// In goto mode, extends if the mode was entered with `G`.
+    Move {
+        movement: Movement,
+        drag: bool,
+    },
+    /// Down or up a line, or a screen row in `display_lines` filetypes.
+    Vertical {
+        up: bool,
+        drag: bool,
+    },
+    Row {
+        up: bool,
+        drag: bool,
+    },
+    Goto {
+        drag: bool,
+    },
+    View,
+    Window,
+    Object {
+        around: bool,
+    },
+    Command,
+    Search,
+    Select,
+    Keep {
+        matching: bool,
+    },
+    Pipe,
+    Palette,
+    SearchNext {
+        forward: bool,
+    },
+    Calc,
+    Case(Case),
+    Repeat,
+    RecordMacro,
+    ReplayMacro,
+    Indent {
+        deeper: bool,
+    },
+    Join,
+    SelectLines {
+        up: bool,
+    },
+    RotateSelections {
+        forward: bool,
+    },
+    CopySelections {
+        down: bool,
+    },
+    RotateContents {
+        forward: bool,
+    },
+    ToggleBookmark,
+    Bookmark {
+        forward: bool,
+    },
+    SetMark,
+    JumpMark,
+    SaveSelections,
+    RestoreSelections,
+    CombineSelections,
+    Surround,
+    SurroundAdd,
+    SurroundDelete,
+    SurroundChange,
+    Result {
+        forward: bool,
+    },
+    LastEdit,
+    OpenAtCursor,
+    Definition,
+    Hover,
+    Align(View),
+    Split(Orientation),
+    /// Focuses the window in the direction of an `hjkl` key.
+    Focus(char),
+    /// Selects an object, inner or around depending on the object mode.
+    SelectObject(TextObject),
+}
+
+const fn move_(movement: Movement, drag: bool) -> Action {
+    Action::Move { movement, drag }
+}
+
+/// Every action by name, with a description.
+const ACTIONS: &[(&str, Action, &str)] = &[
+    (
+        "register",
+        Action::Register,
+        "choose the register for the next command",
+    ),
+    ("insert", Action::Insert, "insert before selections"),
+    ("append", Action::Append, "append after selections"),
+    (
+        "append-line-end",
+        Action::AppendLineEnd,
+        "append at line end",
+    ),
+    (
+        "open-below",
+        Action::Open { above: false },
+        "open a line below",
+    ),
+    (
+        "open-above",
+        Action::Open { above: true },
+        "open a line above",
+    ),
+    (
+        "change",
+        Action::Change { yank: true },
+        "yank and change selections",
+    ),
+    (
+        "change-no-yank",
+        Action::Change { yank: false },
+        "change selections without yanking",
+    ),
+    (
+        "delete",
+        Action::Delete { yank: true },
+        "yank and delete selections",
+    ),
+    (
+        "delete-no-yank",
+        Action::Delete { yank: false },
+        "delete selections without yanking",
+    ),
+    ("yank", Action::Yank, "yank selections"),
+    (
+        "replace",
+        Action::Replace,
+        "replace selected chars with the next char",
+    ),
+    (
+        "paste-after",
+        Action::Paste { before: false },
+        "paste after selections",
+    ),
+    (
+        "paste-before",
+        Action::Paste { before: true },
+        "paste before selections",
+    ),
+    ("undo", Action::Undo, "undo"),
+    ("redo", Action::Redo, "redo"),
+    ("move-left", move_(Movement::Left, false), "move left"),
+    ("move-right", move_(Movement::Right, false), "move right"),
+    (
+        "move-down",
+        Action::Vertical {
+            up: false,
+            drag: false,
+        },
+        "move down",
+    ),
+    (
+        "move-up",
+        Action::Vertical {
+            up: true,
+            drag: false,
+        },
+        "move up",
+    ),
+    ("extend-left", move_(Movement::Left, true), "extend left"),
+    ("extend-right", move_(Movement::Right, true), "extend right"),
+    (
+        "extend-down",
+        Action::Vertical {
+            up: false,
+            drag: true,
+        },
+        "extend down",
+    ),
+    (
+        "extend-up",
+        Action::Vertical {
+            up: true,
+            drag: true,
+        },
+        "extend up",
+    ),
+    (
+        "row-down",
+        Action::Row {
+            up: false,
+            drag: false,
+        },
+        "move down a screen row",
+    ),
+    (
+        "row-up",
+        Action::Row {
+            up: true,
+            drag: false,
+        },
+        "move up a screen row",
+    ),
+    (
+        "next-word",
+        move_(Movement::NextWordStart, false),
+        "move to next word",
+    ),
+    (
+        "prev-word",
+        move_(Movement::PrevWordStart, false),
+        "move to previous word",
+    ),
+    (
+        "word-end",
+        move_(Movement::WordEnd, false),
+        "move to word end",
+    ),
+    (
+        "extend-next-word",
+        move_(Movement::NextWordStart, true),
+        "extend to next word",
+    ),
+    (
+        "extend-prev-word",
+        move_(Movement::PrevWordStart, true),
+        "extend to previous word",
+    ),
+    (
+        "extend-word-end",
+        move_(Movement::WordEnd, true),
+        "extend to word end",
+    ),
+    (
+        "matching-bracket",
+        move_(Movement::MatchingBracket, false),
+        "move to the matching bracket",
+    ),
+    (
+        "extend-matching-bracket",
+        move_(Movement::MatchingBracket, true),
+        "extend to the matching bracket",
+    ),
+    ("goto", Action::Goto { drag: false }, "goto"),
+    (
+        "goto-extend",
+        Action::Goto { drag: true },
+        "extend with goto",
+    ),
+    ("view", Action::View, "view"),
+    ("window", Action::Window, "window"),
+    (
+        "inner-object",
+        Action::Object { around: false },
+        "select inner object",
+    ),
+    (
+        "around-object",
+        Action::Object { around: true },
+        "select around object",
+    ),
+    ("command", Action::Command, "command prompt"),
+    ("search", Action::Search, "search prompt"),
+    (
+        "select",
+        Action::Select,
+        "select regex matches in selections",
+    ),
+    (
+        "keep",
+        Action::Keep { matching: true },
+        "keep selections matching a regex",
+    ),
+    (
+        "keep-not",
+        Action::Keep { matching: false },
+        "drop selections matching a regex",
+    ),
+    (
+        "pipe",
+        Action::Pipe,
+        "pipe selections through a shell command",
+    ),
+    (
+        "command-palette",
+        Action::Palette,
+        "choose a command from a filtered list",
+    ),
+    (
+        "search-next",
+        Action::SearchNext { forward: true },
+        "select next search match",
+    ),
+    (
+        "search-prev",
+        Action::SearchNext { forward: false },
+        "select previous search match",
+    ),
+    ("calc", Action::Calc, "evaluate selections as expressions"),
+    ("repeat", Action::Repeat, "repeat the last change"),
+    (
+        "record-macro",
+        Action::RecordMacro,
+        "start or stop recording keys into a register",
+    ),
+    (
+        "replay-macro",
+        Action::ReplayMacro,
+        "replay keys from a register",
+    ),
+    (
+        "swap-case",
+        Action::Case(Case::Swap),
+        "swap the case of selections",
+    ),
+    (
+        "lowercase",
+        Action::Case(Case::Lower),
+        "lowercase selections",
+    ),
+    (
+        "uppercase",
+        Action::Case(Case::Upper),
+        "uppercase selections",
+    ),
+    (
+        "indent",
+        Action::Indent { deeper: true },
+        "indent selected lines",
+    ),
+    (
+        "dedent",
+        Action::Indent { deeper: false },
+        "dedent selected lines",
+    ),
+    ("join", Action::Join, "join selected lines"),
+    (
+        "select-line",
+        Action::SelectLines { up: false },
+        "select whole lines, extending down on repeat",
+    ),
+    (
+        "select-line-up",
+        Action::SelectLines { up: true },
+        "select whole lines, extending up on repeat",
+    ),
+    (
+        "rotate-forward",
+        Action::RotateSelections { forward: true },
+        "rotate selections forward",
+    ),
+    (
+        "rotate-backward",
+        Action::RotateSelections { forward: false },
+        "rotate selections backward",
+    ),
+    (
+        "copy-down",
+        Action::CopySelections { down: true },
+        "copy selections to the next line",
+    ),
+    (
+        "copy-up",
+        Action::CopySelections { down: false },
+        "copy selections to the previous line",
+    ),
+    (
+        "rotate-contents-forward",
+        Action::RotateContents { forward: true },
+        "rotate selection contents forward",
+    ),
+    (
+        "rotate-contents-backward",
+        Action::RotateContents { forward: false },
+        "rotate selection contents backward",
+    ),
+    (
+        "toggle-bookmark",
+        Action::ToggleBookmark,
+        "toggle a bookmark",
+    ),
+    (
+        "next-bookmark",
+        Action::Bookmark { forward: true },
+        "next bookmark",
+    ),
+    (
+        "set-mark",
+        Action::SetMark,
+        "set the mark named by the next char",
+    ),
+    (
+        "jump-mark",
+        Action::JumpMark,
+        "jump to the mark named by the next char",
+    ),
+    (
+        "save-selections",
+        Action::SaveSelections,
+        "save the selections to the register",
+    ),
+    (
+        "restore-selections",
+        Action::RestoreSelections,
+        "restore the selections saved to the register",
+    ),
+    (
+        "combine-selections",
+        Action::CombineSelections,
+        "combine the selections with those saved to the register",
+    ),
+    ("surround", Action::Surround, "surround"),
+    (
+        "surround-add",
+        Action::SurroundAdd,
+        "surround the selections with the pair of the next char",
+    ),
+    (
+        "surround-delete",
+        Action::SurroundDelete,
+        "delete the pair of the next char around the selections",
+    ),
+    (
+        "surround-change",
+        Action::SurroundChange,
+        "change the pair of the next char around the selections to the one after",
+    ),
+    (
+        "prev-bookmark",
+        Action::Bookmark { forward: false },
+        "previous bookmark",
+    ),
+    (
+        "next-result",
+        Action::Result { forward: true },
+        "next find-all result",
+    ),
+    (
+        "prev-result",
+        Action::Result { forward: false },
+        "previous find-all result",
+    ),
+    (
+        "line-start",
+        move_(Movement::LineStart, false),
+        "line start",
+    ),
+    ("line-end", move_(Movement::LineEnd, false), "line end"),
+    (
+        "file-start",
+        move_(Movement::FileStart, false),
+        "file start",
+    ),
+    ("file-end", move_(Movement::FileEnd, false), "file end"),
+    ("last-edit", Action::LastEdit, "last edit"),
+    (
+        "open-at-cursor",
+        Action::OpenAtCursor,
+        "open the URL or file under the cursor",
+    ),
+    (
+        "goto-definition",
+        Action::Definition,
+        "go to the definition of the symbol under the cursor",
+    ),
+    (
+        "hover",
+        Action::Hover,
+        "show the language server's hover for the cursor",
+    ),
+    (
+        "view-center",
+        Action::Align(View::Center),
+        "center the cursor line",
+    ),
+    ("view-top", Action::Align(View::Top), "cursor line at top"),
+    (
+        "view-bottom",
+        Action::Align(View::Bottom),
+        "cursor line at bottom",
+    ),
+    (
+        "split",
+        Action::Split(Orientation::Horizontal),
+        "split stacked",
+    ),
+    (
+        "vsplit",
+        Action::Split(Orientation::Vertical),
+        "split side by side",
+    ),
+    ("focus-left", Action::Focus('h'), "focus the window left"),
+    ("focus-down", Action::Focus('j'), "focus the window below"),
+    ("focus-up", Action::Focus('k'), "focus the window above"),
+    ("focus-right", Action::Focus('l'), "focus the window right"),
+    (
+        "word-object",
+        Action::SelectObject(TextObject::Word),
+        "word",
+    ),
+    (
+        "paren-object",
+        Action::SelectObject(TextObject::Pair('(', ')')),
+        "parentheses",
+    ),
+    (
+        "bracket-object",
+        Action::SelectObject(TextObject::Pair('[', ']')),
+        "brackets",
+    ),
+    (
+        "brace-object",
+        Action::SelectObject(TextObject::Pair('{', '}')),
+        "braces",
+    ),
+    (
+        "angle-object",
+        Action::SelectObject(TextObject::Pair('<', '>')),
+        "angle brackets",
+    ),
+    (
+        "double-quote-object",
+        Action::SelectObject(TextObject::Quote('"')),
+        "double quotes",
+    ),
+    (
+        "single-quote-object",
+        Action::SelectObject(TextObject::Quote('\'')),
+        "single quotes",
+    ),
+    (
+        "backtick-object",
+        Action::SelectObject(TextObject::Quote('`')),
+        "backticks",
+    ),
+    (
+        "indent-object",
+        Action::SelectObject(TextObject::Indent),
+        "indentation block",
+    ),
+];
+
+/// The default bindings as (mode, key, action), in `keys` notation.
+const DEFAULTS: &[(&str, &str, &str)] = &[
+    ("normal", "\"", "register"),
+    ("normal", "i", "insert"),
+    ("normal", "a", "append"),
+    ("normal", "A", "append-line-end"),
+    ("normal", "o", "open-below"),
+    ("normal", "O", "open-above"),
+    ("normal", "c", "change"),
+    ("normal", "<a-c>", "change-no-yank"),
+    ("normal", "d", "delete"),
+    ("normal", "<a-d>", "delete-no-yank"),
+    ("normal", "y", "yank"),
+    ("normal", "r", "replace"),
+    ("normal", "p", "paste-after"),
+    ("normal", "P", "paste-before"),
+    ("normal", "u", "undo"),
+    ("normal", "U", "redo"),
+    ("normal", "h", "move-left"),
+    ("normal", "<left>", "move-left"),
+    ("normal", "j", "move-down"),
+    ("normal", "k", "move-up"),
+    ("normal", "l", "move-right"),
+    ("normal", "<right>", "move-right"),
+    ("normal", "<down>", "row-down"),
+    ("normal", "<up>", "row-up"),
+    ("normal", "H", "extend-left"),
+    ("normal", "J", "extend-down"),
+    ("normal", "K", "extend-up"),
+    ("normal", "L", "extend-right"),
+    ("normal", "w", "next-word"),
+    ("normal", "b", "prev-word"),
+    ("normal", "e", "word-end"),
+    ("normal", "W", "extend-next-word"),
+    ("normal", "B", "extend-prev-word"),
+    ("normal", "E", "extend-word-end"),
+    ("normal", "g", "goto"),
+    ("normal", "G", "goto-extend"),
+    ("normal", "v", "view"),
+    ("normal", "<c-w>", "window"),
+    ("normal", "<a-i>", "inner-object"),
+    ("normal", "<a-a>", "around-object"),
+    ("normal", ":", "command"),
+    ("normal", "<a-:>", "command-palette"),
+    ("normal", "/", "search"),
+    ("normal", "n", "search-next"),
+    ("normal", "N", "search-prev"),
+    ("normal", "s", "select"),
+    ("normal", "<a-k>", "keep"),
+    ("normal", "<a-K>", "keep-not"),
+    ("normal", "|", "pipe"),
+    ("normal", "=", "calc"),
+    ("normal", ".", "repeat"),
+    ("normal", "Q", "record-macro"),
+    ("normal", "q", "replay-macro"),
+    ("normal", "~", "swap-case"),
+    ("normal", "`", "lowercase"),
+    ("normal", "<a-`>", "uppercase"),
+    ("normal", "<a-j>", "join"),
+    ("normal", "x", "select-line"),
+    ("normal", "X", "select-line-up"),
+    ("normal", ">", "indent"),
+    ("normal", "<lt>", "dedent"),
+    ("normal", ")", "rotate-forward"),
+    ("normal", "(", "rotate-backward"),
+    ("normal", "C", "copy-down"),
+    ("normal", "<a-C>", "copy-up"),
+    ("normal", "<a-)>", "rotate-contents-forward"),
+    ("normal", "<a-(>", "rotate-contents-backward"),
+    ("normal", "<a-m>", "toggle-bookmark"),
+    ("normal", "]", "next-bookmark"),
+    ("normal", "[", "prev-bookmark"),
+    ("normal", "%", "matching-bracket"),
+    ("normal", "<a-%>", "extend-matching-bracket"),
+    ("normal", "m", "set-mark"),
+    ("normal", "'", "jump-mark"),
+    ("normal", "Z", "save-selections"),
+    ("normal", "z", "restore-selections"),
+    ("normal", "<a-z>", "combine-selections"),
+    ("normal", "S", "surround"),
+    ("normal", "<a-h>", "hover"),
+    ("normal", "<a-n>", "next-result"),
+    ("normal", "<a-N>", "prev-result"),
+    ("goto", "h", "line-start"),
+    ("goto", "l", "line-end"),
+    ("goto", "j", "file-end"),
+    ("goto", "k", "file-start"),
+    ("goto", ".", "last-edit"),
+    ("goto", "x", "open-at-cursor"),
+    ("goto", "d", "goto-definition"),
+    ("view", "v", "view-center"),
+    ("view", "c", "view-center"),
+    ("view", "t", "view-top"),
+    ("view", "b", "view-bottom"),
+    ("window", "s", "split"),
+    ("window", "v", "vsplit"),
+    ("window", "h", "focus-left"),
+    ("window", "j", "focus-down"),
+    ("window", "k", "focus-up"),
+    ("window", "l", "focus-right"),
+    ("window", "<c-h>", "focus-left"),
+    ("window", "<c-j>", "focus-down"),
+    ("window", "<c-k>", "focus-up"),
+    ("window", "<c-l>", "focus-right"),
+    ("object", "w", "word-object"),
+    ("object", "(", "paren-object"),
+    ("object", ")", "paren-object"),
+    ("object", "b", "paren-object"),
+    ("object", "[", "bracket-object"),
+    ("object", "]", "bracket-object"),
+    ("object", "{", "brace-object"),
+    ("object", "}", "brace-object"),
+    ("object", "B", "brace-object"),
+    ("object", "<lt>", "angle-object"),
+    ("object", ">", "angle-object"),
+    ("object", "\"", "double-quote-object"),
+    ("object", "'", "single-quote-object"),
+    ("object", "`", "backtick-object"),
+    ("object", "i", "indent-object"),
+    ("surround", "a", "surround-add"),
+    ("surround", "d", "surround-delete"),
+    ("surround", "c", "surround-change"),
+];
+
+/// Keys handled outside the keymap, as (mode, keys, description).
+const FIXED: &[(&str, &str, &str)] = &[
+    (
+        "insert",
+        "<tab> <backtab>",
+        "expand snippet, next, previous field",
+    ),
+    ("insert", "<c-v>", "insert a codepoint by hex"),
+    ("insert", "<c-k>", "insert a digraph"),
+    ("insert", "<c-r>", "insert a register"),
+    ("prompt", "<up> <down>", "previous, next history entry"),
+    ("prompt", "<left> <right> <home> <end>", "move the cursor"),
+];
+
+/// The names of all actions, for completion.
+pub fn action_names() -> impl Iterator<Item = &'static str> {
+    ACTIONS.iter().map(|(name, _, _)| *name)
+}
+
+#[throws]
+fn mode(name: &str) -> &'static str {
+    MODES
+        .iter()
+        .copied()
+        .find(|&mode| mode == name)
+        .ok_or_else(|| format_err!("mode '{}' can't be mapped", name))?
+}
+
+#[throws]
+fn action(name: &str) -> (&'static str, Action) {
+    ACTIONS
+        .iter()
+        .find(|(action, _, _)| *action == name)
+        .map(|&(name, action, _)| (name, action))
+        .ok_or_else(|| format_err!("unknown action '{}'", name))?
+}
+
+#[throws]
+fn key(text: &str) -> Key {
+    match *keys::parse(text)? {
+        [key] => key,
+        _ => throw!(format_err!("'{}' is not a single key", text)),
+    }
+}
+
+/// The bindings of each mappable mode, with their action names for display.
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: HashMap<(&'static str, Key), (&'static str, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: HashMap::new(),
+        };
+        for (mode, key, action) in DEFAULTS {
+            keymap.map(mode, key, action).unwrap();
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    pub fn get(&self, mode: &'static str, key: Key) -> Option<Action> {
+        self.bindings.get(&(mode, key)).map(|&(_, action)| action)
+    }
+
+    /// Binds `key` in `mode` to the action named `action`.
+    #[throws]
+    pub fn map(&mut self, mode: &str, key: &str, action: &str) {
+        let binding = (self::mode(mode)?, self::key(key)?);
+        self.bindings.insert(binding, self::action(action)?);
+    }
+
+    #[throws]
+    pub fn unmap(&mut self, mode: &str, key: &str) {
+        let binding = (self::mode(mode)?, self::key(key)?);
+        self.bindings
+            .remove(&binding)
+            .ok_or_else(|| format_err!("'{}' isn't mapped in {} mode", key, mode))?;
+    }
+
+    /// Renders the bindings as aligned text, one per line, grouped by mode.
+    pub fn dump(&self) -> String {
+        let mut rows = Vec::new();
+        for mode in MODES {
+            let mut bindings = self
+                .bindings
+                .iter()
+                .filter(|((name, _), _)| name == mode)
+                .map(|((_, key), (action, _))| (keys::format(&[*key]), *action))
+                .collect::<Vec<_>>();
+            bindings.sort();
+            for (key, action) in bindings {
+                let description = ACTIONS
+                    .iter()
+                    .find(|(name, _, _)| *name == action)
+                    .map_or("", |(_, _, description)| description);
+                rows.push((*mode, key, action, description));
+            }
+        }
+        for &(mode, keys, description) in FIXED {
+            rows.push((mode, keys.to_owned(), "", description));
+        }
+        let width = rows
+            .iter()
+            .map(|(_, key, _, _)| key.len())
+            .max()
+            .unwrap_or(0);
+        let action_width = rows
+            .iter()
+            .map(|(_, _, action, _)| action.len())
+            .max()
+            .unwrap_or(0);
+        let mut text = String::new();
+        for (mode, key, action, description) in rows {
+            text.push_str(&format!(
+                "{:<8}{:<width$}  {:<action_width$}  {}\n",
+                mode,
+                key,
+                action,
+                description,
+                width = width,
+                action_width = action_width,
+            ));
+        }
+        text
+    }
+}
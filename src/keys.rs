@@ -0,0 +1,132 @@
+use crate::Error;
+use anyhow::format_err;
+use fehler::{throw, throws};
+use termion::event::Key;
+
+const NAMED: &[(&str, Key)] = &[
+    ("ret", Key::Char('\n')),
+    ("tab", Key::Char('\t')),
+    ("lt", Key::Char('<')),
+    ("esc", Key::Esc),
+    ("backspace", Key::Backspace),
+    ("del", Key::Delete),
+    ("ins", Key::Insert),
+    ("left", Key::Left),
+    ("right", Key::Right),
+    ("up", Key::Up),
+    ("down", Key::Down),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("backtab", Key::BackTab),
+    ("null", Key::Null),
+];
+
+/// Renders keys in `<name>` notation, e.g. `ihello<esc>`.
+pub fn format(keys: &[Key]) -> String {
+    let mut text = String::new();
+    for key in keys {
+        match key {
+            Key::Char(c) if *c != '<' && *c != '\n' && *c != '\t' => text.push(*c),
+            Key::Alt(c) => text.push_str(&format!("<a-{}>", c)),
+            Key::Ctrl(c) => text.push_str(&format!("<c-{}>", c)),
+            Key::F(n) => text.push_str(&format!("<f{}>", n)),
+            key => match NAMED.iter().find(|(_, named)| named == key) {
+                Some((name, _)) => text.push_str(&format!("<{}>", name)),
+                None => log::error!("key {:?} has no notation", key),
+            },
+        }
+    }
+    text
+}
+
+/// Parses `<name>` notation back into keys; literal newlines are ignored so
+/// long sequences can be wrapped.
+#[throws]
+pub fn parse(text: &str) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {}
+            '<' => {
+                let mut name = String::new();
+                // The first char after a modifier may be `>` itself.
+                while let Some(c) = chars.next_if(|&c| c != '>' || name == "a-" || name == "c-") {
+                    name.push(c);
+                }
+                if chars.next().is_none() {
+                    throw!(format_err!("unclosed key '<{}'", name));
+                }
+                keys.push(parse_named(&name)?);
+            }
+            c => keys.push(Key::Char(c)),
+        }
+    }
+    keys
+}
+
+#[throws]
+fn parse_named(name: &str) -> Key {
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some((_, key)) = NAMED.iter().find(|(named, _)| *named == name) {
+        return *key;
+    }
+    if let Some(c) = name.strip_prefix("a-").and_then(single) {
+        return Key::Alt(c);
+    }
+    if let Some(c) = name.strip_prefix("c-").and_then(single) {
+        return Key::Ctrl(c);
+    }
+    let n = name
+        .strip_prefix('f')
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| format_err!("unknown key '<{}>'", name))?;
+    Key::F(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let keys = [
+            Key::Char('i'),
+            Key::Char('<'),
+            Key::Char('>'),
+            Key::Char('\n'),
+            Key::Char('\t'),
+            Key::Esc,
+            Key::Alt('>'),
+            Key::Alt('<'),
+            Key::Ctrl('w'),
+            Key::F(12),
+            Key::BackTab,
+            Key::PageDown,
+        ];
+        let text = format(&keys);
+        assert_eq!(
+            text,
+            "i<lt>><ret><tab><esc><a->><a-<><c-w><f12><backtab><pagedown>"
+        );
+        assert_eq!(parse(&text).unwrap(), keys);
+        assert_eq!(parse("ab\n<ret>").unwrap(), parse("ab<ret>").unwrap());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let error = |text: &str| parse(text).unwrap_err().to_string();
+        assert_eq!(error("<nope>"), "unknown key '<nope>'");
+        assert_eq!(error("<a-bc>"), "unknown key '<a-bc>'");
+        assert_eq!(error("<fx>"), "unknown key '<fx>'");
+        assert_eq!(error("a<esc"), "unclosed key '<esc'");
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    edot::WindowId,
+    terminal::{Point, Rect, Size},
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Orientation {
+    /// Children stacked top to bottom, like `:split`.
+    Horizontal,
+    /// Children side by side, like `:vsplit`.
+    Vertical,
+}
+
+impl Orientation {
+    /// The number of cells along the axis children are placed on.
+    fn cells(self, region: Rect) -> u16 {
+        match self {
+            Orientation::Horizontal => region.rows(),
+            Orientation::Vertical => region.columns(),
+        }
+    }
+
+    /// Splits at least one cell off the start of `region` along the axis.
+    fn split(self, region: Rect, cells: u16) -> (Rect, Option<Rect>) {
+        match self {
+            Orientation::Horizontal => region.split_horizontal(Size::Fixed(cells)),
+            Orientation::Vertical => region.split_vertical(Size::Fixed(cells)),
+        }
+    }
+}
+
+/// The tree of windows currently on screen.
+#[derive(Debug)]
+pub enum Layout {
+    Window(WindowId),
+    Split {
+        orientation: Orientation,
+        children: Vec<Layout>,
+        /// Each child's share of the split, relative to the others. Equal
+        /// until a separator is dragged, then the children's sizes in cells.
+        sizes: Vec<u16>,
+    },
+}
+
+impl Layout {
+    pub fn contains(&self, window_id: WindowId) -> bool {
+        match self {
+            Layout::Window(id) => *id == window_id,
+            Layout::Split { children, .. } => {
+                children.iter().any(|child| child.contains(window_id))
+            }
+        }
+    }
+
+    /// Shows `new` in place of `old`.
+    pub fn replace(&mut self, old: WindowId, new: WindowId) {
+        match self {
+            Layout::Window(id) if *id == old => *id = new,
+            Layout::Window(_) => {}
+            Layout::Split { children, .. } => {
+                for child in children {
+                    child.replace(old, new);
+                }
+            }
+        }
+    }
+
+    /// Shows `new` next to `target`, after it in `orientation`.
+    pub fn split(&mut self, target: WindowId, new: WindowId, orientation: Orientation) {
+        match self {
+            Layout::Window(id) if *id == target => {
+                *self = Layout::Split {
+                    orientation,
+                    children: vec![Layout::Window(target), Layout::Window(new)],
+                    sizes: vec![1, 1],
+                };
+            }
+            Layout::Window(_) => {}
+            Layout::Split {
+                orientation: own,
+                children,
+                sizes,
+            } => {
+                let position = children
+                    .iter()
+                    .position(|child| matches!(child, Layout::Window(id) if *id == target));
+                match position {
+                    Some(i) if *own == orientation => {
+                        children.insert(i + 1, Layout::Window(new));
+                        *sizes = vec![1; children.len()];
+                    }
+                    _ => {
+                        for child in children {
+                            child.split(target, new, orientation);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Divides `region` between the windows, returning their regions and the
+    /// one-cell separators between them.
+    pub fn regions(&self, region: Rect) -> (Vec<(WindowId, Rect)>, Vec<Rect>) {
+        let mut windows = Vec::new();
+        let mut separators = Vec::new();
+        self.divide(region, &mut windows, &mut separators);
+        (windows, separators)
+    }
+
+    fn divide(
+        &self,
+        region: Rect,
+        windows: &mut Vec<(WindowId, Rect)>,
+        separators: &mut Vec<Rect>,
+    ) {
+        let (orientation, children, sizes) = match self {
+            Layout::Window(id) => return windows.push((*id, region)),
+            Layout::Split {
+                orientation,
+                children,
+                sizes,
+            } => (*orientation, children, sizes),
+        };
+        for (child, (region, separator)) in children.iter().zip(place(orientation, sizes, region)) {
+            child.divide(region, windows, separators);
+            separators.extend(separator);
+        }
+    }
+
+    /// Moves the separator at `from` in the layout drawn in `region` to `to`,
+    /// as far as the windows on either side allow, returning where it went.
+    pub fn drag(&mut self, region: Rect, from: Point, to: Point) -> Option<Point> {
+        let (orientation, children, sizes) = match self {
+            Layout::Window(_) => return None,
+            Layout::Split {
+                orientation,
+                children,
+                sizes,
+            } => (*orientation, children, sizes),
+        };
+        let places = place(orientation, sizes, region);
+        let dragged = places
+            .iter()
+            .position(|(_, separator)| separator.is_some_and(|s| s.contains(from)));
+        let i = match dragged {
+            Some(i) => i,
+            None => {
+                return children
+                    .iter_mut()
+                    .zip(places)
+                    .find_map(|(child, (region, _))| child.drag(region, from, to));
+            }
+        };
+        // Children that didn't fit get the least room.
+        let mut cells = vec![1; children.len()];
+        for (cells, (region, _)) in cells.iter_mut().zip(&places) {
+            *cells = orientation.cells(*region);
+        }
+        let (start, at) = match orientation {
+            Orientation::Horizontal => (places[i].0.start.y, to.y),
+            Orientation::Vertical => (places[i].0.start.x, to.x),
+        };
+        let both = cells[i] + cells[i + 1];
+        cells[i] = at.saturating_sub(start).clamp(1, both - 1);
+        cells[i + 1] = both - cells[i];
+        *sizes = cells;
+        Some(match orientation {
+            Orientation::Horizontal => Point {
+                x: from.x,
+                y: start + sizes[i],
+            },
+            Orientation::Vertical => Point {
+                x: start + sizes[i],
+                y: from.y,
+            },
+        })
+    }
+}
+
+/// The regions of a split's children that fit in `region`, in proportion to
+/// their `sizes`, each with the one-cell separator after it, if any.
+fn place(orientation: Orientation, sizes: &[u16], region: Rect) -> Vec<(Rect, Option<Rect>)> {
+    let mut places = Vec::new();
+    let mut rest = Some(region);
+    for (i, &size) in sizes.iter().enumerate() {
+        let region = match rest {
+            Some(region) => region,
+            None => break,
+        };
+        let left = (sizes.len() - i) as u16;
+        // Each child but the last is followed by a separator.
+        let available = u32::from(orientation.cells(region).saturating_sub(left - 1));
+        let total = sizes[i..].iter().map(|&size| u32::from(size)).sum::<u32>();
+        let cells = (available * u32::from(size)).div_ceil(total.max(1));
+        let (child, after) = orientation.split(region, cells as u16);
+        let (separator, after) = match after.map(|after| orientation.split(after, 1)) {
+            Some((separator, Some(after))) if i + 1 < sizes.len() => (Some(separator), Some(after)),
+            _ => (None, None),
+        };
+        places.push((child, separator));
+        rest = after;
+    }
+    places
+}
@@ -0,0 +1,53 @@
+use crate::{
+    edot::BufferId,
+    format::{self, Format},
+};
+use crossbeam_channel::Sender;
+use ropey::Rope;
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    thread,
+};
+
+const CHUNK: usize = 64 * 1024;
+
+pub enum Loaded {
+    /// The number of bytes read so far.
+    Progress { buffer: BufferId, read: u64 },
+    Done {
+        buffer: BufferId,
+        content: io::Result<(Rope, Format)>,
+    },
+}
+
+/// Reads `path` for `buffer` on another thread, sending progress after each
+/// chunk and the text once it is complete.
+pub fn spawn(path: PathBuf, buffer: BufferId, sender: Sender<Loaded>) {
+    thread::spawn(move || {
+        let content = read(&path, buffer, &sender);
+        let _ = sender.send(Loaded::Done { buffer, content });
+    });
+}
+
+fn read(path: &Path, buffer: BufferId, sender: &Sender<Loaded>) -> io::Result<(Rope, Format)> {
+    let mut file = File::open(path)?;
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0; CHUNK];
+    loop {
+        let n = match file.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        bytes.extend_from_slice(&chunk[..n]);
+        let read = bytes.len() as u64;
+        if sender.send(Loaded::Progress { buffer, read }).is_err() {
+            return Err(io::Error::other("editor exited"));
+        }
+    }
+    let (text, format) = format::decode(bytes);
+    Ok((Rope::from_str(&text), format))
+}
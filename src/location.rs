@@ -1,3 +1,4 @@
+use crate::unicode;
 use fehler::{throw, throws};
 use ropey::{Rope, RopeSlice};
 use std::{
@@ -73,12 +74,42 @@ macro_rules! newtype_impl {
     };
 }
 
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum CharClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Whitespace
+    } else if is_word_char(c) {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Line(NonZeroUsize);
 
 newtype_impl!(Line);
 
 impl Line {
+    pub fn last(rope: &Rope) -> Self {
+        let last = Line::from_one_based(rope.len_lines());
+        if last.is_empty(rope) && !last.is_first() {
+            last - 1usize
+        } else {
+            last
+        }
+    }
+
     pub fn range_of(self, rope: &Rope) -> Range<usize> {
         self.char_of(rope)..self.char_of(rope) + self.slice_of(rope).len_chars()
     }
@@ -91,6 +122,14 @@ impl Line {
         rope.line_to_char(self.zero_based())
     }
 
+    pub fn text_len_of(self, rope: &Rope) -> usize {
+        let slice = self.slice_of(rope);
+        match slice.chars().last() {
+            Some('\n') => slice.len_chars() - 1,
+            _ => slice.len_chars(),
+        }
+    }
+
     pub fn remove_from(self, rope: &mut Rope) {
         rope.remove(self.range_of(rope))
     }
@@ -106,6 +145,194 @@ impl Line {
     pub fn is_empty(self, rope: &Rope) -> bool {
         self.slice_of(rope).len_chars() == 0
     }
+
+    /// Returns the width of the leading whitespace, or `None` for blank lines.
+    pub fn indent_of(self, rope: &Rope) -> Option<usize> {
+        let mut indent = 0;
+        for c in self.slice_of(rope).chars() {
+            match c {
+                ' ' | '\t' => indent += 1,
+                '\n' => return None,
+                _ => return Some(indent),
+            }
+        }
+        None
+    }
+}
+
+/// Returns the first and last of the contiguous lines around `line` that are
+/// indented at least as deeply, including blank lines at the edges only when
+/// `around` is set.
+pub fn indent_block(rope: &Rope, line: Line, around: bool) -> (Line, Line) {
+    let last = Line::last(rope);
+    let base = (line.one_based()..=last.one_based())
+        .chain((1..line.one_based()).rev())
+        .find_map(|i| Line::from_one_based(i).indent_of(rope));
+    let base = match base {
+        Some(base) => base,
+        None => return (line, line),
+    };
+    let inside = |line: Line| line.indent_of(rope).is_none_or(|indent| indent >= base);
+    let (mut start, mut end) = (line, line);
+    while !start.is_first() && inside(start - 1usize) {
+        start -= 1usize;
+    }
+    while end < last && inside(end + 1usize) {
+        end += 1usize;
+    }
+    if !around {
+        while start < end && start.indent_of(rope).is_none() {
+            start += 1usize;
+        }
+        while start < end && end.indent_of(rope).is_none() {
+            end -= 1usize;
+        }
+    }
+    (start, end)
+}
+
+/// A region around a position, selected with `<a-i>` or `<a-a>`.
+#[derive(Debug, Copy, Clone)]
+pub enum TextObject {
+    /// A run of chars of the same class, with the following whitespace when
+    /// around.
+    Word,
+    /// The text between matching brackets, with the brackets when around.
+    Pair(char, char),
+    /// The text between two quotes on a line, with the quotes when around.
+    Quote(char),
+    /// The lines of `indent_block`.
+    Indent,
+}
+
+impl TextObject {
+    /// Returns the char range of the object containing char `at`, if any.
+    pub fn range(self, rope: &Rope, at: usize, around: bool) -> Option<Range<usize>> {
+        match self {
+            TextObject::Word => Some(word_object(rope, at, around)),
+            TextObject::Pair(open, close) => pair_object(rope, at, open, close, around),
+            TextObject::Quote(quote) => quote_object(rope, at, quote, around),
+            TextObject::Indent => {
+                let line = Line::from_zero_based(rope.char_to_line(at));
+                let (start, end) = indent_block(rope, line, around);
+                Some(start.char_of(rope)..end.range_of(rope).end)
+            }
+        }
+    }
+}
+
+fn word_object(rope: &Rope, at: usize, around: bool) -> Range<usize> {
+    let last = rope.len_chars() - 1;
+    let class = |i: usize| char_class(rope.char(i));
+    let is_blank = |i: usize| rope.char(i) == ' ' || rope.char(i) == '\t';
+    let (mut start, mut end) = (at, at);
+    while start > 0 && class(start - 1) == class(at) && rope.char(start - 1) != '\n' {
+        start -= 1;
+    }
+    while end < last && class(end + 1) == class(at) && rope.char(end + 1) != '\n' {
+        end += 1;
+    }
+    if around {
+        if end < last && is_blank(end + 1) {
+            while end < last && is_blank(end + 1) {
+                end += 1;
+            }
+        } else {
+            while start > 0 && is_blank(start - 1) {
+                start -= 1;
+            }
+        }
+    }
+    start..end + 1
+}
+
+fn pair_object(
+    rope: &Rope,
+    at: usize,
+    open: char,
+    close: char,
+    around: bool,
+) -> Option<Range<usize>> {
+    let mut depth = 0;
+    let mut start = at;
+    loop {
+        let c = rope.char(start);
+        if c == close && start != at {
+            depth += 1;
+        } else if c == open {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+        }
+        start = start.checked_sub(1)?;
+    }
+    let mut depth = 0;
+    let mut end = start + 1;
+    loop {
+        if end >= rope.len_chars() {
+            return None;
+        }
+        let c = rope.char(end);
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+        }
+        end += 1;
+    }
+    Some(if around {
+        start..end + 1
+    } else {
+        start + 1..end
+    })
+}
+
+fn quote_object(rope: &Rope, at: usize, quote: char, around: bool) -> Option<Range<usize>> {
+    let line = rope.char_to_line(at);
+    let line_start = rope.line_to_char(line);
+    let quotes = rope
+        .line(line)
+        .chars()
+        .enumerate()
+        .filter(|&(_, c)| c == quote)
+        .map(|(i, _)| line_start + i)
+        .collect::<Vec<_>>();
+    let (start, end) = quotes
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|&(start, end)| (start..=end).contains(&at))?;
+    Some(if around {
+        start..end + 1
+    } else {
+        start + 1..end
+    })
+}
+
+/// Whether a grapheme cluster starts at char `i`.
+#[cfg(not(feature = "char-movement"))]
+pub fn is_boundary(rope: &Rope, i: usize) -> bool {
+    if i == 0 || i >= rope.len_chars() {
+        return true;
+    }
+    let (prev, c) = (rope.char(i - 1), rope.char(i));
+    if unicode::is_regional_indicator(prev) && unicode::is_regional_indicator(c) {
+        let run = (0..i)
+            .rev()
+            .take_while(|&j| unicode::is_regional_indicator(rope.char(j)))
+            .count();
+        return run % 2 == 0;
+    }
+    !unicode::extends(prev, c)
+}
+
+/// Every char is its own cluster with the `char-movement` feature.
+#[cfg(feature = "char-movement")]
+pub fn is_boundary(_: &Rope, _: usize) -> bool {
+    true
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -126,6 +353,18 @@ pub struct Position {
 }
 
 impl Position {
+    pub fn from_char(rope: &Rope, char_idx: usize) -> Self {
+        let line = rope.char_to_line(char_idx);
+        Self {
+            line: Line::from_zero_based(line),
+            column: Column::from_zero_based(char_idx - rope.line_to_char(line)),
+        }
+    }
+
+    pub fn from_byte(rope: &Rope, byte_idx: usize) -> Self {
+        Self::from_char(rope, rope.byte_to_char(byte_idx))
+    }
+
     pub fn char_of(self, rope: &Rope) -> usize {
         self.line.char_of(rope) + self.column.zero_based()
     }
@@ -152,7 +391,7 @@ impl Position {
                     assert_eq!(rope.len_chars(), 0);
                     self.line = Line::from_one_based(1);
                     self.column = Column::from_one_based(1);
-                    panic!(MovementError::SelectionEmpty);
+                    panic!("{}", MovementError::SelectionEmpty);
                 }
             } else {
                 self.move_to(rope, Movement::LineEnd).unwrap();
@@ -191,16 +430,26 @@ impl Position {
                         throw!(MovementError::NoPrevLine);
                     }
                 } else {
-                    self.column -= 1;
+                    let start = self.line.char_of(rope);
+                    let mut i = self.char_of(rope) - 1;
+                    while i > start && !is_boundary(rope, i) {
+                        i -= 1;
+                    }
+                    self.column = Column::from_zero_based(i - start);
                 }
             }
             Movement::Right => {
                 self.validate(rope);
-                if self.column.one_based() == self.line.slice_of(rope).len_chars() {
+                let range = self.line.range_of(rope);
+                let mut i = self.char_of(rope) + 1;
+                while i < range.end && !is_boundary(rope, i) {
+                    i += 1;
+                }
+                if i >= range.end {
                     self.move_to(rope, Movement::Down)?;
                     self.move_to(rope, Movement::LineStart)?;
                 } else {
-                    self.column += 1;
+                    self.column = Column::from_zero_based(i - range.start);
                 }
             }
             Movement::Up => {
@@ -217,6 +466,41 @@ impl Position {
                     throw!(MovementError::NoNextLine);
                 }
             }
+            Movement::RowUp(width, wrap) | Movement::RowDown(width, wrap) => {
+                let slice = self.line.slice_of(rope);
+                let column = self
+                    .column
+                    .zero_based()
+                    .min(slice.len_chars().saturating_sub(1));
+                let starts = row_starts(slice, width, wrap);
+                let row = starts.partition_point(|&start| start <= column) - 1;
+                let offset = slice
+                    .chars()
+                    .skip(starts[row])
+                    .take(column - starts[row])
+                    .map(unicode::width)
+                    .sum();
+                match movement {
+                    Movement::RowUp(..) if row > 0 => {
+                        self.column = row_column(slice, &starts, row - 1, offset);
+                    }
+                    Movement::RowUp(..) => {
+                        self.move_to(rope, Movement::Up)?;
+                        let slice = self.line.slice_of(rope);
+                        let starts = row_starts(slice, width, wrap);
+                        self.column = row_column(slice, &starts, starts.len() - 1, offset);
+                    }
+                    _ if row + 1 < starts.len() => {
+                        self.column = row_column(slice, &starts, row + 1, offset);
+                    }
+                    _ => {
+                        self.move_to(rope, Movement::Down)?;
+                        let slice = self.line.slice_of(rope);
+                        let starts = row_starts(slice, width, wrap);
+                        self.column = row_column(slice, &starts, 0, offset);
+                    }
+                }
+            }
             Movement::LineStart => {
                 self.column = Column::from_one_based(1);
             }
@@ -228,19 +512,97 @@ impl Position {
                 self.move_to(rope, Movement::LineStart)?;
             }
             Movement::FileEnd => {
-                let last = Line::from_one_based(rope.len_lines());
-                if !last.is_empty(rope) {
-                    self.line = last;
-                } else {
-                    self.line = last - 1;
-                }
+                self.line = Line::last(rope);
                 self.move_to(rope, Movement::LineStart)?;
             }
+            Movement::NextWordStart => {
+                self.validate(rope);
+                let last = rope.len_chars() - 1;
+                let class = |i: usize| char_class(rope.char(i));
+                let mut i = self.char_of(rope);
+                let start = class(i);
+                while i < last && start != CharClass::Whitespace && class(i) == start {
+                    i += 1;
+                }
+                while i < last && class(i) == CharClass::Whitespace {
+                    i += 1;
+                }
+                if i == self.char_of(rope) || class(i) == CharClass::Whitespace {
+                    throw!(MovementError::NoNextWord);
+                }
+                *self = Self::from_char(rope, i);
+            }
+            Movement::WordEnd => {
+                self.validate(rope);
+                let last = rope.len_chars() - 1;
+                let class = |i: usize| char_class(rope.char(i));
+                let mut i = self.char_of(rope) + 1;
+                while i < last && class(i) == CharClass::Whitespace {
+                    i += 1;
+                }
+                if i >= last {
+                    throw!(MovementError::NoNextWord);
+                }
+                while i < last && class(i + 1) == class(i) {
+                    i += 1;
+                }
+                *self = Self::from_char(rope, i);
+            }
+            Movement::MatchingBracket => {
+                self.validate(rope);
+                let at = self.char_of(rope);
+                let c = rope.char(at);
+                let &(open, close) = BRACKETS
+                    .iter()
+                    .find(|&&(open, close)| c == open || c == close)
+                    .ok_or(MovementError::NoBracket)?;
+                let forward = c == open;
+                let mut depth = 0usize;
+                let mut i = at;
+                loop {
+                    i = match forward {
+                        true if i + 1 < rope.len_chars() => i + 1,
+                        false if i > 0 => i - 1,
+                        _ => throw!(MovementError::Unbalanced),
+                    };
+                    let c = rope.char(i);
+                    if c == open || c == close {
+                        if (c == open) == forward {
+                            depth += 1;
+                        } else if depth == 0 {
+                            break;
+                        } else {
+                            depth -= 1;
+                        }
+                    }
+                }
+                *self = Self::from_char(rope, i);
+            }
+            Movement::PrevWordStart => {
+                self.validate(rope);
+                let class = |i: usize| char_class(rope.char(i));
+                let mut i = self.char_of(rope);
+                while i > 0 && class(i - 1) == CharClass::Whitespace {
+                    i -= 1;
+                }
+                if i == 0 {
+                    throw!(MovementError::NoPrevWord);
+                }
+                i -= 1;
+                while i > 0 && class(i - 1) == class(i) {
+                    i -= 1;
+                }
+                *self = Self::from_char(rope, i);
+            }
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+/// The brackets `%` matches. Other kinds nested inside are skipped over, so
+/// `( [ ) ]` still matches its parentheses.
+const BRACKETS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}')];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Selection {
     pub start: Position,
     pub end: Position,
@@ -308,10 +670,58 @@ impl Selection {
         rope.remove(self.range_of(rope));
         self.end = self.start;
         self.validate_fix(rope);
-        // TODO: the file must be terminated by a final newline
     }
 }
 
+/// Merges selections that overlap or are adjacent, with no char between them,
+/// keeping the first one primary. A merged selection faces the way the
+/// primary, or else its first part, did. Returns whether anything was merged.
+pub fn merge_selections(selections: &mut Vec<Selection>, rope: &Rope) -> bool {
+    let chars = selections
+        .iter()
+        .map(|selection| {
+            let selection = selection.valid(rope).ordered();
+            (selection.start.char_of(rope), selection.end.char_of(rope))
+        })
+        .collect::<Vec<_>>();
+    let mut order = (0..selections.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| chars[i]);
+    // Each merged char range, with the index it takes its direction from.
+    let mut merged = Vec::<((usize, usize), usize)>::new();
+    for i in order {
+        let (start, end) = chars[i];
+        match merged.last_mut() {
+            Some(((_, last_end), from)) if start <= *last_end + 1 => {
+                *last_end = (*last_end).max(end);
+                if i == 0 {
+                    *from = 0;
+                }
+            }
+            _ => merged.push(((start, end), i)),
+        }
+    }
+    if merged.len() == selections.len() {
+        return false;
+    }
+    let primary = merged.iter().position(|&(_, from)| from == 0).unwrap();
+    merged.rotate_left(primary);
+    *selections = merged
+        .into_iter()
+        .map(|((start, end), from)| {
+            let selection = Selection {
+                start: Position::from_char(rope, start),
+                end: Position::from_char(rope, end),
+            };
+            if selections[from].start <= selections[from].end {
+                selection
+            } else {
+                selection.flipped()
+            }
+        })
+        .collect();
+    true
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Movement {
     Left,
@@ -322,6 +732,86 @@ pub enum Movement {
     LineEnd,
     FileStart,
     FileEnd,
+    NextWordStart,
+    PrevWordStart,
+    WordEnd,
+    /// To the bracket matching the one under the cursor.
+    MatchingBracket,
+    /// Up one screen row, for text wrapped at the given width.
+    RowUp(usize, Wrap),
+    /// Down one screen row, for text wrapped at the given width.
+    RowDown(usize, Wrap),
+}
+
+/// How lines wider than the window are broken into screen rows.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Wrap {
+    /// At the last char that fits.
+    Chars,
+    /// After the last blank that fits, or at the last char if there's none.
+    Words,
+    /// Not at all; lines are cut off and the window scrolls sideways.
+    None,
+}
+
+/// Returns the char offsets in `slice` where its screen rows start, for rows
+/// `width` columns wide.
+pub fn row_starts(slice: RopeSlice, width: usize, wrap: Wrap) -> Vec<usize> {
+    let mut starts = vec![0];
+    if wrap == Wrap::None {
+        return starts;
+    }
+    let mut col = 0;
+    // Where the row could be broken after a blank, and the columns since.
+    let mut blank = None;
+    for (i, c) in slice.chars().enumerate() {
+        let char_width = unicode::width(c);
+        if char_width > 0 && col > 0 && col + char_width > width {
+            match blank.filter(|_| wrap == Wrap::Words) {
+                Some((start, since)) if since + char_width <= width => {
+                    starts.push(start);
+                    col = since;
+                }
+                _ => {
+                    starts.push(i);
+                    col = 0;
+                }
+            }
+            blank = None;
+        }
+        col += char_width;
+        if let Some((_, since)) = &mut blank {
+            *since += char_width;
+        }
+        if c == ' ' || c == '\t' {
+            blank = Some((i + 1, 0));
+        }
+    }
+    starts
+}
+
+/// The column `offset` display columns into row `row`, or the row's last
+/// char if it's narrower.
+fn row_column(slice: RopeSlice, starts: &[usize], row: usize, offset: usize) -> Column {
+    let end = starts
+        .get(row + 1)
+        .copied()
+        .unwrap_or_else(|| slice.len_chars());
+    let mut column = starts[row];
+    let mut used = 0;
+    for c in slice
+        .chars()
+        .skip(column)
+        .take(end.saturating_sub(column + 1))
+    {
+        let char_width = unicode::width(c);
+        if char_width > 0 && used + char_width > offset {
+            break;
+        }
+        used += char_width;
+        column += 1;
+    }
+    Column::from_zero_based(column)
 }
 
 #[derive(Debug, Error, Copy, Clone)]
@@ -332,4 +822,102 @@ pub enum MovementError {
     NoPrevLine,
     #[error("no next line")]
     NoNextLine,
+    #[error("no previous word")]
+    NoPrevWord,
+    #[error("no next word")]
+    NoNextWord,
+    #[error("no bracket under the cursor")]
+    NoBracket,
+    #[error("no matching bracket")]
+    Unbalanced,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection(rope: &Rope, start: usize, end: usize) -> Selection {
+        Selection {
+            start: Position::from_char(rope, start),
+            end: Position::from_char(rope, end),
+        }
+    }
+
+    fn merged(text: &str, selections: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let rope = Rope::from(text);
+        let mut selections = selections
+            .iter()
+            .map(|&(start, end)| selection(&rope, start, end))
+            .collect::<Vec<_>>();
+        merge_selections(&mut selections, &rope);
+        selections
+            .iter()
+            .map(|s| (s.start.char_of(&rope), s.end.char_of(&rope)))
+            .collect()
+    }
+
+    #[test]
+    fn merges_overlapping() {
+        assert_eq!(merged("abcdefgh\n", &[(0, 3), (2, 5)]), [(0, 5)]);
+        assert_eq!(merged("abcdefgh\n", &[(0, 6), (2, 3)]), [(0, 6)]);
+    }
+
+    #[test]
+    fn merges_identical() {
+        assert_eq!(merged("abcdefgh\n", &[(1, 2), (1, 2), (1, 2)]), [(1, 2)]);
+    }
+
+    #[test]
+    fn keeps_direction_of_primary() {
+        assert_eq!(merged("abcdefgh\n", &[(5, 2), (0, 3)]), [(5, 0)]);
+        assert_eq!(merged("abcdefgh\n", &[(0, 3), (5, 2)]), [(0, 5)]);
+    }
+
+    #[test]
+    fn keeps_primary_first() {
+        assert_eq!(
+            merged("abcdefgh\n", &[(6, 7), (0, 1), (1, 2)]),
+            [(6, 7), (0, 2)]
+        );
+    }
+
+    #[test]
+    fn merges_adjacent() {
+        assert_eq!(merged("abcdefgh\n", &[(0, 1), (2, 3)]), [(0, 3)]);
+        assert_eq!(merged("abcdefgh\n", &[(4, 4), (3, 3), (5, 5)]), [(3, 5)]);
+    }
+
+    #[test]
+    fn keeps_separate_selections() {
+        assert_eq!(
+            merged("abcdefgh\n", &[(0, 1), (3, 4), (6, 6)]),
+            [(0, 1), (3, 4), (6, 6)]
+        );
+    }
+
+    #[test]
+    fn merges_across_line_ends() {
+        // The newline ending the first line is adjacent to the second line.
+        assert_eq!(merged("abc\ndef\n", &[(1, 3), (4, 5)]), [(1, 5)]);
+        assert_eq!(merged("abc\ndef\n", &[(1, 2), (4, 5)]), [(1, 2), (4, 5)]);
+        assert_eq!(merged("abc\ndef\n", &[(3, 3), (3, 4)]), [(3, 4)]);
+    }
+
+    #[test]
+    fn clamps_selections_past_line_ends() {
+        let rope = Rope::from("ab\ncd\n");
+        let past = Selection {
+            start: Position {
+                line: Line::from_one_based(1),
+                column: Column::from_one_based(9),
+            },
+            end: Position {
+                line: Line::from_one_based(1),
+                column: Column::from_one_based(9),
+            },
+        };
+        let mut selections = vec![selection(&rope, 0, 2), past];
+        assert!(merge_selections(&mut selections, &rope));
+        assert_eq!(selections, [selection(&rope, 0, 2)]);
+    }
 }
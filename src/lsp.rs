@@ -0,0 +1,534 @@
+use crate::{
+    edot::WindowId,
+    effect,
+    json::{self, object, Value},
+    Error,
+};
+use anyhow::{format_err, Context as _};
+use crossbeam_channel::{unbounded, Sender};
+use fehler::throws;
+use log::{error, trace};
+use ropey::Rope;
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    process::{self, Child, Command, Stdio},
+    thread,
+};
+
+/// A message from the language server for `filetype`, or `None` once it
+/// has exited.
+pub struct Incoming {
+    pub filetype: &'static str,
+    pub message: Option<Value>,
+}
+
+/// What a request was sent for, so that its response can be used.
+#[derive(Debug, Copy, Clone)]
+pub enum Pending {
+    Initialize,
+    Definition(WindowId),
+    Hover(WindowId),
+    /// Completion at the buffer revision the request was made at, putting
+    /// in the first or last word.
+    Completion {
+        window: WindowId,
+        revision: usize,
+        forward: bool,
+    },
+}
+
+/// A running language server, spoken to over its stdin and stdout by a
+/// thread each.
+pub struct Server {
+    child: Child,
+    outgoing: Sender<String>,
+    next_id: u64,
+    pending: HashMap<u64, Pending>,
+    /// Whether the server answered `initialize`. Until it has, messages
+    /// wait in `queued`.
+    ready: bool,
+    queued: Vec<String>,
+    /// Whether the server takes changed ranges rather than whole texts.
+    incremental: bool,
+}
+
+impl Server {
+    /// Starts `command` for `filetype` files in `root`, sending what it says
+    /// to `sender`, and asks it to initialize.
+    #[throws]
+    pub fn spawn(
+        filetype: &'static str,
+        command: &str,
+        root: &Path,
+        sender: Sender<Incoming>,
+    ) -> Self {
+        let args = shlex::split(command)
+            .filter(|args| !args.is_empty())
+            .ok_or_else(|| format_err!("invalid language server command '{}'", command))?;
+        let mut child = Command::new(&args[0])
+            .args(&args[1..])
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to start language server '{}'", command))?;
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (outgoing, messages) = unbounded::<String>();
+        thread::spawn(move || {
+            for message in messages {
+                let written = write!(
+                    stdin,
+                    "Content-Length: {}\r\n\r\n{}",
+                    message.len(),
+                    message
+                )
+                .and_then(|()| stdin.flush());
+                if written.is_err() {
+                    return;
+                }
+            }
+        });
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Ok(Some(message)) = read_message(&mut reader) {
+                trace!("{} server: {}", filetype, message);
+                match json::parse(&message) {
+                    Ok(message) => {
+                        let message = Some(message);
+                        if sender.send(Incoming { filetype, message }).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => error!("{} server sent invalid JSON: {}", filetype, err),
+                }
+            }
+            let _ = sender.send(Incoming {
+                filetype,
+                message: None,
+            });
+        });
+        let mut server = Server {
+            child,
+            outgoing,
+            next_id: 0,
+            pending: HashMap::new(),
+            ready: false,
+            queued: Vec::new(),
+            incremental: false,
+        };
+        let params = object(vec![
+            ("processId", Value::from(process::id() as usize)),
+            ("rootUri", Value::from(uri(root))),
+            ("capabilities", capabilities()),
+            ("clientInfo", object(vec![("name", Value::from("edot"))])),
+        ]);
+        let id = server.next_id();
+        server.pending.insert(id, Pending::Initialize);
+        server.write(request(id, "initialize", params));
+        server
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn write(&mut self, message: Value) {
+        trace!("to server: {}", message);
+        let _ = self.outgoing.send(message.to_string());
+    }
+
+    /// Sends `message`, or holds it until the server is initialized.
+    fn send(&mut self, message: Value) {
+        if self.ready {
+            self.write(message);
+        } else {
+            self.queued.push(message.to_string());
+        }
+    }
+
+    pub fn request(&mut self, method: &str, params: Value, pending: Pending) {
+        let id = self.next_id();
+        self.pending.insert(id, pending);
+        self.send(request(id, method, params));
+    }
+
+    pub fn notify(&mut self, method: &str, params: Value) {
+        self.send(object(vec![
+            ("jsonrpc", Value::from("2.0")),
+            ("method", Value::from(method)),
+            ("params", params),
+        ]));
+    }
+
+    /// Answers a request from the server.
+    pub fn respond(&mut self, id: Value, result: Value) {
+        self.write(object(vec![
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id),
+            ("result", result),
+        ]));
+    }
+
+    /// Takes what the response with `id` was for.
+    pub fn take_pending(&mut self, id: u64) -> Option<Pending> {
+        self.pending.remove(&id)
+    }
+
+    /// Finishes initializing with the server's answer, sending what waited.
+    pub fn initialized(&mut self, result: &Value) {
+        let sync = result
+            .get("capabilities")
+            .and_then(|capabilities| capabilities.get("textDocumentSync"));
+        let kind = sync.and_then(|sync| sync.get("change").unwrap_or(sync).as_u64());
+        self.incremental = kind == Some(2);
+        self.ready = true;
+        self.notify("initialized", object(vec![]));
+        for message in std::mem::take(&mut self.queued) {
+            let _ = self.outgoing.send(message);
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    object(vec![
+        ("jsonrpc", Value::from("2.0")),
+        ("id", Value::from(id)),
+        ("method", Value::from(method)),
+        ("params", params),
+    ])
+}
+
+fn capabilities() -> Value {
+    let text_document = object(vec![
+        ("synchronization", object(vec![("didSave", true.into())])),
+        (
+            "hover",
+            object(vec![(
+                "contentFormat",
+                vec![Value::from("plaintext"), Value::from("markdown")].into(),
+            )]),
+        ),
+        ("definition", object(vec![])),
+        (
+            "completion",
+            object(vec![(
+                "completionItem",
+                object(vec![("snippetSupport", false.into())]),
+            )]),
+        ),
+        ("publishDiagnostics", object(vec![])),
+    ]);
+    object(vec![("textDocument", text_document)])
+}
+
+/// Reads one message, framed by a `Content-Length` header. Returns `None` at
+/// the end of the stream.
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            len = value.trim().parse::<usize>().ok();
+        }
+    }
+    let len = len.ok_or_else(|| io::Error::other("message without a Content-Length"))?;
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// A buffer as the server last saw it.
+pub struct Document {
+    pub uri: String,
+    version: u64,
+    /// The buffer revision `text` is from.
+    revision: usize,
+    text: Rope,
+}
+
+impl Document {
+    /// Tells `server` that the file at `path` was opened with `content`.
+    pub fn open(
+        server: &mut Server,
+        path: &Path,
+        filetype: &str,
+        content: &Rope,
+        revision: usize,
+    ) -> Self {
+        let uri = uri(path);
+        let language = match filetype {
+            "sh" => "shellscript",
+            other => other,
+        };
+        server.notify(
+            "textDocument/didOpen",
+            object(vec![(
+                "textDocument",
+                object(vec![
+                    ("uri", Value::from(&*uri)),
+                    ("languageId", Value::from(language)),
+                    ("version", Value::from(0usize)),
+                    ("text", Value::from(content.to_string())),
+                ]),
+            )]),
+        );
+        Document {
+            uri,
+            version: 0,
+            revision,
+            text: content.clone(),
+        }
+    }
+
+    /// The `textDocument` parameter naming the document.
+    pub fn identifier(&self) -> Value {
+        object(vec![("uri", Value::from(&*self.uri))])
+    }
+
+    /// Tells `server` how `content` changed since it last saw it, as the
+    /// one range that covers every change in `effects`, or as the whole text
+    /// if the server doesn't take ranges or the changes were forgotten.
+    pub fn sync(&mut self, server: &mut Server, content: &Rope, effects: &effect::Log) {
+        if effects.revision() == self.revision {
+            return;
+        }
+        let change = match effects.since(self.revision) {
+            Some(changes) if server.incremental => {
+                // Chars before the earliest change and after the latest one
+                // from the end are the same in both texts.
+                let mut len = self.text.len_chars();
+                let (mut start, mut suffix) = (len, len);
+                for change in changes {
+                    start = start.min(change.removed.start);
+                    suffix = suffix.min(len - change.removed.end);
+                    len = len - change.removed.len() + change.inserted;
+                }
+                let old_end = self.text.len_chars() - suffix;
+                let new_end = content.len_chars() - suffix;
+                let start = start.min(old_end).min(new_end);
+                object(vec![
+                    ("range", range(&self.text, start..old_end)),
+                    ("text", content.slice(start..new_end).to_string().into()),
+                ])
+            }
+            _ => object(vec![("text", content.to_string().into())]),
+        };
+        self.version += 1;
+        self.revision = effects.revision();
+        self.text = content.clone();
+        server.notify(
+            "textDocument/didChange",
+            object(vec![
+                (
+                    "textDocument",
+                    object(vec![
+                        ("uri", Value::from(&*self.uri)),
+                        ("version", Value::from(self.version)),
+                    ]),
+                ),
+                ("contentChanges", vec![change].into()),
+            ]),
+        );
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// A problem the server found, with the buffer revision its range is for.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub range: Range<usize>,
+    pub revision: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Reads the diagnostics of a `publishDiagnostics` notification, which are
+/// for the text the server last saw.
+pub fn diagnostics(params: &Value, document: &Document) -> Vec<Diagnostic> {
+    let diagnostics = params
+        .get("diagnostics")
+        .and_then(Value::as_array)
+        .unwrap_or_default();
+    diagnostics
+        .iter()
+        .filter_map(|diagnostic| {
+            let severity = match diagnostic.get("severity").and_then(Value::as_u64) {
+                Some(2) => Severity::Warning,
+                Some(3) => Severity::Information,
+                Some(4) => Severity::Hint,
+                _ => Severity::Error,
+            };
+            Some(Diagnostic {
+                range: offsets(&document.text, diagnostic.get("range")?)?,
+                revision: document.revision,
+                severity,
+                message: diagnostic.get("message")?.as_str()?.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// The position of char `offset` in `content`, in UTF-16 code units as the
+/// protocol counts them.
+pub fn position(content: &Rope, offset: usize) -> Value {
+    let line = content.char_to_line(offset);
+    let character = content
+        .slice(content.line_to_char(line)..offset)
+        .chars()
+        .map(char::len_utf16)
+        .sum::<usize>();
+    object(vec![
+        ("line", Value::from(line)),
+        ("character", Value::from(character)),
+    ])
+}
+
+pub fn range(content: &Rope, range: Range<usize>) -> Value {
+    object(vec![
+        ("start", position(content, range.start)),
+        ("end", position(content, range.end)),
+    ])
+}
+
+/// The char offset of a position, clamped to `content`.
+pub fn offset(content: &Rope, position: &Value) -> Option<usize> {
+    let line = position.get("line")?.as_u64()? as usize;
+    let character = position.get("character")?.as_u64()? as usize;
+    if line >= content.len_lines() {
+        return Some(content.len_chars() - 1);
+    }
+    let start = content.line_to_char(line);
+    let mut units = 0;
+    let mut chars = 0;
+    for c in content.line(line).chars() {
+        if units >= character || c == '\n' {
+            break;
+        }
+        units += c.len_utf16();
+        chars += 1;
+    }
+    Some(start + chars)
+}
+
+pub fn offsets(content: &Rope, range: &Value) -> Option<Range<usize>> {
+    Some(offset(content, range.get("start")?)?..offset(content, range.get("end")?)?)
+}
+
+/// A `file://` URI for `path`, percent-encoding anything but unreserved
+/// chars and slashes.
+pub fn uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for &b in path.to_string_lossy().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~/".contains(&b) {
+            uri.push(char::from(b));
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    uri
+}
+
+/// The path of a `file://` URI.
+pub fn path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let escape = encoded
+            .get(i + 1..i + 3)
+            .filter(|_| encoded[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escape {
+            Some(b) => {
+                bytes.push(b);
+                i += 3;
+            }
+            None => {
+                bytes.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// The text of hover contents, which may be markup, a marked string or a
+/// list of marked strings.
+pub fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .map(hover_text)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        _ => contents
+            .get("value")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned(),
+    }
+}
+
+/// The texts completion items would insert.
+pub fn completions(result: &Value) -> Vec<String> {
+    let items = result
+        .as_array()
+        .or_else(|| result.get("items")?.as_array())
+        .unwrap_or_default();
+    items
+        .iter()
+        .filter_map(|item| {
+            let text = item
+                .get("textEdit")
+                .and_then(|edit| edit.get("newText"))
+                .or_else(|| item.get("insertText"))
+                .or_else(|| item.get("label"))?;
+            Some(text.as_str()?.to_owned())
+        })
+        .collect()
+}
+
+/// The first location of a definition result, which may be a location, a
+/// list of them or a list of location links.
+pub fn location(result: &Value) -> Option<(PathBuf, &Value)> {
+    let location = match result {
+        Value::Array(locations) => locations.first()?,
+        location => location,
+    };
+    let uri = location
+        .get("uri")
+        .or_else(|| location.get("targetUri"))?
+        .as_str()?;
+    let range = location
+        .get("range")
+        .or_else(|| location.get("targetSelectionRange"))?;
+    Some((path(uri)?, range))
+}
@@ -1,18 +1,81 @@
 #![allow(dead_code)]
 
+mod address;
+mod align;
+mod calc;
+mod clipboard;
+mod complete;
+mod config;
 mod edot;
+mod effect;
+mod filetype;
+mod follow;
+mod format;
+mod highlight;
 mod id_vec;
+mod json;
+mod keymap;
+mod keys;
+mod layout;
+mod load;
 mod location;
+mod lsp;
+mod perf;
+mod prompt;
+mod quickfix;
+mod recent;
+mod regex;
+mod save;
+mod screen;
+mod search;
+mod snippet;
+mod subprocess;
+mod substitute;
 mod terminal;
+mod unicode;
+mod url;
+mod watch;
 
 type Result<T = (), E = anyhow::Error> = anyhow::Result<T, E>;
 type Error = anyhow::Error;
 
 use crate::edot::Edot;
-use fehler::throws;
+use anyhow::format_err;
+use fehler::{throw, throws};
+use std::env;
+
+/// Files named on the command line, each with the line from a `+N` before
+/// it, and whether `-R` asked for them to be read-only.
+#[throws]
+fn parse_args(args: impl Iterator<Item = String>) -> (Vec<(String, Option<usize>)>, bool) {
+    let mut files = Vec::new();
+    let mut read_only = false;
+    let mut line = None;
+    let mut options = true;
+    for arg in args {
+        if options && arg == "--" {
+            options = false;
+        } else if options && arg == "-R" {
+            read_only = true;
+        } else if let Some(number) = arg.strip_prefix('+').filter(|_| options) {
+            let number = number
+                .parse()
+                .map_err(|_| format_err!("invalid line number '{}'", arg))?;
+            line = Some(number);
+        } else if options && arg.starts_with('-') {
+            throw!(format_err!("unknown option '{}'", arg));
+        } else {
+            files.push((arg, line.take()));
+        }
+    }
+    (files, read_only)
+}
 
 #[throws]
 fn main() {
     env_logger::init();
-    Edot::new()?.run()?;
+    let (files, read_only) = parse_args(env::args().skip(1))?;
+    let mut edot = Edot::new()?;
+    edot.open_files(files, read_only);
+    edot.run()?;
 }
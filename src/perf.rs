@@ -0,0 +1,58 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// How many recent timings are kept for `:perf`.
+const LEN: usize = 100;
+
+/// Recent event handling and draw durations.
+#[derive(Debug, Default)]
+pub struct Perf {
+    events: VecDeque<Duration>,
+    draws: VecDeque<Duration>,
+    /// Draws that took longer than the frame budget, since startup.
+    slow_draws: usize,
+}
+
+fn push(timings: &mut VecDeque<Duration>, duration: Duration) {
+    if timings.len() == LEN {
+        timings.pop_front();
+    }
+    timings.push_back(duration);
+}
+
+fn summary(name: &str, timings: &VecDeque<Duration>) -> String {
+    let total = timings.iter().sum::<Duration>();
+    let max = timings.iter().max().copied().unwrap_or_default();
+    let average = total.checked_div(timings.len() as u32).unwrap_or_default();
+    format!(
+        "{}: last {} average {:?} max {:?}\n",
+        name,
+        timings.len(),
+        average,
+        max
+    )
+}
+
+impl Perf {
+    pub fn event(&mut self, duration: Duration) {
+        push(&mut self.events, duration);
+    }
+
+    /// Records a draw, returning whether it went over `budget`.
+    pub fn draw(&mut self, duration: Duration, budget: Duration) -> bool {
+        push(&mut self.draws, duration);
+        let slow = duration > budget;
+        self.slow_draws += usize::from(slow);
+        slow
+    }
+
+    pub fn last_draw(&self) -> Option<Duration> {
+        self.draws.back().copied()
+    }
+
+    pub fn report(&self) -> String {
+        let mut text = summary("events", &self.events);
+        text.push_str(&summary("draws", &self.draws));
+        text.push_str(&format!("draws over budget: {}\n", self.slow_draws));
+        text
+    }
+}
@@ -0,0 +1,108 @@
+use termion::event::Key;
+
+/// An in-progress command line with its own cursor and history.
+#[derive(Debug, Default)]
+pub struct Prompt {
+    pub text: String,
+    /// Cursor position in chars.
+    pub cursor: usize,
+    pub history: Vec<String>,
+    /// The history entry being shown, counted back from the newest.
+    history_index: Option<usize>,
+}
+
+impl Prompt {
+    fn byte_of(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    /// Applies an editing key, returning false if it should close the prompt.
+    pub fn edit(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char(c) => self.insert(c),
+            Key::Backspace => return self.backspace(),
+            Key::Left => self.left(),
+            Key::Right => self.right(),
+            Key::Home => self.home(),
+            Key::End => self.end(),
+            Key::Up => self.recall(true),
+            Key::Down => self.recall(false),
+            _ => {}
+        }
+        true
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let at = self.byte_of(self.cursor);
+        self.text.insert(at, c);
+        self.cursor += 1;
+    }
+
+    /// Deletes the char before the cursor, returning false if there was none.
+    pub fn backspace(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        let at = self.byte_of(self.cursor);
+        self.text.remove(at);
+        true
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+
+    /// Steps through history, `back` towards older entries.
+    pub fn recall(&mut self, back: bool) {
+        let index = match (self.history_index, back) {
+            (None, true) if !self.history.is_empty() => Some(0),
+            (None, _) => return,
+            (Some(i), true) => Some((i + 1).min(self.history.len() - 1)),
+            (Some(0), false) => None,
+            (Some(i), false) => Some(i - 1),
+        };
+        self.history_index = index;
+        self.text = match index {
+            Some(i) => self.history[self.history.len() - 1 - i].clone(),
+            None => String::new(),
+        };
+        self.end();
+    }
+
+    /// Splits the text at the cursor for drawing.
+    pub fn split(&self) -> (&str, &str) {
+        self.text.split_at(self.byte_of(self.cursor))
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+        self.history_index = None;
+    }
+
+    /// Takes the entered text, recording it in history.
+    pub fn take(&mut self) -> String {
+        let text = std::mem::take(&mut self.text);
+        self.clear();
+        if !text.is_empty() && self.history.last() != Some(&text) {
+            self.history.push(text.clone());
+        }
+        text
+    }
+}
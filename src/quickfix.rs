@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+/// A location list entry, with one-based line and column.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Splits `path:line[:column]: message`, requiring the path to exist.
+fn parse_location(text: &str, dir: &Path) -> Option<Entry> {
+    let mut fields = text.splitn(4, ':');
+    let path = dir.join(fields.next()?.trim());
+    let line = fields.next()?.trim().parse().ok()?;
+    let rest = fields.collect::<Vec<_>>();
+    let (column, message) = match rest.first().and_then(|c| c.trim().parse().ok()) {
+        Some(column) => (column, rest[1..].join(":")),
+        None => (1, rest.join(":")),
+    };
+    if !path.is_file() {
+        return None;
+    }
+    Some(Entry {
+        path,
+        line,
+        column,
+        message: message.trim().to_owned(),
+    })
+}
+
+/// Finds locations in tool output: `grep -n` and compiler style
+/// `path:line:column: message` lines, and rustc style `--> path:line:column`
+/// lines, which take the message from the last `error` or `warning` line.
+/// Relative paths are resolved against `dir`.
+pub fn parse(text: &str, dir: &Path) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut message = String::new();
+    for line in text.lines() {
+        if line.starts_with("error") || line.starts_with("warning") {
+            message = line.to_owned();
+        } else if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            if let Some(mut entry) = parse_location(location, dir) {
+                entry.message = message.clone();
+                entries.push(entry);
+            }
+            continue;
+        }
+        if let Some(entry) = parse_location(line, dir) {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    #[test]
+    fn parses_grep_and_rustc_output() {
+        let dir = env::temp_dir().join(format!("edot-quickfix-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let grep = "src/main.rs:1:fn main() {}\nmissing.rs:2:gone\nBinary file x matches\n";
+        let entries = parse(grep, &dir);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, dir.join("src/main.rs"));
+        assert_eq!((entries[0].line, entries[0].column), (1, 1));
+        assert_eq!(entries[0].message, "fn main() {}");
+        let rustc = "\
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:1:13
+  |
+1 | fn main() { x }
+  |             ^ not found in this scope
+";
+        let entries = parse(rustc, &dir);
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].line, entries[0].column), (1, 13));
+        assert_eq!(
+            entries[0].message,
+            "error[E0425]: cannot find value `x` in this scope"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
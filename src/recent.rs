@@ -0,0 +1,71 @@
+use crate::Error;
+use anyhow::Context as _;
+use fehler::throws;
+use log::warn;
+use std::{fs, path::PathBuf};
+use xdg::BaseDirectories;
+
+const LEN: usize = 100;
+
+/// The last cursor position in a file, as zero-based line and column.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Reads the recent files list, newest first, skipping invalid entries.
+#[throws]
+pub fn load() -> Vec<Entry> {
+    let dirs = BaseDirectories::with_prefix("edot")?;
+    let path = match dirs.find_data_file("recent") {
+        Some(path) => path,
+        None => return Vec::new(),
+    };
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    text.lines()
+        .filter_map(|line| {
+            let entry = parse(line);
+            if entry.is_none() {
+                warn!("skipping invalid recent files entry '{}'", line);
+            }
+            entry
+        })
+        .collect()
+}
+
+fn parse(line: &str) -> Option<Entry> {
+    let mut fields = line.splitn(3, '\t');
+    Some(Entry {
+        line: fields.next()?.parse().ok()?,
+        column: fields.next()?.parse().ok()?,
+        path: PathBuf::from(fields.next()?),
+    })
+}
+
+/// Moves the `updated` entries to the front of `entries` and writes out the
+/// list.
+#[throws]
+pub fn save(entries: &mut Vec<Entry>, updated: Vec<Entry>) {
+    for entry in updated {
+        entries.retain(|old| old.path != entry.path);
+        entries.insert(0, entry);
+    }
+    entries.truncate(LEN);
+    let dirs = BaseDirectories::with_prefix("edot")?;
+    let path = dirs.place_data_file("recent")?;
+    let text = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{}\t{}\t{}\n",
+                entry.line,
+                entry.column,
+                entry.path.display()
+            )
+        })
+        .collect::<String>();
+    fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))?;
+}
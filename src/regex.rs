@@ -0,0 +1,776 @@
+use crate::{location::is_word_char, Error};
+use anyhow::format_err;
+use fehler::{throw, throws};
+use std::{iter::Peekable, ops::Range, str::Chars};
+
+/// A small backtracking-free regex engine (Pike VM) with capture groups.
+///
+/// Supports alternation, groups (`(...)`, `(?:...)`), greedy and lazy
+/// quantifiers (`*`, `+`, `?`, `{n,m}`), classes (`[a-z]`, `[^...]`),
+/// `.`, `\d`, `\w`, `\s` and their negations, and the assertions `^`, `$`
+/// (which match at line boundaries), `\b` and `\B`.
+/// The largest count a repetition may have.
+const MAX_REPEAT: usize = 1000;
+/// How many instructions a compiled regex may have, since repetitions are
+/// compiled by copying what they repeat.
+const MAX_PROGRAM: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+    groups: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Captures {
+    slots: Vec<Option<usize>>,
+}
+
+impl Captures {
+    pub fn get(&self, group: usize) -> Option<Range<usize>> {
+        match (self.slots.get(group * 2)?, self.slots.get(group * 2 + 1)?) {
+            (Some(start), Some(end)) => Some(*start..*end),
+            _ => None,
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.get(0).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    /// Moves every group `offset` bytes later, for matches found in a slice.
+    pub fn shifted(mut self, offset: usize) -> Self {
+        for slot in self.slots.iter_mut().flatten() {
+            *slot += offset;
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Split(usize, usize),
+    Jmp(usize),
+    Save(usize),
+    Assert(Assertion),
+    Match,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Assertion {
+    LineStart,
+    LineEnd,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+#[derive(Debug, Clone)]
+struct Class {
+    negated: bool,
+    folded: bool,
+    items: Vec<ClassItem>,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+impl Regex {
+    #[throws]
+    pub fn new(pattern: &str) -> Self {
+        Self::with_case(pattern, false)?
+    }
+
+    #[throws]
+    pub fn with_case(pattern: &str, case_insensitive: bool) -> Self {
+        let mut parser = Parser {
+            chars: pattern.chars().peekable(),
+            groups: 1,
+        };
+        let node = parser.alternation()?;
+        if let Some(c) = parser.chars.next() {
+            throw!(format_err!("unexpected '{}' in regex", c));
+        }
+        let mut compiler = Compiler {
+            program: Vec::new(),
+            case_insensitive,
+        };
+        compiler.push(Inst::Save(0));
+        compiler.emit(&node)?;
+        compiler.push(Inst::Save(1));
+        compiler.push(Inst::Match);
+        Self {
+            program: compiler.program,
+            groups: parser.groups,
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find_at(text, 0).is_some()
+    }
+
+    /// Finds the leftmost match starting at or after byte offset `start`.
+    pub fn find_at(&self, text: &str, start: usize) -> Option<Captures> {
+        let len = self.program.len();
+        let mut current = Threads::new(len);
+        let mut next = Threads::new(len);
+        let mut matched = None;
+        let mut pos = start;
+        let mut chars = text[start..].chars();
+        loop {
+            if matched.is_none() {
+                let slots = vec![None; self.groups * 2];
+                self.add(&mut current, text, pos, 0, slots);
+            }
+            let c = chars.next();
+            let width = c.map_or(0, char::len_utf8);
+            for i in 0..current.list.len() {
+                let (pc, slots) = current.list[i].clone();
+                let step = match &self.program[pc] {
+                    Inst::Char(expected) => c == Some(*expected),
+                    Inst::Any => c.is_some_and(|c| c != '\n'),
+                    Inst::Class(class) => c.is_some_and(|c| class.matches(c)),
+                    Inst::Match => {
+                        matched = Some(slots);
+                        break;
+                    }
+                    _ => false,
+                };
+                if step {
+                    self.add(&mut next, text, pos + width, pc + 1, slots);
+                }
+            }
+            if c.is_none() || (next.list.is_empty() && matched.is_some()) {
+                break;
+            }
+            pos += width;
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+        matched.map(|slots| Captures { slots })
+    }
+
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Captures> + 'a {
+        let mut start = Some(0);
+        std::iter::from_fn(move || {
+            let captures = self.find_at(text, start?)?;
+            let range = captures.range();
+            start = if range.is_empty() {
+                text[range.end..]
+                    .chars()
+                    .next()
+                    .map(|c| range.end + c.len_utf8())
+            } else {
+                Some(range.end)
+            };
+            Some(captures)
+        })
+    }
+
+    fn add(
+        &self,
+        threads: &mut Threads,
+        text: &str,
+        pos: usize,
+        pc: usize,
+        mut slots: Vec<Option<usize>>,
+    ) {
+        if threads.contains(pc) {
+            return;
+        }
+        threads.mark(pc);
+        match &self.program[pc] {
+            Inst::Jmp(target) => self.add(threads, text, pos, *target, slots),
+            Inst::Split(first, second) => {
+                self.add(threads, text, pos, *first, slots.clone());
+                self.add(threads, text, pos, *second, slots);
+            }
+            Inst::Save(slot) => {
+                if let Some(slot_value) = slots.get_mut(*slot) {
+                    *slot_value = Some(pos);
+                }
+                self.add(threads, text, pos, pc + 1, slots);
+            }
+            Inst::Assert(assertion) => {
+                if assertion.holds(text, pos) {
+                    self.add(threads, text, pos, pc + 1, slots);
+                }
+            }
+            _ => threads.list.push((pc, slots)),
+        }
+    }
+}
+
+struct Threads {
+    list: Vec<(usize, Vec<Option<usize>>)>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Self {
+            list: Vec::new(),
+            seen: vec![false; len],
+        }
+    }
+
+    fn contains(&self, pc: usize) -> bool {
+        self.seen[pc]
+    }
+
+    fn mark(&mut self, pc: usize) {
+        self.seen[pc] = true;
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        for seen in &mut self.seen {
+            *seen = false;
+        }
+    }
+}
+
+impl Assertion {
+    fn holds(self, text: &str, pos: usize) -> bool {
+        let before = text[..pos].chars().next_back();
+        let after = text[pos..].chars().next();
+        match self {
+            Assertion::LineStart => before.is_none() || before == Some('\n'),
+            Assertion::LineEnd => after.is_none() || after == Some('\n'),
+            Assertion::WordBoundary | Assertion::NotWordBoundary => {
+                let boundary = before.is_some_and(is_word_char) != after.is_some_and(is_word_char);
+                boundary == matches!(self, Assertion::WordBoundary)
+            }
+        }
+    }
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        let hit = |c: char| self.items.iter().any(|item| item.matches(c));
+        let found =
+            hit(c) || (self.folded && (c.to_lowercase().any(hit) || c.to_uppercase().any(hit)));
+        found != self.negated
+    }
+}
+
+impl ClassItem {
+    fn matches(self, c: char) -> bool {
+        match self {
+            ClassItem::Range(start, end) => start <= c && c <= end,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != negated,
+            ClassItem::Word(negated) => is_word_char(c) != negated,
+            ClassItem::Space(negated) => c.is_whitespace() != negated,
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    groups: usize,
+}
+
+impl Parser<'_> {
+    #[throws]
+    fn alternation(&mut self) -> Node {
+        let mut branches = vec![self.concat()?];
+        while self.chars.peek() == Some(&'|') {
+            self.chars.next();
+            branches.push(self.concat()?);
+        }
+        if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Node::Alt(branches)
+        }
+    }
+
+    #[throws]
+    fn concat(&mut self) -> Node {
+        let mut nodes = Vec::new();
+        while let Some(&c) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantifier(atom)?);
+        }
+        match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        }
+    }
+
+    #[throws]
+    fn quantifier(&mut self, node: Node) -> Node {
+        let (min, max) = match self.chars.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.chars.next();
+                return self.counted(node)?;
+            }
+            _ => return node,
+        };
+        self.chars.next();
+        self.repeat(node, min, max)?
+    }
+
+    #[throws]
+    fn counted(&mut self, node: Node) -> Node {
+        let min = self.number()?;
+        let max = if self.chars.peek() == Some(&',') {
+            self.chars.next();
+            if self.chars.peek() == Some(&'}') {
+                None
+            } else {
+                Some(self.number()?)
+            }
+        } else {
+            Some(min)
+        };
+        if self.chars.next() != Some('}') {
+            throw!(format_err!("unterminated repetition in regex"));
+        }
+        if max.is_some_and(|max| max < min) {
+            throw!(format_err!("invalid repetition range in regex"));
+        }
+        if max.unwrap_or(min) > MAX_REPEAT {
+            throw!(format_err!("repetition count over {} in regex", MAX_REPEAT));
+        }
+        self.repeat(node, min, max)?
+    }
+
+    #[throws]
+    fn repeat(&mut self, node: Node, min: usize, max: Option<usize>) -> Node {
+        if let Node::Assert(_) | Node::Empty = node {
+            throw!(format_err!("nothing to repeat in regex"));
+        }
+        let greedy = if self.chars.peek() == Some(&'?') {
+            self.chars.next();
+            false
+        } else {
+            true
+        };
+        Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        }
+    }
+
+    #[throws]
+    fn number(&mut self) -> usize {
+        let mut digits = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            digits.push(c);
+            self.chars.next();
+        }
+        digits
+            .parse()
+            .map_err(|_| format_err!("invalid repetition count in regex"))?
+    }
+
+    #[throws]
+    fn atom(&mut self) -> Node {
+        match self.chars.next().unwrap() {
+            '(' => {
+                let index = if self.chars.peek() == Some(&'?') {
+                    self.chars.next();
+                    if self.chars.next() != Some(':') {
+                        throw!(format_err!("unsupported group syntax in regex"));
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups - 1)
+                };
+                let node = self.alternation()?;
+                if self.chars.next() != Some(')') {
+                    throw!(format_err!("unclosed group in regex"));
+                }
+                Node::Group(Box::new(node), index)
+            }
+            '[' => Node::Class(self.class()?),
+            '.' => Node::Any,
+            '^' => Node::Assert(Assertion::LineStart),
+            '$' => Node::Assert(Assertion::LineEnd),
+            '\\' => match self.escape()? {
+                Escape::Char(c) => Node::Char(c),
+                Escape::Item(item) => Node::Class(Class {
+                    negated: false,
+                    folded: false,
+                    items: vec![item],
+                }),
+                Escape::Assert(assertion) => Node::Assert(assertion),
+            },
+            c @ '*' | c @ '+' | c @ '?' => throw!(format_err!("nothing to repeat before '{}'", c)),
+            c => Node::Char(c),
+        }
+    }
+
+    #[throws]
+    fn escape(&mut self) -> Escape {
+        let c = self
+            .chars
+            .next()
+            .ok_or_else(|| format_err!("trailing backslash in regex"))?;
+        match c {
+            'd' => Escape::Item(ClassItem::Digit(false)),
+            'D' => Escape::Item(ClassItem::Digit(true)),
+            'w' => Escape::Item(ClassItem::Word(false)),
+            'W' => Escape::Item(ClassItem::Word(true)),
+            's' => Escape::Item(ClassItem::Space(false)),
+            'S' => Escape::Item(ClassItem::Space(true)),
+            'b' => Escape::Assert(Assertion::WordBoundary),
+            'B' => Escape::Assert(Assertion::NotWordBoundary),
+            'n' => Escape::Char('\n'),
+            't' => Escape::Char('\t'),
+            'r' => Escape::Char('\r'),
+            c if c.is_alphanumeric() => throw!(format_err!("unknown escape '\\{}' in regex", c)),
+            c => Escape::Char(c),
+        }
+    }
+
+    #[throws]
+    fn class(&mut self) -> Class {
+        let negated = if self.chars.peek() == Some(&'^') {
+            self.chars.next();
+            true
+        } else {
+            false
+        };
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self
+                .chars
+                .next()
+                .ok_or_else(|| format_err!("unclosed character class in regex"))?;
+            let start = match c {
+                ']' if !first => break,
+                '\\' => match self.escape()? {
+                    Escape::Char(c) => c,
+                    Escape::Item(item) => {
+                        items.push(item);
+                        first = false;
+                        continue;
+                    }
+                    Escape::Assert(_) => throw!(format_err!("assertion in character class")),
+                },
+                c => c,
+            };
+            first = false;
+            let mut lookahead = self.chars.clone();
+            if lookahead.next() == Some('-') && lookahead.peek().is_some_and(|&c| c != ']') {
+                self.chars.next();
+                let end = match self.chars.next().unwrap() {
+                    '\\' => match self.escape()? {
+                        Escape::Char(c) => c,
+                        _ => throw!(format_err!("invalid range in character class")),
+                    },
+                    c => c,
+                };
+                if end < start {
+                    throw!(format_err!("invalid range in character class"));
+                }
+                items.push(ClassItem::Range(start, end));
+            } else {
+                items.push(ClassItem::Range(start, start));
+            }
+        }
+        Class {
+            negated,
+            folded: false,
+            items,
+        }
+    }
+}
+
+enum Escape {
+    Char(char),
+    Item(ClassItem),
+    Assert(Assertion),
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+    case_insensitive: bool,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> usize {
+        self.program.push(inst);
+        self.program.len() - 1
+    }
+
+    #[throws]
+    fn emit(&mut self, node: &Node) {
+        if self.program.len() > MAX_PROGRAM {
+            throw!(format_err!("regex too large"));
+        }
+        match node {
+            Node::Empty => {}
+            Node::Char(c) if self.case_insensitive && c.to_lowercase().ne(c.to_uppercase()) => {
+                let mut items = vec![ClassItem::Range(*c, *c)];
+                items.extend(c.to_lowercase().map(|c| ClassItem::Range(c, c)));
+                items.extend(c.to_uppercase().map(|c| ClassItem::Range(c, c)));
+                self.push(Inst::Class(Class {
+                    negated: false,
+                    folded: false,
+                    items,
+                }));
+            }
+            Node::Char(c) => {
+                self.push(Inst::Char(*c));
+            }
+            Node::Any => {
+                self.push(Inst::Any);
+            }
+            Node::Class(class) => {
+                self.push(Inst::Class(Class {
+                    folded: self.case_insensitive,
+                    ..class.clone()
+                }));
+            }
+            Node::Assert(assertion) => {
+                self.push(Inst::Assert(*assertion));
+            }
+            Node::Group(node, index) => {
+                if let Some(index) = index {
+                    self.push(Inst::Save(index * 2));
+                    self.emit(node)?;
+                    self.push(Inst::Save(index * 2 + 1));
+                } else {
+                    self.emit(node)?;
+                }
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.emit(node)?;
+                }
+            }
+            Node::Alt(branches) => {
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 < branches.len() {
+                        let split = self.push(Inst::Split(0, 0));
+                        self.emit(branch)?;
+                        jumps.push(self.push(Inst::Jmp(0)));
+                        let next = self.program.len();
+                        self.program[split] = Inst::Split(split + 1, next);
+                    } else {
+                        self.emit(branch)?;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jmp(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    self.emit(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0));
+                        self.emit(node)?;
+                        self.push(Inst::Jmp(split));
+                        let end = self.program.len();
+                        self.program[split] = self.split(*greedy, split + 1, end);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0)));
+                            self.emit(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = self.split(*greedy, split + 1, end);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn split(&self, greedy: bool, body: usize, exit: usize) -> Inst {
+        if greedy {
+            Inst::Split(body, exit)
+        } else {
+            Inst::Split(exit, body)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, text: &str) -> Option<Range<usize>> {
+        Some(Regex::new(pattern).unwrap().find_at(text, 0)?.range())
+    }
+
+    fn groups<'a>(pattern: &str, text: &'a str) -> Vec<Option<&'a str>> {
+        let captures = Regex::new(pattern).unwrap().find_at(text, 0).unwrap();
+        (0..captures.len())
+            .map(|i| captures.get(i).map(|range| &text[range]))
+            .collect()
+    }
+
+    #[test]
+    fn matches_classes() {
+        assert_eq!(find("[a-c]+", "xxabcay"), Some(2..6));
+        assert_eq!(find("[^0-9]+", "12ab3"), Some(2..4));
+        assert_eq!(find("[]a]", "x]"), Some(1..2));
+        assert_eq!(find("[a-]", "x-"), Some(1..2));
+        assert_eq!(find(r"[\d.]+", "v1.25"), Some(1..5));
+        assert_eq!(find(r"\d+", "ab123c"), Some(2..5));
+        assert_eq!(find(r"\w+", "  foo_1 "), Some(2..7));
+        assert_eq!(find(r"\s", "a\tb"), Some(1..2));
+        assert_eq!(find(r"\D\W", "1a!"), Some(1..3));
+        assert_eq!(find(".", "é"), Some(0..2));
+        assert_eq!(find("a.b", "a\nb"), None);
+        assert!(Regex::new("[b-a]").is_err());
+        assert!(Regex::new("[abc").is_err());
+    }
+
+    #[test]
+    fn folds_case() {
+        let regex = Regex::with_case("[a-c]x", true).unwrap();
+        assert!(regex.is_match("BX"));
+        assert!(Regex::with_case("straße", true).unwrap().is_match("STRAßE"));
+        assert!(!Regex::new("abc").unwrap().is_match("ABC"));
+    }
+
+    #[test]
+    fn matches_anchors() {
+        assert_eq!(find("^b", "a\nb"), Some(2..3));
+        assert_eq!(find("a$", "a\nb"), Some(0..1));
+        assert_eq!(find("^$", "a\n\nb"), Some(2..2));
+        assert_eq!(find(r"\bfoo\b", "afoo foo"), Some(5..8));
+        assert_eq!(find(r"\Bo", "foo"), Some(1..2));
+        assert!(Regex::new("^*").is_err());
+    }
+
+    #[test]
+    fn repeats() {
+        assert_eq!(find("a+", "baaa"), Some(1..4));
+        assert_eq!(find("a+?", "baaa"), Some(1..2));
+        assert_eq!(find("a*", "baaa"), Some(0..0));
+        assert_eq!(find("<.*>", "<a><b>"), Some(0..6));
+        assert_eq!(find("<.*?>", "<a><b>"), Some(0..3));
+        assert_eq!(find("ab?c", "ac"), Some(0..2));
+        assert_eq!(find("a{2}", "aaaa"), Some(0..2));
+        assert_eq!(find("a{2,3}", "aaaa"), Some(0..3));
+        assert_eq!(find("a{2,}", "aaaa"), Some(0..4));
+        assert_eq!(find("a{2,3}?", "aaaa"), Some(0..2));
+        assert_eq!(find("a{3}", "aa"), None);
+        assert_eq!(find("(a*)*b", "aaab"), Some(0..4));
+        for pattern in &["*a", "a{3,2}", "a{", "a{x}", "a**"] {
+            assert!(Regex::new(pattern).is_err(), "{} compiled", pattern);
+        }
+    }
+
+    #[test]
+    fn limits_size() {
+        assert!(Regex::new("a{1000}").is_ok());
+        assert!(Regex::new("a{1001}").is_err());
+        assert!(Regex::new("a{2,1001}").is_err());
+        assert!(Regex::new("a{1000000000}").is_err());
+        assert!(Regex::new("a{99999999999999999999999}").is_err());
+        let nested = Regex::new("(a{1000}){1000}").unwrap_err();
+        assert_eq!(nested.to_string(), "regex too large");
+        assert!(Regex::new("((a{100}){100}){100}").is_err());
+        assert!(Regex::new("(a|b){0,1000}").is_ok());
+    }
+
+    #[test]
+    fn captures_groups() {
+        assert_eq!(
+            groups("(a)(b(c))?", "abc"),
+            [Some("abc"), Some("a"), Some("bc"), Some("c")]
+        );
+        assert_eq!(
+            groups("(a)(b(c))?", "a"),
+            [Some("a"), Some("a"), None, None]
+        );
+        assert_eq!(groups("(?:ab)+", "abab"), [Some("abab")]);
+        assert_eq!(
+            groups("(foo|bar)baz", "barbaz"),
+            [Some("barbaz"), Some("bar")]
+        );
+        assert_eq!(groups("(a)+", "aaa"), [Some("aaa"), Some("a")]);
+        assert!(Regex::new("(a").is_err());
+        assert!(Regex::new("a)").is_err());
+        assert!(Regex::new("(?=a)").is_err());
+    }
+
+    #[test]
+    fn prefers_leftmost_first() {
+        assert_eq!(find("a|ab", "ab"), Some(0..1));
+        assert_eq!(find("ab|a", "ab"), Some(0..2));
+        assert_eq!(find("b|ab", "ab"), Some(0..2));
+    }
+
+    #[test]
+    fn rejects_backreferences() {
+        assert!(Regex::new(r"(a)\1").is_err());
+    }
+
+    #[test]
+    fn finds_all() {
+        let regex = Regex::new("a*").unwrap();
+        let ranges = regex
+            .find_iter("baaé")
+            .map(|c| c.range())
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, [0..0, 1..3, 3..3, 5..5]);
+        let regex = Regex::new("o").unwrap();
+        let starts = regex.find_iter("foo boo").map(|c| c.range().start);
+        assert_eq!(starts.collect::<Vec<_>>(), [1, 2, 5, 6]);
+        assert_eq!(regex.find_at("foo", 2).map(|c| c.range()), Some(2..3));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    format::{self, Format},
+    Error,
+};
+use anyhow::Context as _;
+use fehler::throws;
+use ropey::Rope;
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Writes `content` to `path` so that it is never left half written: the
+/// text goes to a temporary file in the same directory, which is synced and
+/// then renamed over `path`. With `backup`, the old file is kept as `path~`.
+/// Returns whether any chars couldn't be represented in the encoding.
+#[throws]
+pub fn write(content: &Rope, format: Format, path: &Path, backup: bool) -> bool {
+    // Replace what a symlink points to rather than the link.
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    let old = fs::metadata(&path).ok();
+    if backup && old.is_some() {
+        let backup = sibling(&path, "", "~");
+        fs::copy(&path, &backup)
+            .with_context(|| format!("failed to back up to {}", backup.display()))?;
+    }
+    let temp = sibling(&path, ".", &format!(".edot-{}", process::id()));
+    let permissions = old.map(|old| old.permissions());
+    let written = write_temp(content, format, &temp, permissions);
+    let renamed = written.and_then(|lossy| {
+        fs::rename(&temp, &path)
+            .with_context(|| format!("failed to replace {}", path.display()))?;
+        Ok(lossy)
+    });
+    if renamed.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    let lossy = renamed?;
+    if let Some(dir) = path.parent() {
+        // Makes the rename itself durable; not all systems allow this.
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    lossy
+}
+
+#[throws]
+fn write_temp(
+    content: &Rope,
+    format: Format,
+    temp: &Path,
+    permissions: Option<fs::Permissions>,
+) -> bool {
+    let file =
+        File::create(temp).with_context(|| format!("failed to create {}", temp.display()))?;
+    let mut writer = BufWriter::new(file);
+    let lossy = format::encode(content, format, &mut writer)
+        .and_then(|lossy| writer.flush().map(|()| lossy))
+        .with_context(|| format!("failed to write {}", temp.display()))?;
+    let file = writer.into_inner().map_err(|err| err.into_error())?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    file.sync_all()
+        .with_context(|| format!("failed to sync {}", temp.display()))?;
+    lossy
+}
+
+/// `path` with `prefix` and `suffix` around its file name.
+fn sibling(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(prefix);
+    name.push(path.file_name().unwrap_or_default());
+    name.push(suffix);
+    path.with_file_name(name)
+}
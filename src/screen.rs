@@ -0,0 +1,309 @@
+use crate::{
+    terminal::{Point, Rect},
+    unicode,
+};
+use std::{
+    io::{self, Write},
+    mem,
+    rc::Rc,
+};
+use termion::{color, style};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Color {
+    Black = 0,
+    Red = 1,
+    Green = 2,
+    Yellow = 3,
+    Blue = 4,
+    Magenta = 5,
+    Cyan = 6,
+    White = 7,
+    LightBlack = 8,
+}
+
+impl Color {
+    fn ansi(self) -> color::AnsiValue {
+        color::AnsiValue(self as u8)
+    }
+}
+
+/// How a cell is drawn. Layers of styling are combined by setting fields on
+/// top of each other.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub underline: bool,
+    pub invert: bool,
+}
+
+impl Style {
+    pub const INVERT: Style = Style {
+        fg: None,
+        bg: None,
+        bold: false,
+        underline: false,
+        invert: true,
+    };
+
+    pub fn fg(color: Color) -> Self {
+        Style {
+            fg: Some(color),
+            ..Style::default()
+        }
+    }
+
+    fn write(self, output: &mut impl Write) -> io::Result<()> {
+        write!(output, "{}", style::Reset)?;
+        if let Some(fg) = self.fg {
+            write!(output, "{}", color::Fg(fg.ansi()))?;
+        }
+        if let Some(bg) = self.bg {
+            write!(output, "{}", color::Bg(bg.ansi()))?;
+        }
+        if self.bold {
+            write!(output, "{}", style::Bold)?;
+        }
+        if self.underline {
+            write!(output, "{}", style::Underline)?;
+        }
+        if self.invert {
+            write!(output, "{}", style::Invert)?;
+        }
+        Ok(())
+    }
+}
+
+/// Stands in the cell covered by the right half of a wide char.
+const WIDE_TAIL: char = '\0';
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Cell {
+    c: char,
+    /// Zero-width chars drawn on top of `c`, such as combining accents.
+    marks: Option<Box<str>>,
+    style: Style,
+    /// The OSC 8 hyperlink target the cell is part of.
+    link: Option<Rc<str>>,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            c: ' ',
+            marks: None,
+            style: Style::default(),
+            link: None,
+        }
+    }
+}
+
+/// A grid of cells drawn into each frame, written to the terminal as the
+/// difference from the previous frame.
+#[derive(Debug, Default)]
+pub struct Screen {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+    /// What the terminal shows, empty when unknown.
+    shown: Vec<Cell>,
+    /// Rows drawn over by popups in this frame and the last one; what is
+    /// below them has to be drawn again.
+    overlay: Vec<u16>,
+    last_overlay: Vec<u16>,
+}
+
+impl Screen {
+    /// Starts a frame, clearing the grid if the terminal was resized.
+    /// Returns whether it was.
+    pub fn begin(&mut self, (width, height): (u16, u16)) -> bool {
+        self.last_overlay = mem::take(&mut self.overlay);
+        if (self.width, self.height) == (width, height) {
+            return false;
+        }
+        self.width = width;
+        self.height = height;
+        self.cells = vec![Cell::default(); width as usize * height as usize];
+        self.invalidate();
+        true
+    }
+
+    /// Forgets what the terminal shows, so that the next frame is written in
+    /// full.
+    pub fn invalidate(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Marks row `y` as drawn over by a popup.
+    pub fn overlay(&mut self, y: u16) {
+        self.overlay.push(y);
+    }
+
+    /// Whether the cells of `region` still hold what was drawn there, so it
+    /// can be left alone.
+    pub fn intact(&self, region: Rect) -> bool {
+        !self
+            .last_overlay
+            .iter()
+            .any(|y| region.range_y().contains(y))
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        let (x, y) = (point.x.checked_sub(1)?, point.y.checked_sub(1)?);
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width as usize + x as usize)
+    }
+
+    /// Sets the cell at one-based `point`, ignoring points off the screen,
+    /// and returns how many columns `c` took. Wide chars also take the next
+    /// cell, and zero-width ones are added to the cell before.
+    pub fn set(&mut self, point: Point, c: char, style: Style) -> u16 {
+        self.set_link(point, c, style, None)
+    }
+
+    pub fn set_link(&mut self, point: Point, c: char, style: Style, link: Option<Rc<str>>) -> u16 {
+        let width = unicode::width(c) as u16;
+        if width == 0 {
+            let before = self.index(Point {
+                x: point.x.saturating_sub(1),
+                y: point.y,
+            });
+            if let Some(mut i) = before {
+                if self.cells[i].c == WIDE_TAIL {
+                    i -= 1;
+                }
+                let cell = &mut self.cells[i];
+                let mut marks = cell.marks.take().map_or_else(String::new, String::from);
+                marks.push(c);
+                cell.marks = Some(marks.into());
+            }
+            return 0;
+        }
+        let i = match self.index(point) {
+            Some(i) => i,
+            None => return width,
+        };
+        self.clear_wide(i);
+        let tail = Point {
+            x: point.x + 1,
+            y: point.y,
+        };
+        match self.index(tail).filter(|_| width == 2) {
+            Some(j) => {
+                self.clear_wide(j);
+                self.cells[j] = Cell {
+                    c: WIDE_TAIL,
+                    marks: None,
+                    style,
+                    link: link.clone(),
+                };
+            }
+            // A wide char that doesn't fit is left out.
+            None if width == 2 => {
+                self.cells[i] = Cell {
+                    style,
+                    ..Cell::default()
+                };
+                return width;
+            }
+            None => {}
+        }
+        self.cells[i] = Cell {
+            c,
+            marks: None,
+            style,
+            link,
+        };
+        width
+    }
+
+    /// Blanks the other half of a wide char that cell `i` is part of, before
+    /// `i` is overwritten.
+    fn clear_wide(&mut self, i: usize) {
+        let row = i - i % self.width as usize;
+        if self.cells[i].c == WIDE_TAIL && i > row {
+            self.cells[i - 1].c = ' ';
+            self.cells[i - 1].marks = None;
+        } else if self
+            .cells
+            .get(i + 1)
+            .is_some_and(|next| next.c == WIDE_TAIL)
+        {
+            self.cells[i + 1].c = ' ';
+        }
+    }
+
+    /// Draws `text` from `start`, stopping at column `end`, and returns the
+    /// column after it.
+    pub fn print(&mut self, start: Point, end: u16, text: &str, style: Style) -> u16 {
+        let mut x = start.x;
+        for c in text.chars() {
+            if x + (unicode::width(c) as u16).max(1) - 1 > end {
+                break;
+            }
+            x += self.set(Point { x, y: start.y }, c, style);
+        }
+        x
+    }
+
+    /// Blanks `region` in `style`.
+    pub fn fill(&mut self, region: Rect, style: Style) {
+        for y in region.range_y() {
+            for x in region.range_x() {
+                self.set(Point { x, y }, ' ', style);
+            }
+        }
+    }
+
+    /// Writes the cells that changed since the last flush.
+    pub fn flush(&mut self, output: &mut impl Write) -> io::Result<()> {
+        let mut at = None;
+        let mut style = None;
+        let mut link = None;
+        for (i, cell) in self.cells.iter().enumerate() {
+            if self.shown.get(i) == Some(cell) || cell.c == WIDE_TAIL {
+                continue;
+            }
+            let point = Point {
+                x: (i % self.width as usize) as u16 + 1,
+                y: (i / self.width as usize) as u16 + 1,
+            };
+            if at != Some(point) {
+                write!(output, "{}", point.goto())?;
+            }
+            if style != Some(cell.style) {
+                cell.style.write(output)?;
+                style = Some(cell.style);
+            }
+            if cell.link != link {
+                write!(
+                    output,
+                    "\x1b]8;;{}\x1b\\",
+                    cell.link.as_deref().unwrap_or("")
+                )?;
+                link = cell.link.clone();
+            }
+            write!(output, "{}{}", cell.c, cell.marks.as_deref().unwrap_or(""))?;
+            let wide = self
+                .cells
+                .get(i + 1)
+                .is_some_and(|next| next.c == WIDE_TAIL);
+            at = Some(Point {
+                x: point.x + 1 + u16::from(wide),
+                y: point.y,
+            });
+        }
+        if link.is_some() {
+            write!(output, "\x1b]8;;\x1b\\")?;
+        }
+        if style.is_some() {
+            write!(output, "{}", style::Reset)?;
+        }
+        self.shown.clone_from(&self.cells);
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    regex::{Captures, Regex},
+    Error,
+};
+use anyhow::format_err;
+use fehler::{throw, throws};
+use ropey::Rope;
+use std::ops::Range;
+
+pub struct Search {
+    pub pattern: String,
+    pub regex: Regex,
+    /// Character ranges matches must fall within, from the `s` flag.
+    pub bounds: Option<Vec<Range<usize>>>,
+}
+
+impl Search {
+    /// Flags: `i` ignores case, `w` matches whole words only and `s` keeps
+    /// only matches inside `selections`.
+    #[throws]
+    pub fn new(pattern: &str, flags: &str, selections: Vec<Range<usize>>) -> Self {
+        let mut case_insensitive = false;
+        let mut word = false;
+        let mut within = false;
+        for flag in flags.chars() {
+            match flag {
+                'i' => case_insensitive = true,
+                'w' => word = true,
+                's' => within = true,
+                _ => throw!(format_err!("unknown search flag '{}'", flag)),
+            }
+        }
+        let regex = if word {
+            Regex::with_case(&format!("\\b(?:{})\\b", pattern), case_insensitive)?
+        } else {
+            Regex::with_case(pattern, case_insensitive)?
+        };
+        Self {
+            pattern: pattern.to_owned(),
+            regex,
+            bounds: if within { Some(selections) } else { None },
+        }
+    }
+
+    /// Returns the captures of every match in `text`, the contents of `rope`.
+    /// With bounds, each bound is searched on its own, so a match crossing
+    /// into it from outside doesn't hide the ones within.
+    pub fn captures(&self, text: &str, rope: &Rope) -> Vec<Captures> {
+        let bounds = match &self.bounds {
+            Some(bounds) => bounds,
+            None => return self.regex.find_iter(text).collect(),
+        };
+        bounds
+            .iter()
+            .flat_map(|range| {
+                let start = rope.char_to_byte(range.start);
+                let end = rope.char_to_byte(range.end);
+                self.regex
+                    .find_iter(&text[start..end])
+                    .map(move |captures| captures.shifted(start))
+            })
+            .collect()
+    }
+
+    /// Returns the character range of every match in `rope`.
+    pub fn ranges(&self, rope: &Rope) -> Vec<Range<usize>> {
+        let text = rope.to_string();
+        self.captures(&text, rope)
+            .into_iter()
+            .map(|captures| {
+                let range = captures.range();
+                rope.byte_to_char(range.start)..rope.byte_to_char(range.end)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn searches_within_bounds() {
+        let rope = Rope::from_str("abc");
+        let search = Search::new("ab|b", "s", std::iter::once(1..3).collect()).unwrap();
+        assert_eq!(search.ranges(&rope), vec![1..2]);
+        let search = Search::new("ab|b", "", std::iter::once(1..3).collect()).unwrap();
+        assert_eq!(search.ranges(&rope), vec![0..2]);
+    }
+
+    #[test]
+    fn shifts_groups_into_place() {
+        let text = "x = 1; y = 22";
+        let rope = Rope::from_str(text);
+        let search = Search::new("(\\w) = (\\d+)", "s", std::iter::once(7..13).collect()).unwrap();
+        let captures = search.captures(text, &rope);
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures[0].get(1), Some(7..8));
+        assert_eq!(captures[0].get(2), Some(11..13));
+        assert!(Search::new("a", "q", Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use std::ops::Range;
+
+pub struct Expansion {
+    pub text: String,
+    /// Tab-stops in jump order, each with the ranges of all its mirrors.
+    pub fields: Vec<Vec<Range<usize>>>,
+}
+
+pub struct Session {
+    pub fields: Vec<Vec<Range<usize>>>,
+    pub current: Option<usize>,
+    pub replace: bool,
+}
+
+/// Expands a snippet template for insertion on a line indented with `indent`.
+///
+/// Templates support `$1`, `${1:placeholder}` and `$0` for the final cursor
+/// position; a backslash escapes the next character.
+pub fn expand(template: &str, indent: &str) -> Expansion {
+    let mut text = String::new();
+    let mut len = 0;
+    let mut stops: Vec<(usize, Range<usize>)> = Vec::new();
+    let mut placeholder: Option<(usize, usize)> = None;
+    let mut chars = template.chars().peekable();
+    let push = |text: &mut String, len: &mut usize, c: char| {
+        text.push(c);
+        *len += 1;
+        if c == '\n' {
+            text.push_str(indent);
+            *len += indent.chars().count();
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    push(&mut text, &mut len, c);
+                }
+            }
+            '}' if placeholder.is_some() => {
+                let (number, start) = placeholder.take().unwrap();
+                stops.push((number, start..len));
+            }
+            '$' => {
+                let braced = chars.peek() == Some(&'{');
+                if braced {
+                    chars.next();
+                }
+                let mut digits = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    digits.push(c);
+                    chars.next();
+                }
+                let number = match digits.parse() {
+                    Ok(number) => number,
+                    Err(_) => {
+                        push(&mut text, &mut len, '$');
+                        if braced {
+                            push(&mut text, &mut len, '{');
+                        }
+                        continue;
+                    }
+                };
+                if !braced {
+                    stops.push((number, len..len));
+                } else if chars.peek() == Some(&':') && placeholder.is_none() {
+                    chars.next();
+                    placeholder = Some((number, len));
+                } else {
+                    if chars.peek() == Some(&'}') {
+                        chars.next();
+                    }
+                    stops.push((number, len..len));
+                }
+            }
+            c => push(&mut text, &mut len, c),
+        }
+    }
+    if let Some((number, start)) = placeholder {
+        stops.push((number, start..len));
+    }
+    stops.sort_by_key(|(number, range)| (*number == 0, *number, range.start));
+    let mut fields: Vec<Vec<Range<usize>>> = Vec::new();
+    let mut last = None;
+    for (number, range) in stops {
+        if last == Some(number) {
+            fields.last_mut().unwrap().push(range);
+        } else {
+            fields.push(vec![range]);
+            last = Some(number);
+        }
+    }
+    Expansion { text, fields }
+}
+
+impl Session {
+    pub fn shift(&mut self, range: &Range<usize>, inserted: usize) {
+        let shift = |offset: usize, sticky: bool| {
+            if offset < range.start || (sticky && offset == range.start) {
+                offset
+            } else if offset >= range.end {
+                offset - range.len() + inserted
+            } else {
+                range.start
+            }
+        };
+        for field in self.fields.iter_mut().flatten() {
+            *field = shift(field.start, true)..shift(field.end, false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_fields() {
+        let expansion = expand("for ${1:i} in $2 {\n\t$0\n}", "  ");
+        assert_eq!(expansion.text, "for i in  {\n  \t\n  }");
+        assert_eq!(expansion.fields, vec![vec![4..5], vec![9..9], vec![15..15]]);
+        let expansion = expand("$1 = ${1};", "");
+        assert_eq!(expansion.text, " = ;");
+        assert_eq!(expansion.fields, vec![vec![0..0, 3..3]]);
+    }
+
+    #[test]
+    fn keeps_malformed_text() {
+        let expansion = expand("\\$1 costs $x and ${y", "");
+        assert_eq!(expansion.text, "$1 costs $x and ${y");
+        assert!(expansion.fields.is_empty());
+        let expansion = expand("${1:open", "");
+        assert_eq!(expansion.text, "open");
+        assert_eq!(expansion.fields, vec![vec![0..4]]);
+        assert_eq!(expand("end\\", "").text, "end");
+    }
+
+    #[test]
+    fn shifts_fields_with_edits() {
+        let mut session = Session {
+            fields: vec![vec![2..4], vec![6..6]],
+            current: Some(0),
+            replace: false,
+        };
+        session.shift(&(3..4), 2);
+        assert_eq!(session.fields, vec![vec![2..5], vec![7..7]]);
+        session.shift(&(7..7), 1);
+        assert_eq!(session.fields, vec![vec![2..5], vec![7..8]]);
+        session.shift(&(0..3), 0);
+        assert_eq!(session.fields, vec![vec![0..2], vec![4..5]]);
+    }
+}
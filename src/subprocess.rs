@@ -0,0 +1,47 @@
+use crate::Error;
+use anyhow::{format_err, Context as _};
+use fehler::{throw, throws};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+
+/// Runs `command` with `sh -c`, writing `input` to its stdin, and returns its
+/// stdout. Fails with its stderr if it exits unsuccessfully.
+#[throws]
+pub fn run(command: &str, input: &str) -> String {
+    run_with_args(command, &[], input)?
+}
+
+/// Like `run`, with `args` as the command's positional parameters.
+#[throws]
+pub fn run_with_args(command: &str, args: &[&str], input: &str) -> String {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .arg("sh")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run '{}'", command))?;
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_owned();
+    // Written from another thread so a command that fills its stdout before
+    // reading all of its input can't deadlock us.
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    // Commands are free to exit without reading their input.
+    let _ = writer.join();
+    if !output.status.success() {
+        throw!(format_err!(
+            "'{}' failed: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("'{}' printed invalid UTF-8", command))?
+}
@@ -0,0 +1,101 @@
+use crate::regex::Captures;
+
+/// Expands `$N`, `\N` and `&` references to capture groups in `replacement`.
+pub fn expand(replacement: &str, captures: &Captures, text: &str) -> String {
+    let group = |n: usize| captures.get(n).map_or("", |range| &text[range]);
+    let mut expanded = String::new();
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().copied()) {
+            ('$', Some(d)) | ('\\', Some(d)) if d.is_ascii_digit() => {
+                chars.next();
+                expanded.push_str(group(d.to_digit(10).unwrap() as usize));
+            }
+            ('\\', Some('n')) => {
+                chars.next();
+                expanded.push('\n');
+            }
+            ('\\', Some('t')) => {
+                chars.next();
+                expanded.push('\t');
+            }
+            ('\\', Some(c)) => {
+                chars.next();
+                expanded.push(c);
+            }
+            ('&', _) => expanded.push_str(group(0)),
+            (c, _) => expanded.push(c),
+        }
+    }
+    expanded
+}
+
+/// Applies the case pattern of `matched` (all lowercase, all uppercase or
+/// capitalized) to `replacement`, leaving it untouched for mixed case.
+pub fn preserve_case(matched: &str, replacement: &str) -> String {
+    let letters = matched
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<_>>();
+    if letters.is_empty() {
+        return replacement.to_owned();
+    }
+    if letters.iter().all(|c| c.is_uppercase()) && letters.len() > 1 {
+        replacement.to_uppercase()
+    } else if letters.iter().all(|c| c.is_lowercase()) {
+        replacement.to_lowercase()
+    } else if letters[0].is_uppercase() && letters[1..].iter().all(|c| c.is_lowercase()) {
+        let mut chars = replacement.chars();
+        chars
+            .next()
+            .map(|first| {
+                first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        replacement.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex::Regex;
+
+    fn substitute(pattern: &str, text: &str, replacement: &str) -> String {
+        let captures = Regex::new(pattern).unwrap().find_at(text, 0).unwrap();
+        expand(replacement, &captures, text)
+    }
+
+    #[test]
+    fn expands_references() {
+        assert_eq!(
+            substitute(r"(\w+) (\w+)", "hello world", r"$2 \1"),
+            "world hello"
+        );
+        assert_eq!(substitute("b", "abc", "[&]"), "[b]");
+        assert_eq!(substitute("(b)?c", "c", "<$1>"), "<>");
+        assert_eq!(substitute("b", "abc", "$9 $"), " $");
+    }
+
+    #[test]
+    fn expands_escapes() {
+        assert_eq!(substitute("b", "abc", r"\n\t"), "\n\t");
+        assert_eq!(substitute("b", "abc", r"\&\$1\\"), "&$1\\");
+    }
+
+    #[test]
+    fn preserves_case() {
+        assert_eq!(preserve_case("foo", "Bar"), "bar");
+        assert_eq!(preserve_case("FOO", "bar"), "BAR");
+        assert_eq!(preserve_case("Foo", "bAR"), "Bar");
+        assert_eq!(preserve_case("A", "bar"), "Bar");
+        assert_eq!(preserve_case("fOo", "baR"), "baR");
+        assert_eq!(preserve_case("12", "baR"), "baR");
+        assert_eq!(preserve_case("FOO_1", "bar"), "BAR");
+        assert_eq!(preserve_case("Foo", ""), "");
+    }
+}
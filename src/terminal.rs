@@ -1,7 +1,7 @@
 use std::ops::RangeInclusive;
 use termion::cursor;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Point {
     pub x: u16,
     pub y: u16,
@@ -35,4 +35,118 @@ impl Rect {
     pub fn range_y(self) -> RangeInclusive<u16> {
         self.start.y..=self.end.y
     }
+
+    pub fn contains(self, point: Point) -> bool {
+        self.range_x().contains(&point.x) && self.range_y().contains(&point.y)
+    }
+
+    /// The number of columns, counting both edges.
+    pub fn columns(self) -> u16 {
+        self.width() + 1
+    }
+
+    /// The number of rows, counting both edges.
+    pub fn rows(self) -> u16 {
+        self.height() + 1
+    }
+
+    /// Splits off at least one row from the top, returning it and the rows
+    /// below, if any are left.
+    pub fn split_horizontal(self, size: Size) -> (Rect, Option<Rect>) {
+        let rows = size.of(self.rows());
+        let top = Rect {
+            start: self.start,
+            end: Point {
+                x: self.end.x,
+                y: self.start.y + rows - 1,
+            },
+        };
+        let bottom = Some(Rect {
+            start: Point {
+                x: self.start.x,
+                y: top.end.y + 1,
+            },
+            end: self.end,
+        })
+        .filter(|_| rows < self.rows());
+        (top, bottom)
+    }
+
+    /// Splits off at least one column from the left, returning it and the
+    /// columns to the right, if any are left.
+    pub fn split_vertical(self, size: Size) -> (Rect, Option<Rect>) {
+        let columns = size.of(self.columns());
+        let left = Rect {
+            start: self.start,
+            end: Point {
+                x: self.start.x + columns - 1,
+                y: self.end.y,
+            },
+        };
+        let right = Some(Rect {
+            start: Point {
+                x: left.end.x + 1,
+                y: self.start.y,
+            },
+            end: self.end,
+        })
+        .filter(|_| columns < self.columns());
+        (left, right)
+    }
+
+    /// Shrinks the rect by `margin` cells on every side.
+    pub fn inset(self, margin: u16) -> Option<Rect> {
+        if self.columns() <= margin * 2 || self.rows() <= margin * 2 {
+            return None;
+        }
+        Some(Rect {
+            start: Point {
+                x: self.start.x + margin,
+                y: self.start.y + margin,
+            },
+            end: Point {
+                x: self.end.x - margin,
+                y: self.end.y - margin,
+            },
+        })
+    }
+
+    pub fn intersection(self, other: Rect) -> Option<Rect> {
+        let start = Point {
+            x: self.start.x.max(other.start.x),
+            y: self.start.y.max(other.start.y),
+        };
+        let end = Point {
+            x: self.end.x.min(other.end.x),
+            y: self.end.y.min(other.end.y),
+        };
+        Some(Rect { start, end }).filter(|_| start.x <= end.x && start.y <= end.y)
+    }
+
+    /// Moves `point` to the nearest cell inside the rect.
+    pub fn clamp(self, point: Point) -> Point {
+        Point {
+            x: point.x.clamp(self.start.x, self.end.x),
+            y: point.y.clamp(self.start.y, self.end.y),
+        }
+    }
+}
+
+/// How much of a `Rect` to split off along one axis.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Size {
+    /// A number of cells.
+    Fixed(u16),
+    /// A percentage of the cells, rounded down.
+    Percent(u16),
+}
+
+impl Size {
+    fn of(self, total: u16) -> u16 {
+        let cells = match self {
+            Size::Fixed(cells) => cells,
+            Size::Percent(percent) => (u32::from(total) * u32::from(percent.min(100)) / 100) as u16,
+        };
+        cells.clamp(1, total)
+    }
 }
@@ -0,0 +1,1672 @@
+use crate::Error;
+use anyhow::format_err;
+use fehler::{throw, throws};
+use std::{char, cmp::Ordering};
+
+pub fn digraph(first: char, second: char) -> Option<char> {
+    DIGRAPHS
+        .iter()
+        .find(|d| d.0 == first && d.1 == second)
+        .map(|d| d.2)
+}
+
+pub fn codepoint(hex: &str) -> Option<char> {
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+}
+
+pub fn matching(query: &str) -> impl Iterator<Item = (&'static str, char)> {
+    let query = query.to_lowercase();
+    NAMES
+        .iter()
+        .copied()
+        .filter(move |(name, _)| name.contains(&query))
+}
+
+/// Finds the character `query` names or gives the codepoint of, or `None`
+/// if several names match it, for the caller to pick from.
+#[throws]
+pub fn lookup(query: &str) -> Option<char> {
+    for prefix in &["U+", "u+", "0x"] {
+        if let Some(hex) = query.strip_prefix(prefix) {
+            let c = codepoint(hex).ok_or_else(|| format_err!("invalid codepoint '{}'", query))?;
+            return Some(c);
+        }
+    }
+    let lowercase = query.to_lowercase();
+    if let Some((_, c)) = NAMES.iter().find(|(name, _)| *name == lowercase) {
+        return Some(*c);
+    }
+    let mut candidates = matching(query);
+    match (candidates.next(), candidates.next()) {
+        (None, _) => throw!(format_err!("no character named '{}'", query)),
+        (Some((_, c)), None) => Some(c),
+        (Some(_), Some(_)) => None,
+    }
+}
+
+/// Every named character, as the character followed by its name.
+pub fn names() -> impl Iterator<Item = String> {
+    NAMES.iter().map(|(name, c)| format!("{} {}", c, name))
+}
+
+pub fn width(c: char) -> usize {
+    if in_table(ZERO_WIDTH, c) {
+        0
+    } else if in_table(WIDE, c) {
+        2
+    } else {
+        1
+    }
+}
+
+pub fn str_width(s: &str) -> usize {
+    s.chars().map(width).sum()
+}
+
+/// Whether `c` continues the grapheme cluster `prev` is part of, rather
+/// than starting one: combining marks, variation selectors, emoji modifiers,
+/// chars after a zero width joiner, and the `\n` of `\r\n`. Pairs of
+/// regional indicators are left to the caller.
+pub fn extends(prev: char, c: char) -> bool {
+    (prev, c) == ('\r', '\n')
+        || prev == '\u{200d}'
+        || ('\u{1f3fb}'..='\u{1f3ff}').contains(&c)
+        || (width(c) == 0 && c != '\u{200b}')
+}
+
+pub fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+fn in_table(table: &[(char, char)], c: char) -> bool {
+    table
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                Ordering::Less
+            } else if start > c {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+const DIGRAPHS: &[(char, char, char)] = &[
+    ('!', '=', '≠'),
+    ('!', 'I', '¡'),
+    ('"', '6', '“'),
+    ('"', '9', '”'),
+    ('\'', '6', '‘'),
+    ('\'', '9', '’'),
+    ('(', '-', '∈'),
+    ('(', 'C', '⊂'),
+    ('(', 'U', '∩'),
+    ('(', '_', '⊆'),
+    (')', 'C', '⊃'),
+    (')', 'U', '∪'),
+    (')', '_', '⊇'),
+    ('*', '1', '☆'),
+    ('*', '2', '★'),
+    ('*', 'P', '∏'),
+    ('*', 'X', '×'),
+    ('+', '-', '±'),
+    ('+', 'Z', '∑'),
+    (',', '.', '…'),
+    ('-', '!', '↑'),
+    ('-', ')', '∋'),
+    ('-', ':', '÷'),
+    ('-', '>', '→'),
+    ('-', 'M', '—'),
+    ('-', 'N', '–'),
+    ('-', 'v', '↓'),
+    ('.', 'M', '·'),
+    ('.', 'P', '⋅'),
+    ('0', '0', '∞'),
+    ('0', 'M', '●'),
+    ('0', 'm', '○'),
+    ('1', '2', '½'),
+    ('1', '4', '¼'),
+    ('1', 'S', '¹'),
+    ('2', 'S', '²'),
+    ('3', '4', '¾'),
+    ('3', 'S', '³'),
+    ('<', '-', '←'),
+    ('<', '<', '«'),
+    ('<', '=', '⇐'),
+    ('<', '>', '↔'),
+    ('=', '3', '≡'),
+    ('=', '<', '≤'),
+    ('=', '=', '⇔'),
+    ('=', '>', '⇒'),
+    ('>', '=', '≥'),
+    ('>', '>', '»'),
+    ('?', '2', '≈'),
+    ('?', '=', '≅'),
+    ('?', 'I', '¿'),
+    ('A', '!', 'À'),
+    ('A', '\'', 'Á'),
+    ('A', '(', 'Ă'),
+    ('A', '*', 'Α'),
+    ('A', '-', 'Ā'),
+    ('A', '.', 'Ȧ'),
+    ('A', '0', 'Å'),
+    ('A', ':', 'Ä'),
+    ('A', ';', 'Ą'),
+    ('A', '<', 'Ǎ'),
+    ('A', '>', 'Â'),
+    ('A', '?', 'Ã'),
+    ('A', 'A', 'Å'),
+    ('A', 'E', 'Æ'),
+    ('A', 'N', '∧'),
+    ('B', '*', 'Β'),
+    ('C', '\'', 'Ć'),
+    ('C', '*', 'Ξ'),
+    ('C', ',', 'Ç'),
+    ('C', '.', 'Ċ'),
+    ('C', '<', 'Č'),
+    ('C', '>', 'Ĉ'),
+    ('C', 'o', '©'),
+    ('C', 't', '¢'),
+    ('D', '*', 'Δ'),
+    ('D', '-', 'Đ'),
+    ('D', 'G', '°'),
+    ('D', 'b', '◆'),
+    ('E', '!', 'È'),
+    ('E', '\'', 'É'),
+    ('E', '(', 'Ĕ'),
+    ('E', '*', 'Ε'),
+    ('E', ',', 'Ȩ'),
+    ('E', '-', 'Ē'),
+    ('E', '.', 'Ė'),
+    ('E', ':', 'Ë'),
+    ('E', ';', 'Ę'),
+    ('E', '<', 'Ě'),
+    ('E', '>', 'Ê'),
+    ('E', '?', 'Ẽ'),
+    ('E', 'u', '€'),
+    ('F', '*', 'Φ'),
+    ('F', 'A', '∀'),
+    ('G', '\'', 'Ǵ'),
+    ('G', '(', 'Ğ'),
+    ('G', '*', 'Γ'),
+    ('G', ',', 'Ģ'),
+    ('G', '-', 'Ḡ'),
+    ('G', '.', 'Ġ'),
+    ('G', '<', 'Ǧ'),
+    ('G', '>', 'Ĝ'),
+    ('H', '*', 'Θ'),
+    ('I', '!', 'Ì'),
+    ('I', '\'', 'Í'),
+    ('I', '(', 'Ĭ'),
+    ('I', '*', 'Ι'),
+    ('I', '-', 'Ī'),
+    ('I', '.', 'İ'),
+    ('I', ':', 'Ï'),
+    ('I', ';', 'Į'),
+    ('I', '<', 'Ǐ'),
+    ('I', '>', 'Î'),
+    ('I', '?', 'Ĩ'),
+    ('I', 'n', '∫'),
+    ('K', '*', 'Κ'),
+    ('L', '\'', 'Ĺ'),
+    ('L', '*', 'Λ'),
+    ('L', ',', 'Ļ'),
+    ('L', '<', 'Ľ'),
+    ('M', '*', 'Μ'),
+    ('M', 'y', 'µ'),
+    ('N', '!', 'Ǹ'),
+    ('N', '\'', 'Ń'),
+    ('N', '*', 'Ν'),
+    ('N', ',', 'Ņ'),
+    ('N', '.', 'Ṅ'),
+    ('N', '<', 'Ň'),
+    ('N', '?', 'Ñ'),
+    ('N', 'B', '∇'),
+    ('N', 'O', '¬'),
+    ('N', 'S', '\u{a0}'),
+    ('O', '!', 'Ò'),
+    ('O', '\'', 'Ó'),
+    ('O', '(', 'Ŏ'),
+    ('O', '*', 'Ο'),
+    ('O', '-', 'Ō'),
+    ('O', '.', 'Ȯ'),
+    ('O', '/', 'Ø'),
+    ('O', ':', 'Ö'),
+    ('O', ';', 'Ǫ'),
+    ('O', '<', 'Ǒ'),
+    ('O', '>', 'Ô'),
+    ('O', '?', 'Õ'),
+    ('O', 'E', 'Œ'),
+    ('O', 'K', '✓'),
+    ('O', 'R', '∨'),
+    ('P', '*', 'Π'),
+    ('P', 'I', '¶'),
+    ('P', 'L', '◀'),
+    ('P', 'R', '▶'),
+    ('P', 'd', '£'),
+    ('Q', '*', 'Ψ'),
+    ('R', '\'', 'Ŕ'),
+    ('R', '*', 'Ρ'),
+    ('R', ',', 'Ŗ'),
+    ('R', '.', 'Ṙ'),
+    ('R', '<', 'Ř'),
+    ('R', 'T', '√'),
+    ('R', 'g', '®'),
+    ('S', '\'', 'Ś'),
+    ('S', '*', 'Σ'),
+    ('S', ',', 'Ş'),
+    ('S', '.', 'Ṡ'),
+    ('S', '<', 'Š'),
+    ('S', '>', 'Ŝ'),
+    ('S', 'E', '§'),
+    ('S', 'b', '∙'),
+    ('T', '*', 'Τ'),
+    ('T', 'E', '∃'),
+    ('T', 'H', 'Þ'),
+    ('T', 'M', '™'),
+    ('T', 'R', '▲'),
+    ('T', 'r', '▼'),
+    ('U', '!', 'Ù'),
+    ('U', '\'', 'Ú'),
+    ('U', '(', 'Ŭ'),
+    ('U', '*', 'Υ'),
+    ('U', '-', 'Ū'),
+    ('U', '0', 'Ů'),
+    ('U', ':', 'Ü'),
+    ('U', ';', 'Ų'),
+    ('U', '<', 'Ǔ'),
+    ('U', '>', 'Û'),
+    ('U', '?', 'Ũ'),
+    ('U', 'D', '↕'),
+    ('W', '*', 'Ω'),
+    ('X', '*', 'Χ'),
+    ('X', 'X', '✗'),
+    ('Y', '!', 'Ỳ'),
+    ('Y', '\'', 'Ý'),
+    ('Y', '*', 'Η'),
+    ('Y', '-', 'Ȳ'),
+    ('Y', '.', 'Ẏ'),
+    ('Y', ':', 'Ÿ'),
+    ('Y', '>', 'Ŷ'),
+    ('Y', '?', 'Ỹ'),
+    ('Y', 'e', '¥'),
+    ('Z', '\'', 'Ź'),
+    ('Z', '*', 'Ζ'),
+    ('Z', '.', 'Ż'),
+    ('Z', '<', 'Ž'),
+    ('Z', '>', 'Ẑ'),
+    ('a', '!', 'à'),
+    ('a', '\'', 'á'),
+    ('a', '(', 'ă'),
+    ('a', '*', 'α'),
+    ('a', '-', 'ā'),
+    ('a', '.', 'ȧ'),
+    ('a', '0', 'å'),
+    ('a', ':', 'ä'),
+    ('a', ';', 'ą'),
+    ('a', '<', 'ǎ'),
+    ('a', '>', 'â'),
+    ('a', '?', 'ã'),
+    ('a', 'a', 'å'),
+    ('a', 'e', 'æ'),
+    ('b', '*', 'β'),
+    ('c', '\'', 'ć'),
+    ('c', '*', 'ξ'),
+    ('c', ',', 'ç'),
+    ('c', '.', 'ċ'),
+    ('c', '<', 'č'),
+    ('c', '>', 'ĉ'),
+    ('d', '*', 'δ'),
+    ('d', '-', 'đ'),
+    ('d', 'P', '∂'),
+    ('e', '!', 'è'),
+    ('e', '\'', 'é'),
+    ('e', '(', 'ĕ'),
+    ('e', '*', 'ε'),
+    ('e', ',', 'ȩ'),
+    ('e', '-', 'ē'),
+    ('e', '.', 'ė'),
+    ('e', ':', 'ë'),
+    ('e', ';', 'ę'),
+    ('e', '<', 'ě'),
+    ('e', '>', 'ê'),
+    ('e', '?', 'ẽ'),
+    ('f', '*', 'φ'),
+    ('g', '\'', 'ǵ'),
+    ('g', '(', 'ğ'),
+    ('g', '*', 'γ'),
+    ('g', ',', 'ģ'),
+    ('g', '-', 'ḡ'),
+    ('g', '.', 'ġ'),
+    ('g', '<', 'ǧ'),
+    ('g', '>', 'ĝ'),
+    ('h', '*', 'θ'),
+    ('i', '!', 'ì'),
+    ('i', '\'', 'í'),
+    ('i', '(', 'ĭ'),
+    ('i', '*', 'ι'),
+    ('i', '-', 'ī'),
+    ('i', ':', 'ï'),
+    ('i', ';', 'į'),
+    ('i', '<', 'ǐ'),
+    ('i', '>', 'î'),
+    ('i', '?', 'ĩ'),
+    ('k', '*', 'κ'),
+    ('l', '\'', 'ĺ'),
+    ('l', '*', 'λ'),
+    ('l', ',', 'ļ'),
+    ('l', '<', 'ľ'),
+    ('m', '*', 'μ'),
+    ('n', '!', 'ǹ'),
+    ('n', '\'', 'ń'),
+    ('n', '*', 'ν'),
+    ('n', ',', 'ņ'),
+    ('n', '.', 'ṅ'),
+    ('n', '<', 'ň'),
+    ('n', '?', 'ñ'),
+    ('n', 'S', 'ⁿ'),
+    ('o', '!', 'ò'),
+    ('o', '\'', 'ó'),
+    ('o', '(', 'ŏ'),
+    ('o', '*', 'ο'),
+    ('o', '-', 'ō'),
+    ('o', '.', 'ȯ'),
+    ('o', '/', 'ø'),
+    ('o', ':', 'ö'),
+    ('o', ';', 'ǫ'),
+    ('o', '<', 'ǒ'),
+    ('o', '>', 'ô'),
+    ('o', '?', 'õ'),
+    ('o', 'e', 'œ'),
+    ('p', '*', 'π'),
+    ('q', '*', 'ψ'),
+    ('r', '\'', 'ŕ'),
+    ('r', '*', 'ρ'),
+    ('r', ',', 'ŗ'),
+    ('r', '.', 'ṙ'),
+    ('r', '<', 'ř'),
+    ('s', '\'', 'ś'),
+    ('s', '*', 'σ'),
+    ('s', ',', 'ş'),
+    ('s', '.', 'ṡ'),
+    ('s', '<', 'š'),
+    ('s', '>', 'ŝ'),
+    ('s', 's', 'ß'),
+    ('t', '*', 'τ'),
+    ('t', 'h', 'þ'),
+    ('u', '!', 'ù'),
+    ('u', '\'', 'ú'),
+    ('u', '(', 'ŭ'),
+    ('u', '*', 'υ'),
+    ('u', '-', 'ū'),
+    ('u', '0', 'ů'),
+    ('u', ':', 'ü'),
+    ('u', ';', 'ų'),
+    ('u', '<', 'ǔ'),
+    ('u', '>', 'û'),
+    ('u', '?', 'ũ'),
+    ('w', '*', 'ω'),
+    ('x', '*', 'χ'),
+    ('y', '!', 'ỳ'),
+    ('y', '\'', 'ý'),
+    ('y', '*', 'η'),
+    ('y', '-', 'ȳ'),
+    ('y', '.', 'ẏ'),
+    ('y', '0', 'ẙ'),
+    ('y', ':', 'ÿ'),
+    ('y', '>', 'ŷ'),
+    ('y', '?', 'ỹ'),
+    ('z', '\'', 'ź'),
+    ('z', '*', 'ζ'),
+    ('z', '.', 'ż'),
+    ('z', '<', 'ž'),
+    ('z', '>', 'ẑ'),
+];
+
+const NAMES: &[(&str, char)] = &[
+    ("inverted exclamation mark", '¡'),
+    ("cent sign", '¢'),
+    ("pound sign", '£'),
+    ("currency sign", '¤'),
+    ("yen sign", '¥'),
+    ("broken bar", '¦'),
+    ("section sign", '§'),
+    ("diaeresis", '¨'),
+    ("copyright sign", '©'),
+    ("feminine ordinal indicator", 'ª'),
+    ("left-pointing double angle quotation mark", '«'),
+    ("not sign", '¬'),
+    ("soft hyphen", '\u{ad}'),
+    ("registered sign", '®'),
+    ("macron", '¯'),
+    ("degree sign", '°'),
+    ("plus-minus sign", '±'),
+    ("superscript two", '²'),
+    ("superscript three", '³'),
+    ("acute accent", '´'),
+    ("micro sign", 'µ'),
+    ("pilcrow sign", '¶'),
+    ("middle dot", '·'),
+    ("cedilla", '¸'),
+    ("superscript one", '¹'),
+    ("masculine ordinal indicator", 'º'),
+    ("right-pointing double angle quotation mark", '»'),
+    ("vulgar fraction one quarter", '¼'),
+    ("vulgar fraction one half", '½'),
+    ("vulgar fraction three quarters", '¾'),
+    ("inverted question mark", '¿'),
+    ("latin capital letter a with grave", 'À'),
+    ("latin capital letter a with acute", 'Á'),
+    ("latin capital letter a with circumflex", 'Â'),
+    ("latin capital letter a with tilde", 'Ã'),
+    ("latin capital letter a with diaeresis", 'Ä'),
+    ("latin capital letter a with ring above", 'Å'),
+    ("latin capital letter ae", 'Æ'),
+    ("latin capital letter c with cedilla", 'Ç'),
+    ("latin capital letter e with grave", 'È'),
+    ("latin capital letter e with acute", 'É'),
+    ("latin capital letter e with circumflex", 'Ê'),
+    ("latin capital letter e with diaeresis", 'Ë'),
+    ("latin capital letter i with grave", 'Ì'),
+    ("latin capital letter i with acute", 'Í'),
+    ("latin capital letter i with circumflex", 'Î'),
+    ("latin capital letter i with diaeresis", 'Ï'),
+    ("latin capital letter eth", 'Ð'),
+    ("latin capital letter n with tilde", 'Ñ'),
+    ("latin capital letter o with grave", 'Ò'),
+    ("latin capital letter o with acute", 'Ó'),
+    ("latin capital letter o with circumflex", 'Ô'),
+    ("latin capital letter o with tilde", 'Õ'),
+    ("latin capital letter o with diaeresis", 'Ö'),
+    ("multiplication sign", '×'),
+    ("latin capital letter o with stroke", 'Ø'),
+    ("latin capital letter u with grave", 'Ù'),
+    ("latin capital letter u with acute", 'Ú'),
+    ("latin capital letter u with circumflex", 'Û'),
+    ("latin capital letter u with diaeresis", 'Ü'),
+    ("latin capital letter y with acute", 'Ý'),
+    ("latin capital letter thorn", 'Þ'),
+    ("latin small letter sharp s", 'ß'),
+    ("latin small letter a with grave", 'à'),
+    ("latin small letter a with acute", 'á'),
+    ("latin small letter a with circumflex", 'â'),
+    ("latin small letter a with tilde", 'ã'),
+    ("latin small letter a with diaeresis", 'ä'),
+    ("latin small letter a with ring above", 'å'),
+    ("latin small letter ae", 'æ'),
+    ("latin small letter c with cedilla", 'ç'),
+    ("latin small letter e with grave", 'è'),
+    ("latin small letter e with acute", 'é'),
+    ("latin small letter e with circumflex", 'ê'),
+    ("latin small letter e with diaeresis", 'ë'),
+    ("latin small letter i with grave", 'ì'),
+    ("latin small letter i with acute", 'í'),
+    ("latin small letter i with circumflex", 'î'),
+    ("latin small letter i with diaeresis", 'ï'),
+    ("latin small letter eth", 'ð'),
+    ("latin small letter n with tilde", 'ñ'),
+    ("latin small letter o with grave", 'ò'),
+    ("latin small letter o with acute", 'ó'),
+    ("latin small letter o with circumflex", 'ô'),
+    ("latin small letter o with tilde", 'õ'),
+    ("latin small letter o with diaeresis", 'ö'),
+    ("division sign", '÷'),
+    ("latin small letter o with stroke", 'ø'),
+    ("latin small letter u with grave", 'ù'),
+    ("latin small letter u with acute", 'ú'),
+    ("latin small letter u with circumflex", 'û'),
+    ("latin small letter u with diaeresis", 'ü'),
+    ("latin small letter y with acute", 'ý'),
+    ("latin small letter thorn", 'þ'),
+    ("latin small letter y with diaeresis", 'ÿ'),
+    ("latin capital letter a with macron", 'Ā'),
+    ("latin small letter a with macron", 'ā'),
+    ("latin capital letter a with breve", 'Ă'),
+    ("latin small letter a with breve", 'ă'),
+    ("latin capital letter a with ogonek", 'Ą'),
+    ("latin small letter a with ogonek", 'ą'),
+    ("latin capital letter c with acute", 'Ć'),
+    ("latin small letter c with acute", 'ć'),
+    ("latin capital letter c with circumflex", 'Ĉ'),
+    ("latin small letter c with circumflex", 'ĉ'),
+    ("latin capital letter c with dot above", 'Ċ'),
+    ("latin small letter c with dot above", 'ċ'),
+    ("latin capital letter c with caron", 'Č'),
+    ("latin small letter c with caron", 'č'),
+    ("latin capital letter d with caron", 'Ď'),
+    ("latin small letter d with caron", 'ď'),
+    ("latin capital letter d with stroke", 'Đ'),
+    ("latin small letter d with stroke", 'đ'),
+    ("latin capital letter e with macron", 'Ē'),
+    ("latin small letter e with macron", 'ē'),
+    ("latin capital letter e with breve", 'Ĕ'),
+    ("latin small letter e with breve", 'ĕ'),
+    ("latin capital letter e with dot above", 'Ė'),
+    ("latin small letter e with dot above", 'ė'),
+    ("latin capital letter e with ogonek", 'Ę'),
+    ("latin small letter e with ogonek", 'ę'),
+    ("latin capital letter e with caron", 'Ě'),
+    ("latin small letter e with caron", 'ě'),
+    ("latin capital letter g with circumflex", 'Ĝ'),
+    ("latin small letter g with circumflex", 'ĝ'),
+    ("latin capital letter g with breve", 'Ğ'),
+    ("latin small letter g with breve", 'ğ'),
+    ("latin capital letter g with dot above", 'Ġ'),
+    ("latin small letter g with dot above", 'ġ'),
+    ("latin capital letter g with cedilla", 'Ģ'),
+    ("latin small letter g with cedilla", 'ģ'),
+    ("latin capital letter h with circumflex", 'Ĥ'),
+    ("latin small letter h with circumflex", 'ĥ'),
+    ("latin capital letter h with stroke", 'Ħ'),
+    ("latin small letter h with stroke", 'ħ'),
+    ("latin capital letter i with tilde", 'Ĩ'),
+    ("latin small letter i with tilde", 'ĩ'),
+    ("latin capital letter i with macron", 'Ī'),
+    ("latin small letter i with macron", 'ī'),
+    ("latin capital letter i with breve", 'Ĭ'),
+    ("latin small letter i with breve", 'ĭ'),
+    ("latin capital letter i with ogonek", 'Į'),
+    ("latin small letter i with ogonek", 'į'),
+    ("latin capital letter i with dot above", 'İ'),
+    ("latin small letter dotless i", 'ı'),
+    ("latin capital ligature ij", 'Ĳ'),
+    ("latin small ligature ij", 'ĳ'),
+    ("latin capital letter j with circumflex", 'Ĵ'),
+    ("latin small letter j with circumflex", 'ĵ'),
+    ("latin capital letter k with cedilla", 'Ķ'),
+    ("latin small letter k with cedilla", 'ķ'),
+    ("latin small letter kra", 'ĸ'),
+    ("latin capital letter l with acute", 'Ĺ'),
+    ("latin small letter l with acute", 'ĺ'),
+    ("latin capital letter l with cedilla", 'Ļ'),
+    ("latin small letter l with cedilla", 'ļ'),
+    ("latin capital letter l with caron", 'Ľ'),
+    ("latin small letter l with caron", 'ľ'),
+    ("latin capital letter l with middle dot", 'Ŀ'),
+    ("latin small letter l with middle dot", 'ŀ'),
+    ("latin capital letter l with stroke", 'Ł'),
+    ("latin small letter l with stroke", 'ł'),
+    ("latin capital letter n with acute", 'Ń'),
+    ("latin small letter n with acute", 'ń'),
+    ("latin capital letter n with cedilla", 'Ņ'),
+    ("latin small letter n with cedilla", 'ņ'),
+    ("latin capital letter n with caron", 'Ň'),
+    ("latin small letter n with caron", 'ň'),
+    ("latin small letter n preceded by apostrophe", 'ŉ'),
+    ("latin capital letter eng", 'Ŋ'),
+    ("latin small letter eng", 'ŋ'),
+    ("latin capital letter o with macron", 'Ō'),
+    ("latin small letter o with macron", 'ō'),
+    ("latin capital letter o with breve", 'Ŏ'),
+    ("latin small letter o with breve", 'ŏ'),
+    ("latin capital letter o with double acute", 'Ő'),
+    ("latin small letter o with double acute", 'ő'),
+    ("latin capital ligature oe", 'Œ'),
+    ("latin small ligature oe", 'œ'),
+    ("latin capital letter r with acute", 'Ŕ'),
+    ("latin small letter r with acute", 'ŕ'),
+    ("latin capital letter r with cedilla", 'Ŗ'),
+    ("latin small letter r with cedilla", 'ŗ'),
+    ("latin capital letter r with caron", 'Ř'),
+    ("latin small letter r with caron", 'ř'),
+    ("latin capital letter s with acute", 'Ś'),
+    ("latin small letter s with acute", 'ś'),
+    ("latin capital letter s with circumflex", 'Ŝ'),
+    ("latin small letter s with circumflex", 'ŝ'),
+    ("latin capital letter s with cedilla", 'Ş'),
+    ("latin small letter s with cedilla", 'ş'),
+    ("latin capital letter s with caron", 'Š'),
+    ("latin small letter s with caron", 'š'),
+    ("latin capital letter t with cedilla", 'Ţ'),
+    ("latin small letter t with cedilla", 'ţ'),
+    ("latin capital letter t with caron", 'Ť'),
+    ("latin small letter t with caron", 'ť'),
+    ("latin capital letter t with stroke", 'Ŧ'),
+    ("latin small letter t with stroke", 'ŧ'),
+    ("latin capital letter u with tilde", 'Ũ'),
+    ("latin small letter u with tilde", 'ũ'),
+    ("latin capital letter u with macron", 'Ū'),
+    ("latin small letter u with macron", 'ū'),
+    ("latin capital letter u with breve", 'Ŭ'),
+    ("latin small letter u with breve", 'ŭ'),
+    ("latin capital letter u with ring above", 'Ů'),
+    ("latin small letter u with ring above", 'ů'),
+    ("latin capital letter u with double acute", 'Ű'),
+    ("latin small letter u with double acute", 'ű'),
+    ("latin capital letter u with ogonek", 'Ų'),
+    ("latin small letter u with ogonek", 'ų'),
+    ("latin capital letter w with circumflex", 'Ŵ'),
+    ("latin small letter w with circumflex", 'ŵ'),
+    ("latin capital letter y with circumflex", 'Ŷ'),
+    ("latin small letter y with circumflex", 'ŷ'),
+    ("latin capital letter y with diaeresis", 'Ÿ'),
+    ("latin capital letter z with acute", 'Ź'),
+    ("latin small letter z with acute", 'ź'),
+    ("latin capital letter z with dot above", 'Ż'),
+    ("latin small letter z with dot above", 'ż'),
+    ("latin capital letter z with caron", 'Ž'),
+    ("latin small letter z with caron", 'ž'),
+    ("latin small letter long s", 'ſ'),
+    ("greek capital letter alpha", 'Α'),
+    ("greek capital letter beta", 'Β'),
+    ("greek capital letter gamma", 'Γ'),
+    ("greek capital letter delta", 'Δ'),
+    ("greek capital letter epsilon", 'Ε'),
+    ("greek capital letter zeta", 'Ζ'),
+    ("greek capital letter eta", 'Η'),
+    ("greek capital letter theta", 'Θ'),
+    ("greek capital letter iota", 'Ι'),
+    ("greek capital letter kappa", 'Κ'),
+    ("greek capital letter lamda", 'Λ'),
+    ("greek capital letter mu", 'Μ'),
+    ("greek capital letter nu", 'Ν'),
+    ("greek capital letter xi", 'Ξ'),
+    ("greek capital letter omicron", 'Ο'),
+    ("greek capital letter pi", 'Π'),
+    ("greek capital letter rho", 'Ρ'),
+    ("greek capital letter sigma", 'Σ'),
+    ("greek capital letter tau", 'Τ'),
+    ("greek capital letter upsilon", 'Υ'),
+    ("greek capital letter phi", 'Φ'),
+    ("greek capital letter chi", 'Χ'),
+    ("greek capital letter psi", 'Ψ'),
+    ("greek capital letter omega", 'Ω'),
+    ("greek small letter alpha", 'α'),
+    ("greek small letter beta", 'β'),
+    ("greek small letter gamma", 'γ'),
+    ("greek small letter delta", 'δ'),
+    ("greek small letter epsilon", 'ε'),
+    ("greek small letter zeta", 'ζ'),
+    ("greek small letter eta", 'η'),
+    ("greek small letter theta", 'θ'),
+    ("greek small letter iota", 'ι'),
+    ("greek small letter kappa", 'κ'),
+    ("greek small letter lamda", 'λ'),
+    ("greek small letter mu", 'μ'),
+    ("greek small letter nu", 'ν'),
+    ("greek small letter xi", 'ξ'),
+    ("greek small letter omicron", 'ο'),
+    ("greek small letter pi", 'π'),
+    ("greek small letter rho", 'ρ'),
+    ("greek small letter final sigma", 'ς'),
+    ("greek small letter sigma", 'σ'),
+    ("greek small letter tau", 'τ'),
+    ("greek small letter upsilon", 'υ'),
+    ("greek small letter phi", 'φ'),
+    ("greek small letter chi", 'χ'),
+    ("greek small letter psi", 'ψ'),
+    ("greek small letter omega", 'ω'),
+    ("hyphen", '‐'),
+    ("non-breaking hyphen", '‑'),
+    ("figure dash", '‒'),
+    ("en dash", '–'),
+    ("em dash", '—'),
+    ("horizontal bar", '―'),
+    ("double vertical line", '‖'),
+    ("double low line", '‗'),
+    ("left single quotation mark", '‘'),
+    ("right single quotation mark", '’'),
+    ("single low-9 quotation mark", '‚'),
+    ("single high-reversed-9 quotation mark", '‛'),
+    ("left double quotation mark", '“'),
+    ("right double quotation mark", '”'),
+    ("double low-9 quotation mark", '„'),
+    ("double high-reversed-9 quotation mark", '‟'),
+    ("dagger", '†'),
+    ("double dagger", '‡'),
+    ("bullet", '•'),
+    ("triangular bullet", '‣'),
+    ("one dot leader", '․'),
+    ("two dot leader", '‥'),
+    ("horizontal ellipsis", '…'),
+    ("hyphenation point", '‧'),
+    ("per mille sign", '‰'),
+    ("per ten thousand sign", '‱'),
+    ("prime", '′'),
+    ("double prime", '″'),
+    ("triple prime", '‴'),
+    ("reversed prime", '‵'),
+    ("reversed double prime", '‶'),
+    ("reversed triple prime", '‷'),
+    ("caret", '‸'),
+    ("single left-pointing angle quotation mark", '‹'),
+    ("single right-pointing angle quotation mark", '›'),
+    ("reference mark", '※'),
+    ("double exclamation mark", '‼'),
+    ("interrobang", '‽'),
+    ("overline", '‾'),
+    ("undertie", '‿'),
+    ("character tie", '⁀'),
+    ("caret insertion point", '⁁'),
+    ("asterism", '⁂'),
+    ("hyphen bullet", '⁃'),
+    ("fraction slash", '⁄'),
+    ("left square bracket with quill", '⁅'),
+    ("right square bracket with quill", '⁆'),
+    ("double question mark", '⁇'),
+    ("question exclamation mark", '⁈'),
+    ("exclamation question mark", '⁉'),
+    ("tironian sign et", '⁊'),
+    ("reversed pilcrow sign", '⁋'),
+    ("black leftwards bullet", '⁌'),
+    ("black rightwards bullet", '⁍'),
+    ("low asterisk", '⁎'),
+    ("reversed semicolon", '⁏'),
+    ("close up", '⁐'),
+    ("two asterisks aligned vertically", '⁑'),
+    ("commercial minus sign", '⁒'),
+    ("swung dash", '⁓'),
+    ("inverted undertie", '⁔'),
+    ("flower punctuation mark", '⁕'),
+    ("three dot punctuation", '⁖'),
+    ("quadruple prime", '⁗'),
+    ("four dot punctuation", '⁘'),
+    ("five dot punctuation", '⁙'),
+    ("two dot punctuation", '⁚'),
+    ("four dot mark", '⁛'),
+    ("dotted cross", '⁜'),
+    ("tricolon", '⁝'),
+    ("vertical four dots", '⁞'),
+    ("euro-currency sign", '₠'),
+    ("colon sign", '₡'),
+    ("cruzeiro sign", '₢'),
+    ("french franc sign", '₣'),
+    ("lira sign", '₤'),
+    ("mill sign", '₥'),
+    ("naira sign", '₦'),
+    ("peseta sign", '₧'),
+    ("rupee sign", '₨'),
+    ("won sign", '₩'),
+    ("new sheqel sign", '₪'),
+    ("dong sign", '₫'),
+    ("euro sign", '€'),
+    ("kip sign", '₭'),
+    ("tugrik sign", '₮'),
+    ("drachma sign", '₯'),
+    ("german penny sign", '₰'),
+    ("peso sign", '₱'),
+    ("guarani sign", '₲'),
+    ("austral sign", '₳'),
+    ("hryvnia sign", '₴'),
+    ("cedi sign", '₵'),
+    ("livre tournois sign", '₶'),
+    ("spesmilo sign", '₷'),
+    ("tenge sign", '₸'),
+    ("indian rupee sign", '₹'),
+    ("turkish lira sign", '₺'),
+    ("nordic mark sign", '₻'),
+    ("manat sign", '₼'),
+    ("ruble sign", '₽'),
+    ("lari sign", '₾'),
+    ("bitcoin sign", '₿'),
+    ("leftwards arrow", '←'),
+    ("upwards arrow", '↑'),
+    ("rightwards arrow", '→'),
+    ("downwards arrow", '↓'),
+    ("left right arrow", '↔'),
+    ("up down arrow", '↕'),
+    ("north west arrow", '↖'),
+    ("north east arrow", '↗'),
+    ("south east arrow", '↘'),
+    ("south west arrow", '↙'),
+    ("leftwards arrow with stroke", '↚'),
+    ("rightwards arrow with stroke", '↛'),
+    ("leftwards wave arrow", '↜'),
+    ("rightwards wave arrow", '↝'),
+    ("leftwards two headed arrow", '↞'),
+    ("upwards two headed arrow", '↟'),
+    ("rightwards two headed arrow", '↠'),
+    ("downwards two headed arrow", '↡'),
+    ("leftwards arrow with tail", '↢'),
+    ("rightwards arrow with tail", '↣'),
+    ("leftwards arrow from bar", '↤'),
+    ("upwards arrow from bar", '↥'),
+    ("rightwards arrow from bar", '↦'),
+    ("downwards arrow from bar", '↧'),
+    ("up down arrow with base", '↨'),
+    ("leftwards arrow with hook", '↩'),
+    ("rightwards arrow with hook", '↪'),
+    ("leftwards arrow with loop", '↫'),
+    ("rightwards arrow with loop", '↬'),
+    ("left right wave arrow", '↭'),
+    ("left right arrow with stroke", '↮'),
+    ("downwards zigzag arrow", '↯'),
+    ("upwards arrow with tip leftwards", '↰'),
+    ("upwards arrow with tip rightwards", '↱'),
+    ("downwards arrow with tip leftwards", '↲'),
+    ("downwards arrow with tip rightwards", '↳'),
+    ("rightwards arrow with corner downwards", '↴'),
+    ("downwards arrow with corner leftwards", '↵'),
+    ("anticlockwise top semicircle arrow", '↶'),
+    ("clockwise top semicircle arrow", '↷'),
+    ("north west arrow to long bar", '↸'),
+    ("leftwards arrow to bar over rightwards arrow to bar", '↹'),
+    ("anticlockwise open circle arrow", '↺'),
+    ("clockwise open circle arrow", '↻'),
+    ("leftwards harpoon with barb upwards", '↼'),
+    ("leftwards harpoon with barb downwards", '↽'),
+    ("upwards harpoon with barb rightwards", '↾'),
+    ("upwards harpoon with barb leftwards", '↿'),
+    ("rightwards harpoon with barb upwards", '⇀'),
+    ("rightwards harpoon with barb downwards", '⇁'),
+    ("downwards harpoon with barb rightwards", '⇂'),
+    ("downwards harpoon with barb leftwards", '⇃'),
+    ("rightwards arrow over leftwards arrow", '⇄'),
+    ("upwards arrow leftwards of downwards arrow", '⇅'),
+    ("leftwards arrow over rightwards arrow", '⇆'),
+    ("leftwards paired arrows", '⇇'),
+    ("upwards paired arrows", '⇈'),
+    ("rightwards paired arrows", '⇉'),
+    ("downwards paired arrows", '⇊'),
+    ("leftwards harpoon over rightwards harpoon", '⇋'),
+    ("rightwards harpoon over leftwards harpoon", '⇌'),
+    ("leftwards double arrow with stroke", '⇍'),
+    ("left right double arrow with stroke", '⇎'),
+    ("rightwards double arrow with stroke", '⇏'),
+    ("leftwards double arrow", '⇐'),
+    ("upwards double arrow", '⇑'),
+    ("rightwards double arrow", '⇒'),
+    ("downwards double arrow", '⇓'),
+    ("left right double arrow", '⇔'),
+    ("up down double arrow", '⇕'),
+    ("north west double arrow", '⇖'),
+    ("north east double arrow", '⇗'),
+    ("south east double arrow", '⇘'),
+    ("south west double arrow", '⇙'),
+    ("leftwards triple arrow", '⇚'),
+    ("rightwards triple arrow", '⇛'),
+    ("leftwards squiggle arrow", '⇜'),
+    ("rightwards squiggle arrow", '⇝'),
+    ("upwards arrow with double stroke", '⇞'),
+    ("downwards arrow with double stroke", '⇟'),
+    ("leftwards dashed arrow", '⇠'),
+    ("upwards dashed arrow", '⇡'),
+    ("rightwards dashed arrow", '⇢'),
+    ("downwards dashed arrow", '⇣'),
+    ("leftwards arrow to bar", '⇤'),
+    ("rightwards arrow to bar", '⇥'),
+    ("leftwards white arrow", '⇦'),
+    ("upwards white arrow", '⇧'),
+    ("rightwards white arrow", '⇨'),
+    ("downwards white arrow", '⇩'),
+    ("upwards white arrow from bar", '⇪'),
+    ("upwards white arrow on pedestal", '⇫'),
+    ("upwards white arrow on pedestal with horizontal bar", '⇬'),
+    ("upwards white arrow on pedestal with vertical bar", '⇭'),
+    ("upwards white double arrow", '⇮'),
+    ("upwards white double arrow on pedestal", '⇯'),
+    ("rightwards white arrow from wall", '⇰'),
+    ("north west arrow to corner", '⇱'),
+    ("south east arrow to corner", '⇲'),
+    ("up down white arrow", '⇳'),
+    ("right arrow with small circle", '⇴'),
+    ("downwards arrow leftwards of upwards arrow", '⇵'),
+    ("three rightwards arrows", '⇶'),
+    ("leftwards arrow with vertical stroke", '⇷'),
+    ("rightwards arrow with vertical stroke", '⇸'),
+    ("left right arrow with vertical stroke", '⇹'),
+    ("leftwards arrow with double vertical stroke", '⇺'),
+    ("rightwards arrow with double vertical stroke", '⇻'),
+    ("left right arrow with double vertical stroke", '⇼'),
+    ("leftwards open-headed arrow", '⇽'),
+    ("rightwards open-headed arrow", '⇾'),
+    ("left right open-headed arrow", '⇿'),
+    ("for all", '∀'),
+    ("complement", '∁'),
+    ("partial differential", '∂'),
+    ("there exists", '∃'),
+    ("there does not exist", '∄'),
+    ("empty set", '∅'),
+    ("increment", '∆'),
+    ("nabla", '∇'),
+    ("element of", '∈'),
+    ("not an element of", '∉'),
+    ("small element of", '∊'),
+    ("contains as member", '∋'),
+    ("does not contain as member", '∌'),
+    ("small contains as member", '∍'),
+    ("end of proof", '∎'),
+    ("n-ary product", '∏'),
+    ("n-ary coproduct", '∐'),
+    ("n-ary summation", '∑'),
+    ("minus sign", '−'),
+    ("minus-or-plus sign", '∓'),
+    ("dot plus", '∔'),
+    ("division slash", '∕'),
+    ("set minus", '∖'),
+    ("asterisk operator", '∗'),
+    ("ring operator", '∘'),
+    ("bullet operator", '∙'),
+    ("square root", '√'),
+    ("cube root", '∛'),
+    ("fourth root", '∜'),
+    ("proportional to", '∝'),
+    ("infinity", '∞'),
+    ("right angle", '∟'),
+    ("angle", '∠'),
+    ("measured angle", '∡'),
+    ("spherical angle", '∢'),
+    ("divides", '∣'),
+    ("does not divide", '∤'),
+    ("parallel to", '∥'),
+    ("not parallel to", '∦'),
+    ("logical and", '∧'),
+    ("logical or", '∨'),
+    ("intersection", '∩'),
+    ("union", '∪'),
+    ("integral", '∫'),
+    ("double integral", '∬'),
+    ("triple integral", '∭'),
+    ("contour integral", '∮'),
+    ("surface integral", '∯'),
+    ("volume integral", '∰'),
+    ("clockwise integral", '∱'),
+    ("clockwise contour integral", '∲'),
+    ("anticlockwise contour integral", '∳'),
+    ("therefore", '∴'),
+    ("because", '∵'),
+    ("ratio", '∶'),
+    ("proportion", '∷'),
+    ("dot minus", '∸'),
+    ("excess", '∹'),
+    ("geometric proportion", '∺'),
+    ("homothetic", '∻'),
+    ("tilde operator", '∼'),
+    ("reversed tilde", '∽'),
+    ("inverted lazy s", '∾'),
+    ("sine wave", '∿'),
+    ("wreath product", '≀'),
+    ("not tilde", '≁'),
+    ("minus tilde", '≂'),
+    ("asymptotically equal to", '≃'),
+    ("not asymptotically equal to", '≄'),
+    ("approximately equal to", '≅'),
+    ("approximately but not actually equal to", '≆'),
+    ("neither approximately nor actually equal to", '≇'),
+    ("almost equal to", '≈'),
+    ("not almost equal to", '≉'),
+    ("almost equal or equal to", '≊'),
+    ("triple tilde", '≋'),
+    ("all equal to", '≌'),
+    ("equivalent to", '≍'),
+    ("geometrically equivalent to", '≎'),
+    ("difference between", '≏'),
+    ("approaches the limit", '≐'),
+    ("geometrically equal to", '≑'),
+    ("approximately equal to or the image of", '≒'),
+    ("image of or approximately equal to", '≓'),
+    ("colon equals", '≔'),
+    ("equals colon", '≕'),
+    ("ring in equal to", '≖'),
+    ("ring equal to", '≗'),
+    ("corresponds to", '≘'),
+    ("estimates", '≙'),
+    ("equiangular to", '≚'),
+    ("star equals", '≛'),
+    ("delta equal to", '≜'),
+    ("equal to by definition", '≝'),
+    ("measured by", '≞'),
+    ("questioned equal to", '≟'),
+    ("not equal to", '≠'),
+    ("identical to", '≡'),
+    ("not identical to", '≢'),
+    ("strictly equivalent to", '≣'),
+    ("less-than or equal to", '≤'),
+    ("greater-than or equal to", '≥'),
+    ("less-than over equal to", '≦'),
+    ("greater-than over equal to", '≧'),
+    ("less-than but not equal to", '≨'),
+    ("greater-than but not equal to", '≩'),
+    ("much less-than", '≪'),
+    ("much greater-than", '≫'),
+    ("between", '≬'),
+    ("not equivalent to", '≭'),
+    ("not less-than", '≮'),
+    ("not greater-than", '≯'),
+    ("neither less-than nor equal to", '≰'),
+    ("neither greater-than nor equal to", '≱'),
+    ("less-than or equivalent to", '≲'),
+    ("greater-than or equivalent to", '≳'),
+    ("neither less-than nor equivalent to", '≴'),
+    ("neither greater-than nor equivalent to", '≵'),
+    ("less-than or greater-than", '≶'),
+    ("greater-than or less-than", '≷'),
+    ("neither less-than nor greater-than", '≸'),
+    ("neither greater-than nor less-than", '≹'),
+    ("precedes", '≺'),
+    ("succeeds", '≻'),
+    ("precedes or equal to", '≼'),
+    ("succeeds or equal to", '≽'),
+    ("precedes or equivalent to", '≾'),
+    ("succeeds or equivalent to", '≿'),
+    ("does not precede", '⊀'),
+    ("does not succeed", '⊁'),
+    ("subset of", '⊂'),
+    ("superset of", '⊃'),
+    ("not a subset of", '⊄'),
+    ("not a superset of", '⊅'),
+    ("subset of or equal to", '⊆'),
+    ("superset of or equal to", '⊇'),
+    ("neither a subset of nor equal to", '⊈'),
+    ("neither a superset of nor equal to", '⊉'),
+    ("subset of with not equal to", '⊊'),
+    ("superset of with not equal to", '⊋'),
+    ("multiset", '⊌'),
+    ("multiset multiplication", '⊍'),
+    ("multiset union", '⊎'),
+    ("square image of", '⊏'),
+    ("square original of", '⊐'),
+    ("square image of or equal to", '⊑'),
+    ("square original of or equal to", '⊒'),
+    ("square cap", '⊓'),
+    ("square cup", '⊔'),
+    ("circled plus", '⊕'),
+    ("circled minus", '⊖'),
+    ("circled times", '⊗'),
+    ("circled division slash", '⊘'),
+    ("circled dot operator", '⊙'),
+    ("circled ring operator", '⊚'),
+    ("circled asterisk operator", '⊛'),
+    ("circled equals", '⊜'),
+    ("circled dash", '⊝'),
+    ("squared plus", '⊞'),
+    ("squared minus", '⊟'),
+    ("squared times", '⊠'),
+    ("squared dot operator", '⊡'),
+    ("right tack", '⊢'),
+    ("left tack", '⊣'),
+    ("down tack", '⊤'),
+    ("up tack", '⊥'),
+    ("assertion", '⊦'),
+    ("models", '⊧'),
+    ("true", '⊨'),
+    ("forces", '⊩'),
+    ("triple vertical bar right turnstile", '⊪'),
+    ("double vertical bar double right turnstile", '⊫'),
+    ("does not prove", '⊬'),
+    ("not true", '⊭'),
+    ("does not force", '⊮'),
+    ("negated double vertical bar double right turnstile", '⊯'),
+    ("precedes under relation", '⊰'),
+    ("succeeds under relation", '⊱'),
+    ("normal subgroup of", '⊲'),
+    ("contains as normal subgroup", '⊳'),
+    ("normal subgroup of or equal to", '⊴'),
+    ("contains as normal subgroup or equal to", '⊵'),
+    ("original of", '⊶'),
+    ("image of", '⊷'),
+    ("multimap", '⊸'),
+    ("hermitian conjugate matrix", '⊹'),
+    ("intercalate", '⊺'),
+    ("xor", '⊻'),
+    ("nand", '⊼'),
+    ("nor", '⊽'),
+    ("right angle with arc", '⊾'),
+    ("right triangle", '⊿'),
+    ("n-ary logical and", '⋀'),
+    ("n-ary logical or", '⋁'),
+    ("n-ary intersection", '⋂'),
+    ("n-ary union", '⋃'),
+    ("diamond operator", '⋄'),
+    ("dot operator", '⋅'),
+    ("star operator", '⋆'),
+    ("division times", '⋇'),
+    ("bowtie", '⋈'),
+    ("left normal factor semidirect product", '⋉'),
+    ("right normal factor semidirect product", '⋊'),
+    ("left semidirect product", '⋋'),
+    ("right semidirect product", '⋌'),
+    ("reversed tilde equals", '⋍'),
+    ("curly logical or", '⋎'),
+    ("curly logical and", '⋏'),
+    ("double subset", '⋐'),
+    ("double superset", '⋑'),
+    ("double intersection", '⋒'),
+    ("double union", '⋓'),
+    ("pitchfork", '⋔'),
+    ("equal and parallel to", '⋕'),
+    ("less-than with dot", '⋖'),
+    ("greater-than with dot", '⋗'),
+    ("very much less-than", '⋘'),
+    ("very much greater-than", '⋙'),
+    ("less-than equal to or greater-than", '⋚'),
+    ("greater-than equal to or less-than", '⋛'),
+    ("equal to or less-than", '⋜'),
+    ("equal to or greater-than", '⋝'),
+    ("equal to or precedes", '⋞'),
+    ("equal to or succeeds", '⋟'),
+    ("does not precede or equal", '⋠'),
+    ("does not succeed or equal", '⋡'),
+    ("not square image of or equal to", '⋢'),
+    ("not square original of or equal to", '⋣'),
+    ("square image of or not equal to", '⋤'),
+    ("square original of or not equal to", '⋥'),
+    ("less-than but not equivalent to", '⋦'),
+    ("greater-than but not equivalent to", '⋧'),
+    ("precedes but not equivalent to", '⋨'),
+    ("succeeds but not equivalent to", '⋩'),
+    ("not normal subgroup of", '⋪'),
+    ("does not contain as normal subgroup", '⋫'),
+    ("not normal subgroup of or equal to", '⋬'),
+    ("does not contain as normal subgroup or equal", '⋭'),
+    ("vertical ellipsis", '⋮'),
+    ("midline horizontal ellipsis", '⋯'),
+    ("up right diagonal ellipsis", '⋰'),
+    ("down right diagonal ellipsis", '⋱'),
+    ("element of with long horizontal stroke", '⋲'),
+    (
+        "element of with vertical bar at end of horizontal stroke",
+        '⋳',
+    ),
+    (
+        "small element of with vertical bar at end of horizontal stroke",
+        '⋴',
+    ),
+    ("element of with dot above", '⋵'),
+    ("element of with overbar", '⋶'),
+    ("small element of with overbar", '⋷'),
+    ("element of with underbar", '⋸'),
+    ("element of with two horizontal strokes", '⋹'),
+    ("contains with long horizontal stroke", '⋺'),
+    (
+        "contains with vertical bar at end of horizontal stroke",
+        '⋻',
+    ),
+    (
+        "small contains with vertical bar at end of horizontal stroke",
+        '⋼',
+    ),
+    ("contains with overbar", '⋽'),
+    ("small contains with overbar", '⋾'),
+    ("z notation bag membership", '⋿'),
+    ("black square", '■'),
+    ("white square", '□'),
+    ("white square with rounded corners", '▢'),
+    ("white square containing black small square", '▣'),
+    ("square with horizontal fill", '▤'),
+    ("square with vertical fill", '▥'),
+    ("square with orthogonal crosshatch fill", '▦'),
+    ("square with upper left to lower right fill", '▧'),
+    ("square with upper right to lower left fill", '▨'),
+    ("square with diagonal crosshatch fill", '▩'),
+    ("black small square", '▪'),
+    ("white small square", '▫'),
+    ("black rectangle", '▬'),
+    ("white rectangle", '▭'),
+    ("black vertical rectangle", '▮'),
+    ("white vertical rectangle", '▯'),
+    ("black parallelogram", '▰'),
+    ("white parallelogram", '▱'),
+    ("black up-pointing triangle", '▲'),
+    ("white up-pointing triangle", '△'),
+    ("black up-pointing small triangle", '▴'),
+    ("white up-pointing small triangle", '▵'),
+    ("black right-pointing triangle", '▶'),
+    ("white right-pointing triangle", '▷'),
+    ("black right-pointing small triangle", '▸'),
+    ("white right-pointing small triangle", '▹'),
+    ("black right-pointing pointer", '►'),
+    ("white right-pointing pointer", '▻'),
+    ("black down-pointing triangle", '▼'),
+    ("white down-pointing triangle", '▽'),
+    ("black down-pointing small triangle", '▾'),
+    ("white down-pointing small triangle", '▿'),
+    ("black left-pointing triangle", '◀'),
+    ("white left-pointing triangle", '◁'),
+    ("black left-pointing small triangle", '◂'),
+    ("white left-pointing small triangle", '◃'),
+    ("black left-pointing pointer", '◄'),
+    ("white left-pointing pointer", '◅'),
+    ("black diamond", '◆'),
+    ("white diamond", '◇'),
+    ("white diamond containing black small diamond", '◈'),
+    ("fisheye", '◉'),
+    ("lozenge", '◊'),
+    ("white circle", '○'),
+    ("dotted circle", '◌'),
+    ("circle with vertical fill", '◍'),
+    ("bullseye", '◎'),
+    ("black circle", '●'),
+    ("circle with left half black", '◐'),
+    ("circle with right half black", '◑'),
+    ("circle with lower half black", '◒'),
+    ("circle with upper half black", '◓'),
+    ("circle with upper right quadrant black", '◔'),
+    ("circle with all but upper left quadrant black", '◕'),
+    ("left half black circle", '◖'),
+    ("right half black circle", '◗'),
+    ("inverse bullet", '◘'),
+    ("inverse white circle", '◙'),
+    ("upper half inverse white circle", '◚'),
+    ("lower half inverse white circle", '◛'),
+    ("upper left quadrant circular arc", '◜'),
+    ("upper right quadrant circular arc", '◝'),
+    ("lower right quadrant circular arc", '◞'),
+    ("lower left quadrant circular arc", '◟'),
+    ("upper half circle", '◠'),
+    ("lower half circle", '◡'),
+    ("black lower right triangle", '◢'),
+    ("black lower left triangle", '◣'),
+    ("black upper left triangle", '◤'),
+    ("black upper right triangle", '◥'),
+    ("white bullet", '◦'),
+    ("square with left half black", '◧'),
+    ("square with right half black", '◨'),
+    ("square with upper left diagonal half black", '◩'),
+    ("square with lower right diagonal half black", '◪'),
+    ("white square with vertical bisecting line", '◫'),
+    ("white up-pointing triangle with dot", '◬'),
+    ("up-pointing triangle with left half black", '◭'),
+    ("up-pointing triangle with right half black", '◮'),
+    ("large circle", '◯'),
+    ("white square with upper left quadrant", '◰'),
+    ("white square with lower left quadrant", '◱'),
+    ("white square with lower right quadrant", '◲'),
+    ("white square with upper right quadrant", '◳'),
+    ("white circle with upper left quadrant", '◴'),
+    ("white circle with lower left quadrant", '◵'),
+    ("white circle with lower right quadrant", '◶'),
+    ("white circle with upper right quadrant", '◷'),
+    ("upper left triangle", '◸'),
+    ("upper right triangle", '◹'),
+    ("lower left triangle", '◺'),
+    ("white medium square", '◻'),
+    ("black medium square", '◼'),
+    ("white medium small square", '◽'),
+    ("black medium small square", '◾'),
+    ("lower right triangle", '◿'),
+];
+
+const WIDE: &[(char, char)] = &[
+    ('\u{1100}', '\u{115f}'),
+    ('\u{231a}', '\u{231b}'),
+    ('\u{2329}', '\u{232a}'),
+    ('\u{23e9}', '\u{23ec}'),
+    ('\u{23f0}', '\u{23f0}'),
+    ('\u{23f3}', '\u{23f3}'),
+    ('\u{25fd}', '\u{25fe}'),
+    ('\u{2614}', '\u{2615}'),
+    ('\u{2648}', '\u{2653}'),
+    ('\u{267f}', '\u{267f}'),
+    ('\u{2693}', '\u{2693}'),
+    ('\u{26a1}', '\u{26a1}'),
+    ('\u{26aa}', '\u{26ab}'),
+    ('\u{26bd}', '\u{26be}'),
+    ('\u{26c4}', '\u{26c5}'),
+    ('\u{26ce}', '\u{26ce}'),
+    ('\u{26d4}', '\u{26d4}'),
+    ('\u{26ea}', '\u{26ea}'),
+    ('\u{26f2}', '\u{26f3}'),
+    ('\u{26f5}', '\u{26f5}'),
+    ('\u{26fa}', '\u{26fa}'),
+    ('\u{26fd}', '\u{26fd}'),
+    ('\u{2705}', '\u{2705}'),
+    ('\u{270a}', '\u{270b}'),
+    ('\u{2728}', '\u{2728}'),
+    ('\u{274c}', '\u{274c}'),
+    ('\u{274e}', '\u{274e}'),
+    ('\u{2753}', '\u{2755}'),
+    ('\u{2757}', '\u{2757}'),
+    ('\u{2795}', '\u{2797}'),
+    ('\u{27b0}', '\u{27b0}'),
+    ('\u{27bf}', '\u{27bf}'),
+    ('\u{2b1b}', '\u{2b1c}'),
+    ('\u{2b50}', '\u{2b50}'),
+    ('\u{2b55}', '\u{2b55}'),
+    ('\u{2e80}', '\u{303e}'),
+    ('\u{3041}', '\u{3247}'),
+    ('\u{3250}', '\u{4dbf}'),
+    ('\u{4e00}', '\u{a4c6}'),
+    ('\u{a960}', '\u{a97c}'),
+    ('\u{ac00}', '\u{d7a3}'),
+    ('\u{f900}', '\u{fad9}'),
+    ('\u{fe10}', '\u{fe19}'),
+    ('\u{fe30}', '\u{fe6b}'),
+    ('\u{ff01}', '\u{ff60}'),
+    ('\u{ffe0}', '\u{ffe6}'),
+    ('\u{16fe0}', '\u{1b2fb}'),
+    ('\u{1f004}', '\u{1f004}'),
+    ('\u{1f0cf}', '\u{1f0cf}'),
+    ('\u{1f18e}', '\u{1f18e}'),
+    ('\u{1f191}', '\u{1f19a}'),
+    ('\u{1f200}', '\u{1f320}'),
+    ('\u{1f32d}', '\u{1f335}'),
+    ('\u{1f337}', '\u{1f37c}'),
+    ('\u{1f37e}', '\u{1f393}'),
+    ('\u{1f3a0}', '\u{1f3ca}'),
+    ('\u{1f3cf}', '\u{1f3d3}'),
+    ('\u{1f3e0}', '\u{1f3f0}'),
+    ('\u{1f3f4}', '\u{1f3f4}'),
+    ('\u{1f3f8}', '\u{1f43e}'),
+    ('\u{1f440}', '\u{1f440}'),
+    ('\u{1f442}', '\u{1f4fc}'),
+    ('\u{1f4ff}', '\u{1f53d}'),
+    ('\u{1f54b}', '\u{1f54e}'),
+    ('\u{1f550}', '\u{1f567}'),
+    ('\u{1f57a}', '\u{1f57a}'),
+    ('\u{1f595}', '\u{1f596}'),
+    ('\u{1f5a4}', '\u{1f5a4}'),
+    ('\u{1f5fb}', '\u{1f64f}'),
+    ('\u{1f680}', '\u{1f6c5}'),
+    ('\u{1f6cc}', '\u{1f6cc}'),
+    ('\u{1f6d0}', '\u{1f6d2}'),
+    ('\u{1f6d5}', '\u{1f6df}'),
+    ('\u{1f6eb}', '\u{1f6ec}'),
+    ('\u{1f6f4}', '\u{1f6fc}'),
+    ('\u{1f7e0}', '\u{1f7f0}'),
+    ('\u{1f90c}', '\u{1f93a}'),
+    ('\u{1f93c}', '\u{1f945}'),
+    ('\u{1f947}', '\u{1f9ff}'),
+    ('\u{1fa70}', '\u{1faf6}'),
+    ('\u{20000}', '\u{3134a}'),
+];
+
+const ZERO_WIDTH: &[(char, char)] = &[
+    ('\u{300}', '\u{36f}'),
+    ('\u{483}', '\u{489}'),
+    ('\u{591}', '\u{5bd}'),
+    ('\u{5bf}', '\u{5bf}'),
+    ('\u{5c1}', '\u{5c2}'),
+    ('\u{5c4}', '\u{5c5}'),
+    ('\u{5c7}', '\u{5c7}'),
+    ('\u{610}', '\u{61a}'),
+    ('\u{64b}', '\u{65f}'),
+    ('\u{670}', '\u{670}'),
+    ('\u{6d6}', '\u{6dc}'),
+    ('\u{6df}', '\u{6e4}'),
+    ('\u{6e7}', '\u{6e8}'),
+    ('\u{6ea}', '\u{6ed}'),
+    ('\u{711}', '\u{711}'),
+    ('\u{730}', '\u{74a}'),
+    ('\u{7a6}', '\u{7b0}'),
+    ('\u{7eb}', '\u{7f3}'),
+    ('\u{7fd}', '\u{7fd}'),
+    ('\u{816}', '\u{819}'),
+    ('\u{81b}', '\u{823}'),
+    ('\u{825}', '\u{827}'),
+    ('\u{829}', '\u{82d}'),
+    ('\u{859}', '\u{85b}'),
+    ('\u{898}', '\u{89f}'),
+    ('\u{8ca}', '\u{8e1}'),
+    ('\u{8e3}', '\u{902}'),
+    ('\u{93a}', '\u{93a}'),
+    ('\u{93c}', '\u{93c}'),
+    ('\u{941}', '\u{948}'),
+    ('\u{94d}', '\u{94d}'),
+    ('\u{951}', '\u{957}'),
+    ('\u{962}', '\u{963}'),
+    ('\u{981}', '\u{981}'),
+    ('\u{9bc}', '\u{9bc}'),
+    ('\u{9c1}', '\u{9c4}'),
+    ('\u{9cd}', '\u{9cd}'),
+    ('\u{9e2}', '\u{9e3}'),
+    ('\u{9fe}', '\u{a02}'),
+    ('\u{a3c}', '\u{a3c}'),
+    ('\u{a41}', '\u{a51}'),
+    ('\u{a70}', '\u{a71}'),
+    ('\u{a75}', '\u{a75}'),
+    ('\u{a81}', '\u{a82}'),
+    ('\u{abc}', '\u{abc}'),
+    ('\u{ac1}', '\u{ac8}'),
+    ('\u{acd}', '\u{acd}'),
+    ('\u{ae2}', '\u{ae3}'),
+    ('\u{afa}', '\u{b01}'),
+    ('\u{b3c}', '\u{b3c}'),
+    ('\u{b3f}', '\u{b3f}'),
+    ('\u{b41}', '\u{b44}'),
+    ('\u{b4d}', '\u{b56}'),
+    ('\u{b62}', '\u{b63}'),
+    ('\u{b82}', '\u{b82}'),
+    ('\u{bc0}', '\u{bc0}'),
+    ('\u{bcd}', '\u{bcd}'),
+    ('\u{c00}', '\u{c00}'),
+    ('\u{c04}', '\u{c04}'),
+    ('\u{c3c}', '\u{c3c}'),
+    ('\u{c3e}', '\u{c40}'),
+    ('\u{c46}', '\u{c56}'),
+    ('\u{c62}', '\u{c63}'),
+    ('\u{c81}', '\u{c81}'),
+    ('\u{cbc}', '\u{cbc}'),
+    ('\u{cbf}', '\u{cbf}'),
+    ('\u{cc6}', '\u{cc6}'),
+    ('\u{ccc}', '\u{ccd}'),
+    ('\u{ce2}', '\u{ce3}'),
+    ('\u{d00}', '\u{d01}'),
+    ('\u{d3b}', '\u{d3c}'),
+    ('\u{d41}', '\u{d44}'),
+    ('\u{d4d}', '\u{d4d}'),
+    ('\u{d62}', '\u{d63}'),
+    ('\u{d81}', '\u{d81}'),
+    ('\u{dca}', '\u{dca}'),
+    ('\u{dd2}', '\u{dd6}'),
+    ('\u{e31}', '\u{e31}'),
+    ('\u{e34}', '\u{e3a}'),
+    ('\u{e47}', '\u{e4e}'),
+    ('\u{eb1}', '\u{eb1}'),
+    ('\u{eb4}', '\u{ebc}'),
+    ('\u{ec8}', '\u{ecd}'),
+    ('\u{f18}', '\u{f19}'),
+    ('\u{f35}', '\u{f35}'),
+    ('\u{f37}', '\u{f37}'),
+    ('\u{f39}', '\u{f39}'),
+    ('\u{f71}', '\u{f7e}'),
+    ('\u{f80}', '\u{f84}'),
+    ('\u{f86}', '\u{f87}'),
+    ('\u{f8d}', '\u{fbc}'),
+    ('\u{fc6}', '\u{fc6}'),
+    ('\u{102d}', '\u{1030}'),
+    ('\u{1032}', '\u{1037}'),
+    ('\u{1039}', '\u{103a}'),
+    ('\u{103d}', '\u{103e}'),
+    ('\u{1058}', '\u{1059}'),
+    ('\u{105e}', '\u{1060}'),
+    ('\u{1071}', '\u{1074}'),
+    ('\u{1082}', '\u{1082}'),
+    ('\u{1085}', '\u{1086}'),
+    ('\u{108d}', '\u{108d}'),
+    ('\u{109d}', '\u{109d}'),
+    ('\u{135d}', '\u{135f}'),
+    ('\u{1712}', '\u{1714}'),
+    ('\u{1732}', '\u{1733}'),
+    ('\u{1752}', '\u{1753}'),
+    ('\u{1772}', '\u{1773}'),
+    ('\u{17b4}', '\u{17b5}'),
+    ('\u{17b7}', '\u{17bd}'),
+    ('\u{17c6}', '\u{17c6}'),
+    ('\u{17c9}', '\u{17d3}'),
+    ('\u{17dd}', '\u{17dd}'),
+    ('\u{180b}', '\u{180d}'),
+    ('\u{180f}', '\u{180f}'),
+    ('\u{1885}', '\u{1886}'),
+    ('\u{18a9}', '\u{18a9}'),
+    ('\u{1920}', '\u{1922}'),
+    ('\u{1927}', '\u{1928}'),
+    ('\u{1932}', '\u{1932}'),
+    ('\u{1939}', '\u{193b}'),
+    ('\u{1a17}', '\u{1a18}'),
+    ('\u{1a1b}', '\u{1a1b}'),
+    ('\u{1a56}', '\u{1a56}'),
+    ('\u{1a58}', '\u{1a60}'),
+    ('\u{1a62}', '\u{1a62}'),
+    ('\u{1a65}', '\u{1a6c}'),
+    ('\u{1a73}', '\u{1a7f}'),
+    ('\u{1ab0}', '\u{1b03}'),
+    ('\u{1b34}', '\u{1b34}'),
+    ('\u{1b36}', '\u{1b3a}'),
+    ('\u{1b3c}', '\u{1b3c}'),
+    ('\u{1b42}', '\u{1b42}'),
+    ('\u{1b6b}', '\u{1b73}'),
+    ('\u{1b80}', '\u{1b81}'),
+    ('\u{1ba2}', '\u{1ba5}'),
+    ('\u{1ba8}', '\u{1ba9}'),
+    ('\u{1bab}', '\u{1bad}'),
+    ('\u{1be6}', '\u{1be6}'),
+    ('\u{1be8}', '\u{1be9}'),
+    ('\u{1bed}', '\u{1bed}'),
+    ('\u{1bef}', '\u{1bf1}'),
+    ('\u{1c2c}', '\u{1c33}'),
+    ('\u{1c36}', '\u{1c37}'),
+    ('\u{1cd0}', '\u{1cd2}'),
+    ('\u{1cd4}', '\u{1ce0}'),
+    ('\u{1ce2}', '\u{1ce8}'),
+    ('\u{1ced}', '\u{1ced}'),
+    ('\u{1cf4}', '\u{1cf4}'),
+    ('\u{1cf8}', '\u{1cf9}'),
+    ('\u{1dc0}', '\u{1dff}'),
+    ('\u{200b}', '\u{200d}'),
+    ('\u{20d0}', '\u{20f0}'),
+    ('\u{2cef}', '\u{2cf1}'),
+    ('\u{2d7f}', '\u{2d7f}'),
+    ('\u{2de0}', '\u{2dff}'),
+    ('\u{302a}', '\u{302d}'),
+    ('\u{3099}', '\u{309a}'),
+    ('\u{a66f}', '\u{a672}'),
+    ('\u{a674}', '\u{a67d}'),
+    ('\u{a69e}', '\u{a69f}'),
+    ('\u{a6f0}', '\u{a6f1}'),
+    ('\u{a802}', '\u{a802}'),
+    ('\u{a806}', '\u{a806}'),
+    ('\u{a80b}', '\u{a80b}'),
+    ('\u{a825}', '\u{a826}'),
+    ('\u{a82c}', '\u{a82c}'),
+    ('\u{a8c4}', '\u{a8c5}'),
+    ('\u{a8e0}', '\u{a8f1}'),
+    ('\u{a8ff}', '\u{a8ff}'),
+    ('\u{a926}', '\u{a92d}'),
+    ('\u{a947}', '\u{a951}'),
+    ('\u{a980}', '\u{a982}'),
+    ('\u{a9b3}', '\u{a9b3}'),
+    ('\u{a9b6}', '\u{a9b9}'),
+    ('\u{a9bc}', '\u{a9bd}'),
+    ('\u{a9e5}', '\u{a9e5}'),
+    ('\u{aa29}', '\u{aa2e}'),
+    ('\u{aa31}', '\u{aa32}'),
+    ('\u{aa35}', '\u{aa36}'),
+    ('\u{aa43}', '\u{aa43}'),
+    ('\u{aa4c}', '\u{aa4c}'),
+    ('\u{aa7c}', '\u{aa7c}'),
+    ('\u{aab0}', '\u{aab0}'),
+    ('\u{aab2}', '\u{aab4}'),
+    ('\u{aab7}', '\u{aab8}'),
+    ('\u{aabe}', '\u{aabf}'),
+    ('\u{aac1}', '\u{aac1}'),
+    ('\u{aaec}', '\u{aaed}'),
+    ('\u{aaf6}', '\u{aaf6}'),
+    ('\u{abe5}', '\u{abe5}'),
+    ('\u{abe8}', '\u{abe8}'),
+    ('\u{abed}', '\u{abed}'),
+    ('\u{fb1e}', '\u{fb1e}'),
+    ('\u{fe00}', '\u{fe0f}'),
+    ('\u{fe20}', '\u{fe2f}'),
+    ('\u{feff}', '\u{feff}'),
+    ('\u{101fd}', '\u{101fd}'),
+    ('\u{102e0}', '\u{102e0}'),
+    ('\u{10376}', '\u{1037a}'),
+    ('\u{10a01}', '\u{10a0f}'),
+    ('\u{10a38}', '\u{10a3f}'),
+    ('\u{10ae5}', '\u{10ae6}'),
+    ('\u{10d24}', '\u{10d27}'),
+    ('\u{10eab}', '\u{10eac}'),
+    ('\u{10f46}', '\u{10f50}'),
+    ('\u{10f82}', '\u{10f85}'),
+    ('\u{11001}', '\u{11001}'),
+    ('\u{11038}', '\u{11046}'),
+    ('\u{11070}', '\u{11070}'),
+    ('\u{11073}', '\u{11074}'),
+    ('\u{1107f}', '\u{11081}'),
+    ('\u{110b3}', '\u{110b6}'),
+    ('\u{110b9}', '\u{110ba}'),
+    ('\u{110c2}', '\u{110c2}'),
+    ('\u{11100}', '\u{11102}'),
+    ('\u{11127}', '\u{1112b}'),
+    ('\u{1112d}', '\u{11134}'),
+    ('\u{11173}', '\u{11173}'),
+    ('\u{11180}', '\u{11181}'),
+    ('\u{111b6}', '\u{111be}'),
+    ('\u{111c9}', '\u{111cc}'),
+    ('\u{111cf}', '\u{111cf}'),
+    ('\u{1122f}', '\u{11231}'),
+    ('\u{11234}', '\u{11234}'),
+    ('\u{11236}', '\u{11237}'),
+    ('\u{1123e}', '\u{1123e}'),
+    ('\u{112df}', '\u{112df}'),
+    ('\u{112e3}', '\u{112ea}'),
+    ('\u{11300}', '\u{11301}'),
+    ('\u{1133b}', '\u{1133c}'),
+    ('\u{11340}', '\u{11340}'),
+    ('\u{11366}', '\u{11374}'),
+    ('\u{11438}', '\u{1143f}'),
+    ('\u{11442}', '\u{11444}'),
+    ('\u{11446}', '\u{11446}'),
+    ('\u{1145e}', '\u{1145e}'),
+    ('\u{114b3}', '\u{114b8}'),
+    ('\u{114ba}', '\u{114ba}'),
+    ('\u{114bf}', '\u{114c0}'),
+    ('\u{114c2}', '\u{114c3}'),
+    ('\u{115b2}', '\u{115b5}'),
+    ('\u{115bc}', '\u{115bd}'),
+    ('\u{115bf}', '\u{115c0}'),
+    ('\u{115dc}', '\u{115dd}'),
+    ('\u{11633}', '\u{1163a}'),
+    ('\u{1163d}', '\u{1163d}'),
+    ('\u{1163f}', '\u{11640}'),
+    ('\u{116ab}', '\u{116ab}'),
+    ('\u{116ad}', '\u{116ad}'),
+    ('\u{116b0}', '\u{116b5}'),
+    ('\u{116b7}', '\u{116b7}'),
+    ('\u{1171d}', '\u{1171f}'),
+    ('\u{11722}', '\u{11725}'),
+    ('\u{11727}', '\u{1172b}'),
+    ('\u{1182f}', '\u{11837}'),
+    ('\u{11839}', '\u{1183a}'),
+    ('\u{1193b}', '\u{1193c}'),
+    ('\u{1193e}', '\u{1193e}'),
+    ('\u{11943}', '\u{11943}'),
+    ('\u{119d4}', '\u{119db}'),
+    ('\u{119e0}', '\u{119e0}'),
+    ('\u{11a01}', '\u{11a0a}'),
+    ('\u{11a33}', '\u{11a38}'),
+    ('\u{11a3b}', '\u{11a3e}'),
+    ('\u{11a47}', '\u{11a47}'),
+    ('\u{11a51}', '\u{11a56}'),
+    ('\u{11a59}', '\u{11a5b}'),
+    ('\u{11a8a}', '\u{11a96}'),
+    ('\u{11a98}', '\u{11a99}'),
+    ('\u{11c30}', '\u{11c3d}'),
+    ('\u{11c3f}', '\u{11c3f}'),
+    ('\u{11c92}', '\u{11ca7}'),
+    ('\u{11caa}', '\u{11cb0}'),
+    ('\u{11cb2}', '\u{11cb3}'),
+    ('\u{11cb5}', '\u{11cb6}'),
+    ('\u{11d31}', '\u{11d45}'),
+    ('\u{11d47}', '\u{11d47}'),
+    ('\u{11d90}', '\u{11d91}'),
+    ('\u{11d95}', '\u{11d95}'),
+    ('\u{11d97}', '\u{11d97}'),
+    ('\u{11ef3}', '\u{11ef4}'),
+    ('\u{16af0}', '\u{16af4}'),
+    ('\u{16b30}', '\u{16b36}'),
+    ('\u{16f4f}', '\u{16f4f}'),
+    ('\u{16f8f}', '\u{16f92}'),
+    ('\u{16fe4}', '\u{16fe4}'),
+    ('\u{1bc9d}', '\u{1bc9e}'),
+    ('\u{1cf00}', '\u{1cf46}'),
+    ('\u{1d167}', '\u{1d169}'),
+    ('\u{1d17b}', '\u{1d182}'),
+    ('\u{1d185}', '\u{1d18b}'),
+    ('\u{1d1aa}', '\u{1d1ad}'),
+    ('\u{1d242}', '\u{1d244}'),
+    ('\u{1da00}', '\u{1da36}'),
+    ('\u{1da3b}', '\u{1da6c}'),
+    ('\u{1da75}', '\u{1da75}'),
+    ('\u{1da84}', '\u{1da84}'),
+    ('\u{1da9b}', '\u{1daaf}'),
+    ('\u{1e000}', '\u{1e02a}'),
+    ('\u{1e130}', '\u{1e136}'),
+    ('\u{1e2ae}', '\u{1e2ae}'),
+    ('\u{1e2ec}', '\u{1e2ef}'),
+    ('\u{1e8d0}', '\u{1e8d6}'),
+    ('\u{1e944}', '\u{1e94a}'),
+    ('\u{e0100}', '\u{e01ef}'),
+];
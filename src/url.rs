@@ -0,0 +1,81 @@
+use std::ops::Range;
+
+const SCHEMES: &[&str] = &["http://", "https://", "ftp://", "file://", "mailto:"];
+
+fn is_token_char(c: char) -> bool {
+    !c.is_whitespace() && !"<>\"'`()[]{}".contains(c)
+}
+
+/// Returns the char ranges of whitespace-separated tokens, without trailing
+/// punctuation.
+fn tokens(line: &str) -> Vec<Range<usize>> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !is_token_char(chars[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && is_token_char(chars[i]) {
+            i += 1;
+        }
+        let mut end = i;
+        while end > start && ".,;:!?".contains(chars[end - 1]) {
+            end -= 1;
+        }
+        if end > start {
+            tokens.push(start..end);
+        }
+    }
+    tokens
+}
+
+fn slice(line: &str, range: Range<usize>) -> String {
+    line.chars().skip(range.start).take(range.len()).collect()
+}
+
+pub fn is_url(text: &str) -> bool {
+    SCHEMES.iter().any(|scheme| text.starts_with(scheme))
+}
+
+/// Returns the char ranges of the URLs in `line`.
+pub fn find(line: &str) -> Vec<Range<usize>> {
+    tokens(line)
+        .into_iter()
+        .filter(|range| is_url(&slice(line, range.clone())))
+        .collect()
+}
+
+/// Returns the token containing char `column`, which may be a URL or a path.
+pub fn at(line: &str, column: usize) -> Option<String> {
+    tokens(line)
+        .into_iter()
+        .find(|range| range.contains(&column))
+        .map(|range| slice(line, range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_urls() {
+        let line = "see <https://example.com/a?b=1>, or (mailto:me@x.org). ftp:/no";
+        assert_eq!(find(line), vec![5..30, 37..52]);
+        assert_eq!(slice(line, 5..30), "https://example.com/a?b=1");
+        assert_eq!(find("é http://é.fr."), vec![2..13]);
+        assert!(find("http:/ www.example.com").is_empty());
+    }
+
+    #[test]
+    fn takes_the_token_at_a_column() {
+        let line = "open src/main.rs: now";
+        assert_eq!(at(line, 5).as_deref(), Some("src/main.rs"));
+        assert_eq!(at(line, 15).as_deref(), Some("src/main.rs"));
+        assert_eq!(at(line, 16), None);
+        assert_eq!(at(line, 4), None);
+        assert_eq!(at(line, 100), None);
+    }
+}
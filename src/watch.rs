@@ -0,0 +1,90 @@
+use crossbeam_channel::{Sender, TrySendError};
+use std::{collections::HashSet, io, path::Path, path::PathBuf, thread};
+
+/// Sends a message whenever something may have changed in the directories
+/// of watched files. Messages that arrive before the last one was received
+/// are merged into it.
+pub struct Watcher {
+    #[cfg(target_os = "linux")]
+    fd: std::os::unix::io::RawFd,
+    dirs: HashSet<PathBuf>,
+}
+
+/// Sends on `changed` without blocking, returning whether the receiver is
+/// still there.
+fn notify(changed: &Sender<()>) -> bool {
+    !matches!(changed.try_send(()), Err(TrySendError::Disconnected(())))
+}
+
+#[cfg(target_os = "linux")]
+impl Watcher {
+    pub fn new(changed: Sender<()>) -> io::Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        thread::spawn(move || {
+            let mut events = [0u8; 4096];
+            loop {
+                let read = unsafe { libc::read(fd, events.as_mut_ptr().cast(), events.len()) };
+                if read < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                if read <= 0 || !notify(&changed) {
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            fd,
+            dirs: HashSet::new(),
+        })
+    }
+
+    /// Watches the directory containing `path`, so that files replaced by
+    /// renaming over them are noticed too. Directories are never unwatched.
+    pub fn add(&mut self, path: &Path) -> io::Result<()> {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+        let dir = match path.parent() {
+            Some(dir) if !self.dirs.contains(dir) => dir,
+            _ => return Ok(()),
+        };
+        let name = CString::new(dir.as_os_str().as_bytes())?;
+        let mask = libc::IN_MODIFY | libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE;
+        if unsafe { libc::inotify_add_watch(self.fd, name.as_ptr(), mask) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        self.dirs.insert(dir.to_owned());
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+/// Without inotify, files are polled every second.
+#[cfg(not(target_os = "linux"))]
+impl Watcher {
+    pub fn new(changed: Sender<()>) -> io::Result<Self> {
+        thread::spawn(move || loop {
+            thread::sleep(std::time::Duration::from_secs(1));
+            if !notify(&changed) {
+                return;
+            }
+        });
+        Ok(Self {
+            dirs: HashSet::new(),
+        })
+    }
+
+    pub fn add(&mut self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            self.dirs.insert(dir.to_owned());
+        }
+        Ok(())
+    }
+}